@@ -0,0 +1,1672 @@
+//! Lowering of the IR into an LLVM module.
+//!
+//! Every local variable and parameter lives in an `alloca` in the entry
+//! block; expressions are emitted as loads and stores against those slots.
+
+use crate::CodegenError;
+use crate::CodegenModule;
+use crate::OptLevel;
+use crate::run_optimizations;
+use llvm_sys::LLVMAttributeFunctionIndex;
+use llvm_sys::LLVMIntPredicate;
+use llvm_sys::LLVMLinkage;
+use llvm_sys::LLVMRealPredicate;
+use llvm_sys::LLVMTypeKind;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashMap;
+use shizuku_ir::BinOp;
+use shizuku_ir::Constant;
+use shizuku_ir::Expr;
+use shizuku_ir::Function;
+use shizuku_ir::InlineHint;
+use shizuku_ir::Intrinsic;
+use shizuku_ir::Mutability;
+use shizuku_ir::Program;
+use shizuku_ir::Stmt;
+use shizuku_ir::Symbol;
+use shizuku_ir::Type;
+use shizuku_ir::UnOp;
+use std::cell::RefCell;
+use std::ffi::CString;
+
+/// Options controlling how a program is compiled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// Optimizations to run on the generated module, none by default.
+    pub opt_level: OptLevel,
+    /// Emit globals and functions sorted by name instead of in program order,
+    /// so the module does not depend on the order of the source.
+    pub deterministic_order: bool,
+}
+
+/// Generates an LLVM module for a type checked `program`.
+pub fn codegen_program(program: &Program) -> Result<CodegenModule, CodegenError> {
+    codegen_program_with_options(program, &CodegenOptions::default())
+}
+
+/// Generates an LLVM module for a type checked `program` with non-default
+/// options.
+pub fn codegen_program_with_options(
+    program: &Program,
+    options: &CodegenOptions,
+) -> Result<CodegenModule, CodegenError> {
+    let module = CodegenModule::new("shizuku_module");
+    let mut codegen = Codegen::new(&module);
+
+    let mut globals: Vec<_> = program.globals.iter().collect();
+    let mut functions: Vec<_> = program.functions.iter().collect();
+    if options.deterministic_order {
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    for (name, ty, init, mutability) in globals {
+        codegen.emit_global(name, ty, init.as_ref(), *mutability)?;
+    }
+    // Declare every function before emitting bodies so calls resolve
+    // independently of definition order.
+    for function in &functions {
+        codegen.declare_function(function);
+    }
+    // External functions stay declarations, the linker resolves them.
+    for function in functions.iter().filter(|f| !f.is_external) {
+        codegen.emit_function(function)?;
+    }
+
+    if options.opt_level > OptLevel::None {
+        run_optimizations(&module, options.opt_level)?;
+    }
+    Ok(module)
+}
+
+/// A value produced by an expression, with the IR type it was computed at.
+struct Value {
+    value: LLVMValueRef,
+    ty: Type,
+}
+
+/// A named storage location: a local `alloca` or a global.
+#[derive(Clone)]
+struct Slot {
+    ptr: LLVMValueRef,
+    ty: Type,
+}
+
+/// The jump targets of a loop whose body is being emitted.
+struct Loop {
+    label: Option<Symbol>,
+    /// Where `continue` jumps to.
+    continue_block: LLVMBasicBlockRef,
+    /// Where `break` jumps to.
+    break_block: LLVMBasicBlockRef,
+}
+
+struct Codegen {
+    context: LLVMContextRef,
+    module: LLVMModuleRef,
+    builder: LLVMBuilderRef,
+    /// Function values by name; their `Type::Function` is the callee type.
+    functions: DHashMap<Symbol, Value>,
+    globals: DHashMap<Symbol, Slot>,
+    /// Innermost scope last.
+    scopes: Vec<DHashMap<Symbol, Slot>>,
+    /// The function currently being emitted.
+    function: LLVMValueRef,
+    /// Enclosing loops, innermost last.
+    loops: Vec<Loop>,
+    /// The LLVM type of every `Type` seen so far, so each maps to one type.
+    types: RefCell<DHashMap<Type, LLVMTypeRef>>,
+}
+
+fn cstr(name: &str) -> CString {
+    CString::new(name).expect("symbol contains a NUL byte")
+}
+
+/// Struct fields in declaration order, which is also their layout order.
+fn struct_fields(ty: &Type) -> &[(Symbol, Type)] {
+    match ty {
+        Type::Struct(fields) => fields,
+        _ => &[],
+    }
+}
+
+impl Codegen {
+    fn new(module: &CodegenModule) -> Self {
+        let context = module.context();
+        Self {
+            context,
+            module: module.module(),
+            builder: unsafe { LLVMCreateBuilderInContext(context) },
+            functions: dmap::new(),
+            globals: dmap::new(),
+            scopes: Vec::new(),
+            function: std::ptr::null_mut(),
+            loops: Vec::new(),
+            types: RefCell::new(dmap::new()),
+        }
+    }
+
+    fn llvm_type(&self, ty: &Type) -> LLVMTypeRef {
+        if let Some(&llvm_ty) = self.types.borrow().get(ty) {
+            return llvm_ty;
+        }
+        // Not borrowed across the call, which recurses into element types.
+        let llvm_ty = self.new_llvm_type(ty);
+        self.types.borrow_mut().insert(ty.clone(), llvm_ty);
+        llvm_ty
+    }
+
+    fn new_llvm_type(&self, ty: &Type) -> LLVMTypeRef {
+        unsafe {
+            match ty {
+                Type::Int => LLVMInt64TypeInContext(self.context),
+                Type::Int8 => LLVMInt8TypeInContext(self.context),
+                Type::Int16 => LLVMInt16TypeInContext(self.context),
+                Type::Int32 => LLVMInt32TypeInContext(self.context),
+                Type::Float => LLVMDoubleTypeInContext(self.context),
+                Type::Bool => LLVMInt1TypeInContext(self.context),
+                Type::String => LLVMPointerType(LLVMInt8TypeInContext(self.context), 0),
+                Type::Void => LLVMVoidTypeInContext(self.context),
+                Type::Infer => unreachable!("codegen of an uninferred type"),
+                Type::TypeVar(name) => unreachable!("codegen of type variable `{}`", name.0),
+                Type::Function(params, ret, variadic) => {
+                    LLVMPointerType(self.function_type(params, ret, *variadic), 0)
+                }
+                // `LLVMArrayType2` takes a 64-bit length but only exists from LLVM 17 on.
+                #[allow(deprecated)]
+                Type::Array(elem, size) => LLVMArrayType(self.llvm_type(elem), *size as u32),
+                Type::Struct(fields) => {
+                    let mut fields: Vec<_> =
+                        fields.iter().map(|(_, ty)| self.llvm_type(ty)).collect();
+                    LLVMStructTypeInContext(
+                        self.context,
+                        fields.as_mut_ptr(),
+                        fields.len() as u32,
+                        0,
+                    )
+                }
+            }
+        }
+    }
+
+    fn function_type(&self, params: &[Type], ret: &Type, variadic: bool) -> LLVMTypeRef {
+        // The type checker rejects `void` parameters, only returns may be `void`.
+        assert!(
+            !params.contains(&Type::Void),
+            "codegen of a `void` parameter"
+        );
+        let mut params: Vec<_> = params.iter().map(|ty| self.llvm_type(ty)).collect();
+        unsafe {
+            LLVMFunctionType(
+                self.llvm_type(ret),
+                params.as_mut_ptr(),
+                params.len() as u32,
+                variadic as i32,
+            )
+        }
+    }
+
+    fn lookup(&self, sym: &Symbol) -> Option<Slot> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(sym))
+            .or_else(|| self.globals.get(sym))
+            .cloned()
+    }
+
+    /// True if the block the builder points at already ends in a terminator.
+    fn is_terminated(&self) -> bool {
+        unsafe {
+            let block = LLVMGetInsertBlock(self.builder);
+            !LLVMGetBasicBlockTerminator(block).is_null()
+        }
+    }
+
+    fn append_block(&self, name: &str) -> LLVMBasicBlockRef {
+        let name = cstr(name);
+        unsafe { LLVMAppendBasicBlockInContext(self.context, self.function, name.as_ptr()) }
+    }
+
+    /// Allocates a stack slot at the start of the entry block.
+    fn alloca(&self, ty: &Type, name: &str) -> LLVMValueRef {
+        assert!(*ty != Type::Void, "codegen of a `void` variable `{name}`");
+        let name = cstr(name);
+        unsafe {
+            let builder = LLVMCreateBuilderInContext(self.context);
+            let entry = LLVMGetEntryBasicBlock(self.function);
+            let first = LLVMGetFirstInstruction(entry);
+            if first.is_null() {
+                LLVMPositionBuilderAtEnd(builder, entry);
+            } else {
+                LLVMPositionBuilderBefore(builder, first);
+            }
+            let ptr = LLVMBuildAlloca(builder, self.llvm_type(ty), name.as_ptr());
+            LLVMDisposeBuilder(builder);
+            ptr
+        }
+    }
+
+    fn load(&self, slot: &Slot, name: &str) -> Value {
+        let name = cstr(name);
+        let value = unsafe {
+            LLVMBuildLoad2(
+                self.builder,
+                self.llvm_type(&slot.ty),
+                slot.ptr,
+                name.as_ptr(),
+            )
+        };
+        Value {
+            value,
+            ty: slot.ty.clone(),
+        }
+    }
+
+    fn store(&self, value: &Value, slot: &Slot) {
+        unsafe {
+            LLVMBuildStore(self.builder, value.value, slot.ptr);
+        }
+    }
+
+    /// A constant usable both as an instruction operand and a global initializer.
+    fn constant(&self, constant: &Constant) -> Value {
+        let ty = constant.ty();
+        let llvm_ty = self.llvm_type(&ty);
+        let value = unsafe {
+            match constant {
+                Constant::Int(value) => LLVMConstInt(llvm_ty, *value as u64, 1),
+                Constant::Float(value) => LLVMConstReal(llvm_ty, *value),
+                Constant::Bool(value) => LLVMConstInt(llvm_ty, *value as u64, 0),
+                Constant::String(value) => {
+                    // The `2` variant takes a 64-bit length but only exists
+                    // from LLVM 19 on.
+                    #[allow(deprecated)]
+                    let bytes = LLVMConstStringInContext(
+                        self.context,
+                        value.as_ptr() as *const _,
+                        value.len() as u32,
+                        0,
+                    );
+                    let global = LLVMAddGlobal(self.module, LLVMTypeOf(bytes), c".str".as_ptr());
+                    LLVMSetInitializer(global, bytes);
+                    LLVMSetGlobalConstant(global, 1);
+                    LLVMSetLinkage(global, LLVMLinkage::LLVMPrivateLinkage);
+                    LLVMConstPointerCast(global, llvm_ty)
+                }
+            }
+        };
+        Value { value, ty }
+    }
+
+    fn emit_global(
+        &mut self,
+        name: &Symbol,
+        ty: &Type,
+        init: Option<&Constant>,
+        mutability: Mutability,
+    ) -> Result<(), CodegenError> {
+        let llvm_ty = self.llvm_type(ty);
+        let cname = cstr(&name.0);
+        unsafe {
+            let global = LLVMAddGlobal(self.module, llvm_ty, cname.as_ptr());
+            let init = match init {
+                // Type checking let an integer initializer give any integer
+                // type its value fits.
+                Some(Constant::Int(value)) => LLVMConstInt(llvm_ty, *value as u64, 1),
+                Some(constant) => self.constant(constant).value,
+                None => LLVMConstNull(llvm_ty),
+            };
+            LLVMSetInitializer(global, init);
+            LLVMSetGlobalConstant(global, (mutability == Mutability::Const) as i32);
+            self.globals.insert(
+                name.clone(),
+                Slot {
+                    ptr: global,
+                    ty: ty.clone(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn declare_function(&mut self, function: &Function) {
+        let ty = function.signature();
+        let Type::Function(params, ret, variadic) = &ty else {
+            unreachable!("signature is a function type")
+        };
+        let fn_type = self.function_type(params, ret, *variadic);
+        let name = cstr(&function.name.0);
+        let value = unsafe { LLVMAddFunction(self.module, name.as_ptr(), fn_type) };
+        let attribute = match function.inline_hint {
+            InlineHint::Default => None,
+            InlineHint::Always => Some("alwaysinline"),
+            InlineHint::Never => Some("noinline"),
+        };
+        if let Some(attribute) = attribute {
+            unsafe {
+                let kind =
+                    LLVMGetEnumAttributeKindForName(attribute.as_ptr() as _, attribute.len());
+                let attr = LLVMCreateEnumAttribute(self.context, kind, 0);
+                LLVMAddAttributeAtIndex(value, LLVMAttributeFunctionIndex, attr);
+            }
+        }
+        self.functions
+            .insert(function.name.clone(), Value { value, ty });
+    }
+
+    fn emit_function(&mut self, function: &Function) -> Result<(), CodegenError> {
+        self.function = self.functions[&function.name].value;
+        unsafe {
+            let entry = self.append_block("entry");
+            LLVMPositionBuilderAtEnd(self.builder, entry);
+        }
+
+        // Spill parameters so they can be reassigned like any other local.
+        let mut params = dmap::new();
+        for (index, (name, ty)) in function.params.iter().enumerate() {
+            let slot = Slot {
+                ptr: self.alloca(ty, &name.0),
+                ty: ty.clone(),
+            };
+            let value = unsafe { LLVMGetParam(self.function, index as u32) };
+            self.store(
+                &Value {
+                    value,
+                    ty: ty.clone(),
+                },
+                &slot,
+            );
+            params.insert(name.clone(), slot);
+        }
+        self.scopes = vec![params];
+
+        self.emit_stmt(&function.body)?;
+
+        if !self.is_terminated() {
+            unsafe {
+                if function.return_type == Type::Void {
+                    LLVMBuildRetVoid(self.builder);
+                } else {
+                    LLVMBuildUnreachable(self.builder);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_stmt(&mut self, stmt: &Stmt) -> Result<(), CodegenError> {
+        // Anything after a terminator in the same block is unreachable.
+        if self.is_terminated() {
+            return Ok(());
+        }
+
+        match stmt {
+            Stmt::Declare(sym, ty, init) => {
+                let slot = Slot {
+                    ptr: self.alloca(ty, &sym.0),
+                    ty: ty.clone(),
+                };
+                if let Some(init) = init {
+                    let value = self.emit_expr(init)?;
+                    self.store(&value, &slot);
+                }
+                self.scopes
+                    .last_mut()
+                    .expect("declaration outside of a scope")
+                    .insert(sym.clone(), slot);
+            }
+            Stmt::Assign(target, value) => {
+                let value = self.emit_expr(value)?;
+                let slot = self.address(target)?;
+                self.store(&value, &slot);
+            }
+            Stmt::Expr(expr) => {
+                self.emit_expr(expr)?;
+            }
+            Stmt::Return(value) => {
+                let value = value.as_ref().map(|v| self.emit_expr(v)).transpose()?;
+                unsafe {
+                    match value {
+                        // `return f();` in a `void` function returns nothing,
+                        // the call itself has no value to return.
+                        Some(value) if value.ty != Type::Void => {
+                            LLVMBuildRet(self.builder, value.value)
+                        }
+                        _ => {
+                            let ty = LLVMGetReturnType(LLVMGlobalGetValueType(self.function));
+                            assert!(
+                                LLVMGetTypeKind(ty) == LLVMTypeKind::LLVMVoidTypeKind,
+                                "codegen of `return;` in a non-`void` function"
+                            );
+                            LLVMBuildRetVoid(self.builder)
+                        }
+                    };
+                }
+            }
+            Stmt::Block(stmts) => {
+                self.scopes.push(dmap::new());
+                let result = stmts.iter().try_for_each(|stmt| self.emit_stmt(stmt));
+                self.scopes.pop();
+                result?;
+            }
+            Stmt::If(cond, then_branch, else_branch) => {
+                let cond = self.emit_expr(cond)?;
+                let then_block = self.append_block("then");
+                let else_block = self.append_block("else");
+                let merge_block = self.append_block("endif");
+                unsafe {
+                    LLVMBuildCondBr(self.builder, cond.value, then_block, else_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, then_block);
+                    self.emit_stmt(then_branch)?;
+                    self.branch_to(merge_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, else_block);
+                    if let Some(else_branch) = else_branch {
+                        self.emit_stmt(else_branch)?;
+                    }
+                    self.branch_to(merge_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, merge_block);
+                }
+            }
+            Stmt::While(cond, body, label) => {
+                let cond_block = self.append_block("while.cond");
+                let body_block = self.append_block("while.body");
+                let exit_block = self.append_block("while.end");
+                unsafe {
+                    LLVMBuildBr(self.builder, cond_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, cond_block);
+                    let cond = self.emit_expr(cond)?;
+                    LLVMBuildCondBr(self.builder, cond.value, body_block, exit_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, body_block);
+                    self.loops.push(Loop {
+                        label: label.clone(),
+                        continue_block: cond_block,
+                        break_block: exit_block,
+                    });
+                    let result = self.emit_stmt(body);
+                    self.loops.pop();
+                    result?;
+                    self.branch_to(cond_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, exit_block);
+                }
+            }
+            Stmt::Switch(scrutinee, arms, default) => {
+                let scrutinee = self.emit_expr(scrutinee)?;
+                let scrutinee_ty = self.llvm_type(&scrutinee.ty);
+                let default_block = self.append_block("switch.default");
+                let exit_block = self.append_block("switch.end");
+                unsafe {
+                    let switch = LLVMBuildSwitch(
+                        self.builder,
+                        scrutinee.value,
+                        default_block,
+                        arms.len() as u32,
+                    );
+                    for (value, arm) in arms {
+                        let Constant::Int(value) = value else {
+                            unreachable!("switch case is not an integer");
+                        };
+                        let case_block = self.append_block("switch.case");
+                        LLVMAddCase(
+                            switch,
+                            LLVMConstInt(scrutinee_ty, *value as u64, 1),
+                            case_block,
+                        );
+                        LLVMPositionBuilderAtEnd(self.builder, case_block);
+                        self.emit_stmt(arm)?;
+                        self.branch_to(exit_block);
+                    }
+
+                    LLVMPositionBuilderAtEnd(self.builder, default_block);
+                    if let Some(default) = default {
+                        self.emit_stmt(default)?;
+                    }
+                    self.branch_to(exit_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, exit_block);
+                }
+            }
+            Stmt::Break(label) => {
+                let target = self.jump_target(label.as_ref())?.break_block;
+                unsafe {
+                    LLVMBuildBr(self.builder, target);
+                }
+            }
+            Stmt::Continue(label) => {
+                let target = self.jump_target(label.as_ref())?.continue_block;
+                unsafe {
+                    LLVMBuildBr(self.builder, target);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The loop a `break` or `continue` with `label` leaves, the innermost
+    /// one if there is no label.
+    fn jump_target(&self, label: Option<&Symbol>) -> Result<&Loop, CodegenError> {
+        match label {
+            None => self.loops.last().ok_or(CodegenError::JumpOutsideLoop),
+            Some(label) => self
+                .loops
+                .iter()
+                .rev()
+                .find(|l| l.label.as_ref() == Some(label))
+                .ok_or_else(|| CodegenError::UnknownLabel(label.clone())),
+        }
+    }
+
+    /// Falls through to `block` unless the current block already terminated.
+    fn branch_to(&self, block: LLVMBasicBlockRef) {
+        if !self.is_terminated() {
+            unsafe {
+                LLVMBuildBr(self.builder, block);
+            }
+        }
+    }
+
+    /// Returns the storage location an l-value expression refers to.
+    fn address(&mut self, expr: &Expr) -> Result<Slot, CodegenError> {
+        match expr {
+            Expr::Var(sym) => self.lookup(sym).ok_or(CodegenError::NotAddressable),
+            Expr::ArrayAccess(array, index) => {
+                let array = self.address(array)?;
+                let Type::Array(elem, _) = &array.ty else {
+                    return Err(CodegenError::NotAddressable);
+                };
+                let index = self.emit_expr(index)?;
+                let ptr = unsafe {
+                    let zero = LLVMConstInt(LLVMInt64TypeInContext(self.context), 0, 0);
+                    let mut indices = [zero, index.value];
+                    LLVMBuildGEP2(
+                        self.builder,
+                        self.llvm_type(&array.ty),
+                        array.ptr,
+                        indices.as_mut_ptr(),
+                        indices.len() as u32,
+                        c"elem".as_ptr(),
+                    )
+                };
+                Ok(Slot {
+                    ptr,
+                    ty: (**elem).clone(),
+                })
+            }
+            Expr::FieldAccess(object, field) => {
+                let object = self.address(object)?;
+                let (index, (_, ty)) = struct_fields(&object.ty)
+                    .iter()
+                    .enumerate()
+                    .find(|(_, (name, _))| name == field)
+                    .ok_or(CodegenError::NotAddressable)?;
+                let ty = ty.clone();
+                let name = cstr(&field.0);
+                let ptr = unsafe {
+                    LLVMBuildStructGEP2(
+                        self.builder,
+                        self.llvm_type(&object.ty),
+                        object.ptr,
+                        index as u32,
+                        name.as_ptr(),
+                    )
+                };
+                Ok(Slot { ptr, ty })
+            }
+            _ => Err(CodegenError::NotAddressable),
+        }
+    }
+
+    fn emit_expr(&mut self, expr: &Expr) -> Result<Value, CodegenError> {
+        match expr {
+            Expr::Var(sym) => {
+                if let Some(slot) = self.lookup(sym) {
+                    return Ok(self.load(&slot, &sym.0));
+                }
+                match self.functions.get(sym) {
+                    Some(function) => Ok(Value {
+                        value: function.value,
+                        ty: function.ty.clone(),
+                    }),
+                    None => Err(CodegenError::UnresolvedSymbol(sym.clone())),
+                }
+            }
+            Expr::Const(constant) => Ok(self.constant(constant)),
+            Expr::BinOp(op @ (BinOp::And | BinOp::Or), lhs, rhs) => {
+                self.emit_logical(*op, lhs, rhs)
+            }
+            Expr::BinOp(op, lhs, rhs) => {
+                let lhs = self.emit_expr(lhs)?;
+                let rhs = self.emit_expr(rhs)?;
+                self.emit_binop(*op, lhs, rhs)
+            }
+            Expr::UnOp(op, operand) => {
+                let operand = self.emit_expr(operand)?;
+                self.emit_unop(*op, operand)
+            }
+            Expr::Cast(value, ty) => {
+                let value = self.emit_expr(value)?;
+                self.emit_cast(value, ty)
+            }
+            Expr::Call(sym, args) => {
+                if let Some(intrinsic) = Intrinsic::from_symbol(sym) {
+                    return self.emit_intrinsic(intrinsic, args);
+                }
+                let callee = self.emit_expr(&Expr::Var(sym.clone()))?;
+                self.emit_call(callee, args)
+            }
+            Expr::CallIndirect(callee, args) => {
+                let callee = self.emit_expr(callee)?;
+                self.emit_call(callee, args)
+            }
+            Expr::ArrayAccess(..) | Expr::FieldAccess(..) => {
+                let slot = self.address(expr)?;
+                Ok(self.load(&slot, ""))
+            }
+            Expr::If(cond, then_expr, else_expr) => {
+                let cond = self.emit_expr(cond)?;
+                let then_block = self.append_block("if.then");
+                let else_block = self.append_block("if.else");
+                let merge_block = self.append_block("if.end");
+                unsafe {
+                    LLVMBuildCondBr(self.builder, cond.value, then_block, else_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, then_block);
+                    let then_value = self.emit_expr(then_expr)?;
+                    let then_end = LLVMGetInsertBlock(self.builder);
+                    LLVMBuildBr(self.builder, merge_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, else_block);
+                    let else_value = self.emit_expr(else_expr)?;
+                    let else_end = LLVMGetInsertBlock(self.builder);
+                    LLVMBuildBr(self.builder, merge_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, merge_block);
+                    let phi = LLVMBuildPhi(
+                        self.builder,
+                        self.llvm_type(&then_value.ty),
+                        c"if.value".as_ptr(),
+                    );
+                    let mut values = [then_value.value, else_value.value];
+                    let mut blocks = [then_end, else_end];
+                    LLVMAddIncoming(phi, values.as_mut_ptr(), blocks.as_mut_ptr(), 2);
+                    Ok(Value {
+                        value: phi,
+                        ty: then_value.ty,
+                    })
+                }
+            }
+        }
+    }
+
+    fn emit_call(&mut self, callee: Value, args: &[Expr]) -> Result<Value, CodegenError> {
+        let Type::Function(params, ret, variadic) = &callee.ty else {
+            return Err(CodegenError::NotCallable(callee.ty));
+        };
+        let fn_type = self.function_type(params, ret, *variadic);
+
+        let mut args = args
+            .iter()
+            .map(|arg| self.emit_expr(arg).map(|arg| arg.value))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Void calls produce no value and must stay unnamed.
+        let name = if **ret == Type::Void { c"" } else { c"call" };
+        let value = unsafe {
+            LLVMBuildCall2(
+                self.builder,
+                fn_type,
+                callee.value,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                name.as_ptr(),
+            )
+        };
+        Ok(Value {
+            value,
+            ty: (**ret).clone(),
+        })
+    }
+
+    /// Returns the function `name` of the given type, declaring it on first use.
+    fn runtime_function(
+        &self,
+        name: &str,
+        params: &[Type],
+        ret: &Type,
+    ) -> (LLVMValueRef, LLVMTypeRef) {
+        let fn_type = self.function_type(params, ret, false);
+        let name = cstr(name);
+        unsafe {
+            let mut function = LLVMGetNamedFunction(self.module, name.as_ptr());
+            if function.is_null() {
+                function = LLVMAddFunction(self.module, name.as_ptr(), fn_type);
+            }
+            (function, fn_type)
+        }
+    }
+
+    fn call_runtime(
+        &self,
+        name: &str,
+        params: &[Type],
+        ret: &Type,
+        args: &mut [LLVMValueRef],
+    ) -> LLVMValueRef {
+        let (function, fn_type) = self.runtime_function(name, params, ret);
+        let label = if *ret == Type::Void { c"" } else { c"call" };
+        unsafe {
+            LLVMBuildCall2(
+                self.builder,
+                fn_type,
+                function,
+                args.as_mut_ptr(),
+                args.len() as u32,
+                label.as_ptr(),
+            )
+        }
+    }
+
+    /// Emits a call to a built-in function.
+    ///
+    /// A failing `assert` branches to a block calling `llvm.trap`, and `panic`
+    /// prints its message with `puts` before calling `abort`. Code following
+    /// a `panic` is emitted into an unreachable block.
+    fn emit_intrinsic(
+        &mut self,
+        intrinsic: Intrinsic,
+        args: &[Expr],
+    ) -> Result<Value, CodegenError> {
+        // Both intrinsics take a single argument, see `Intrinsic::signature`.
+        let [arg] = args else {
+            return Err(CodegenError::ArityMismatch {
+                callee: intrinsic.symbol(),
+                expected: 1,
+                found: args.len(),
+            });
+        };
+        let arg = self.emit_expr(arg)?;
+        let value = unsafe {
+            match intrinsic {
+                Intrinsic::Assert => {
+                    let fail_block = self.append_block("assert.fail");
+                    let ok_block = self.append_block("assert.ok");
+                    LLVMBuildCondBr(self.builder, arg.value, ok_block, fail_block);
+
+                    LLVMPositionBuilderAtEnd(self.builder, fail_block);
+                    let trap = self.call_runtime("llvm.trap", &[], &Type::Void, &mut []);
+                    LLVMBuildUnreachable(self.builder);
+
+                    LLVMPositionBuilderAtEnd(self.builder, ok_block);
+                    trap
+                }
+                Intrinsic::Panic => {
+                    self.call_runtime("puts", &[Type::String], &Type::Int32, &mut [arg.value]);
+                    let abort = self.call_runtime("abort", &[], &Type::Void, &mut []);
+                    LLVMBuildUnreachable(self.builder);
+
+                    let after = self.append_block("panic.after");
+                    LLVMPositionBuilderAtEnd(self.builder, after);
+                    abort
+                }
+            }
+        };
+        Ok(Value {
+            value,
+            ty: Type::Void,
+        })
+    }
+
+    fn emit_binop(&mut self, op: BinOp, lhs: Value, rhs: Value) -> Result<Value, CodegenError> {
+        if lhs.ty == Type::String {
+            return self.emit_string_binop(op, lhs, rhs);
+        }
+        let float = lhs.ty.is_float();
+        // `bool` is an `i1`, so the integer instructions apply to it as well.
+        if !float && !lhs.ty.is_integer() && lhs.ty != Type::Bool {
+            return Err(CodegenError::InvalidOperands { op, ty: lhs.ty });
+        }
+
+        let (l, r, b) = (lhs.value, rhs.value, self.builder);
+        let value = unsafe {
+            match op {
+                BinOp::Add if float => LLVMBuildFAdd(b, l, r, c"add".as_ptr()),
+                BinOp::Add => LLVMBuildAdd(b, l, r, c"add".as_ptr()),
+                BinOp::Sub if float => LLVMBuildFSub(b, l, r, c"sub".as_ptr()),
+                BinOp::Sub => LLVMBuildSub(b, l, r, c"sub".as_ptr()),
+                BinOp::Mul if float => LLVMBuildFMul(b, l, r, c"mul".as_ptr()),
+                BinOp::Mul => LLVMBuildMul(b, l, r, c"mul".as_ptr()),
+                BinOp::Div if float => LLVMBuildFDiv(b, l, r, c"div".as_ptr()),
+                BinOp::Div => LLVMBuildSDiv(b, l, r, c"div".as_ptr()),
+                BinOp::Mod if float => LLVMBuildFRem(b, l, r, c"rem".as_ptr()),
+                BinOp::Mod => LLVMBuildSRem(b, l, r, c"rem".as_ptr()),
+                BinOp::And | BinOp::Or => unreachable!("logical operators short-circuit"),
+                BinOp::Eq | BinOp::Neq | BinOp::Lt | BinOp::Gt | BinOp::Leq | BinOp::Geq => {
+                    return Ok(self.emit_compare(op, float, l, r));
+                }
+            }
+        };
+        Ok(Value { value, ty: lhs.ty })
+    }
+
+    /// Emits `+`, `==` and `!=` on strings through the C library. `+` copies
+    /// both operands into a buffer from `malloc`, which is never freed, and
+    /// the comparisons compare the contents with `strcmp`.
+    fn emit_string_binop(
+        &mut self,
+        op: BinOp,
+        lhs: Value,
+        rhs: Value,
+    ) -> Result<Value, CodegenError> {
+        let (l, r, b) = (lhs.value, rhs.value, self.builder);
+        let string = [Type::String];
+        let strings = [Type::String, Type::String];
+        unsafe {
+            match op {
+                BinOp::Add => {
+                    let l_len = self.call_runtime("strlen", &string, &Type::Int, &mut [l]);
+                    let r_len = self.call_runtime("strlen", &string, &Type::Int, &mut [r]);
+                    let len = LLVMBuildAdd(b, l_len, r_len, c"len".as_ptr());
+                    let one = LLVMConstInt(self.llvm_type(&Type::Int), 1, 0);
+                    // One more byte for the terminating NUL.
+                    let size = LLVMBuildAdd(b, len, one, c"size".as_ptr());
+                    let buf = self.call_runtime("malloc", &[Type::Int], &Type::String, &mut [size]);
+                    self.call_runtime("strcpy", &strings, &Type::String, &mut [buf, l]);
+                    self.call_runtime("strcat", &strings, &Type::String, &mut [buf, r]);
+                    Ok(Value {
+                        value: buf,
+                        ty: Type::String,
+                    })
+                }
+                BinOp::Eq | BinOp::Neq => {
+                    let order = self.call_runtime("strcmp", &strings, &Type::Int32, &mut [l, r]);
+                    let zero = LLVMConstInt(self.llvm_type(&Type::Int32), 0, 0);
+                    let predicate = if op == BinOp::Eq {
+                        LLVMIntPredicate::LLVMIntEQ
+                    } else {
+                        LLVMIntPredicate::LLVMIntNE
+                    };
+                    Ok(Value {
+                        value: LLVMBuildICmp(b, predicate, order, zero, c"cmp".as_ptr()),
+                        ty: Type::Bool,
+                    })
+                }
+                _ => Err(CodegenError::InvalidOperands { op, ty: lhs.ty }),
+            }
+        }
+    }
+
+    fn emit_unop(&mut self, op: UnOp, operand: Value) -> Result<Value, CodegenError> {
+        match op {
+            // `bool` is an `i1`, so flipping every bit is logical not.
+            UnOp::Not if operand.ty == Type::Bool => {
+                let value = unsafe { LLVMBuildNot(self.builder, operand.value, c"not".as_ptr()) };
+                Ok(Value {
+                    value,
+                    ty: Type::Bool,
+                })
+            }
+            UnOp::Not => Err(CodegenError::InvalidOperand { op, ty: operand.ty }),
+        }
+    }
+
+    /// Converts `value` to `to`. Integers are sign-extended or truncated, and
+    /// floats converted to integers round toward zero.
+    fn emit_cast(&mut self, value: Value, to: &Type) -> Result<Value, CodegenError> {
+        let (from, dest, b) = (&value.ty, self.llvm_type(to), self.builder);
+        let v = value.value;
+        let converted = unsafe {
+            if from == to {
+                v
+            } else if from.is_integer() && to.is_float() {
+                LLVMBuildSIToFP(b, v, dest, c"conv".as_ptr())
+            } else if from.is_float() && to.is_integer() {
+                LLVMBuildFPToSI(b, v, dest, c"conv".as_ptr())
+            } else if from.is_integer() && to.is_integer() && from.size_of() < to.size_of() {
+                LLVMBuildSExt(b, v, dest, c"conv".as_ptr())
+            } else if from.is_integer() && to.is_integer() {
+                LLVMBuildTrunc(b, v, dest, c"conv".as_ptr())
+            } else {
+                return Err(CodegenError::InvalidCast {
+                    from: value.ty,
+                    to: to.clone(),
+                });
+            }
+        };
+        Ok(Value {
+            value: converted,
+            ty: to.clone(),
+        })
+    }
+
+    /// Emits `and`/`or` so that `rhs` is only evaluated when it decides the result.
+    fn emit_logical(&mut self, op: BinOp, lhs: &Expr, rhs: &Expr) -> Result<Value, CodegenError> {
+        let lhs = self.emit_expr(lhs)?;
+        let rhs_block = self.append_block("logic.rhs");
+        let merge_block = self.append_block("logic.end");
+        unsafe {
+            let lhs_end = LLVMGetInsertBlock(self.builder);
+            // The value of the whole expression when `rhs` is skipped.
+            let short_circuit = match op {
+                BinOp::And => {
+                    LLVMBuildCondBr(self.builder, lhs.value, rhs_block, merge_block);
+                    LLVMConstInt(LLVMInt1TypeInContext(self.context), 0, 0)
+                }
+                _ => {
+                    LLVMBuildCondBr(self.builder, lhs.value, merge_block, rhs_block);
+                    LLVMConstInt(LLVMInt1TypeInContext(self.context), 1, 0)
+                }
+            };
+
+            LLVMPositionBuilderAtEnd(self.builder, rhs_block);
+            let rhs = self.emit_expr(rhs)?;
+            let rhs_end = LLVMGetInsertBlock(self.builder);
+            LLVMBuildBr(self.builder, merge_block);
+
+            LLVMPositionBuilderAtEnd(self.builder, merge_block);
+            let phi = LLVMBuildPhi(
+                self.builder,
+                LLVMInt1TypeInContext(self.context),
+                c"logic".as_ptr(),
+            );
+            let mut values = [short_circuit, rhs.value];
+            let mut blocks = [lhs_end, rhs_end];
+            LLVMAddIncoming(phi, values.as_mut_ptr(), blocks.as_mut_ptr(), 2);
+            Ok(Value {
+                value: phi,
+                ty: Type::Bool,
+            })
+        }
+    }
+
+    fn emit_compare(&self, op: BinOp, float: bool, l: LLVMValueRef, r: LLVMValueRef) -> Value {
+        let value = unsafe {
+            if float {
+                let predicate = match op {
+                    BinOp::Eq => LLVMRealPredicate::LLVMRealOEQ,
+                    BinOp::Neq => LLVMRealPredicate::LLVMRealONE,
+                    BinOp::Lt => LLVMRealPredicate::LLVMRealOLT,
+                    BinOp::Gt => LLVMRealPredicate::LLVMRealOGT,
+                    BinOp::Leq => LLVMRealPredicate::LLVMRealOLE,
+                    _ => LLVMRealPredicate::LLVMRealOGE,
+                };
+                LLVMBuildFCmp(self.builder, predicate, l, r, c"cmp".as_ptr())
+            } else {
+                let predicate = match op {
+                    BinOp::Eq => LLVMIntPredicate::LLVMIntEQ,
+                    BinOp::Neq => LLVMIntPredicate::LLVMIntNE,
+                    BinOp::Lt => LLVMIntPredicate::LLVMIntSLT,
+                    BinOp::Gt => LLVMIntPredicate::LLVMIntSGT,
+                    BinOp::Leq => LLVMIntPredicate::LLVMIntSLE,
+                    _ => LLVMIntPredicate::LLVMIntSGE,
+                };
+                LLVMBuildICmp(self.builder, predicate, l, r, c"cmp".as_ptr())
+            }
+        };
+        Value {
+            value,
+            ty: Type::Bool,
+        }
+    }
+}
+
+impl Drop for Codegen {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeBuilder(self.builder) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(sym(name))
+    }
+
+    fn assert_valid(module: &CodegenModule) {
+        if let Err(error) = module.verify() {
+            panic!("invalid module: {error}\n{}", module.to_llvm_ir_string());
+        }
+    }
+
+    /// `fn double(x: int) -> int { return x + x; }` plus a `main` with `body`.
+    fn program(body: Vec<Stmt>) -> Program {
+        Program {
+            functions: vec![
+                Function {
+                    name: sym("double"),
+                    params: vec![(sym("x"), Type::Int)],
+                    return_type: Type::Int,
+                    is_external: false,
+                    is_variadic: false,
+                    inline_hint: InlineHint::Default,
+                    body: Stmt::Block(vec![Stmt::Return(Some(Expr::BinOp(
+                        BinOp::Add,
+                        Box::new(var("x")),
+                        Box::new(var("x")),
+                    )))]),
+                },
+                Function {
+                    name: sym("main"),
+                    params: vec![],
+                    return_type: Type::Int,
+                    is_external: false,
+                    is_variadic: false,
+                    inline_hint: InlineHint::Default,
+                    body: Stmt::Block(body),
+                },
+            ],
+            globals: vec![],
+            default_int: Type::Int,
+        }
+    }
+
+    #[test]
+    fn test_llvm_type_is_cached() {
+        let module = CodegenModule::new("types");
+        let codegen = Codegen::new(&module);
+        let point = Type::Struct(vec![(sym("x"), Type::Int), (sym("y"), Type::Float)]);
+
+        let first = codegen.llvm_type(&point);
+        assert_eq!(codegen.llvm_type(&point), first);
+        assert_eq!(codegen.llvm_type(&point.clone()), first);
+        assert_eq!(codegen.types.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_narrow_global_initializer() {
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.globals.push((
+            sym("X"),
+            Type::Int32,
+            Some(Constant::Int(5)),
+            Mutability::Const,
+        ));
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+        let ir = module.to_llvm_ir_string();
+        assert!(ir.contains("@X = constant i32 5"), "{ir}");
+    }
+
+    #[test]
+    fn test_intrinsic_without_arguments() {
+        let program = program(vec![
+            Stmt::Expr(Expr::Call(Intrinsic::Assert.symbol(), vec![])),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+
+        assert_eq!(
+            codegen_program(&program).err(),
+            Some(CodegenError::ArityMismatch {
+                callee: Intrinsic::Assert.symbol(),
+                expected: 1,
+                found: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_string_concat_and_compare() {
+        // let a = "ab"; let b = "cd";
+        // if a + b == "abcd" && a != b { return 1; } return 0;
+        let string = |value: &str| Expr::Const(Constant::String(value.to_string()));
+        let program = program(vec![
+            Stmt::Declare(sym("a"), Type::String, Some(string("ab"))),
+            Stmt::Declare(sym("b"), Type::String, Some(string("cd"))),
+            Stmt::If(
+                Expr::BinOp(
+                    BinOp::And,
+                    Box::new(Expr::BinOp(
+                        BinOp::Eq,
+                        Box::new(Expr::BinOp(
+                            BinOp::Add,
+                            Box::new(var("a")),
+                            Box::new(var("b")),
+                        )),
+                        Box::new(string("abcd")),
+                    )),
+                    Box::new(Expr::BinOp(
+                        BinOp::Neq,
+                        Box::new(var("a")),
+                        Box::new(var("b")),
+                    )),
+                ),
+                Box::new(Stmt::Return(Some(Expr::Const(Constant::Int(1))))),
+                None,
+            ),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+        assert_eq!(module.jit_run_main(), Ok(1));
+    }
+
+    #[test]
+    fn test_direct_call() {
+        let program = program(vec![Stmt::Return(Some(Expr::Call(
+            sym("double"),
+            vec![Expr::Const(Constant::Int(21))],
+        )))]);
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+        assert!(
+            module
+                .to_llvm_ir_string()
+                .contains("call i64 @double(i64 21)")
+        );
+    }
+
+    #[test]
+    fn test_call_to_external() {
+        // extern fn puts(s: string) -> i32;
+        // puts("hi");
+        let mut program = program(vec![
+            Stmt::Expr(Expr::Call(
+                sym("puts"),
+                vec![Expr::Const(Constant::String("hi".to_string()))],
+            )),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        program.functions.push(Function {
+            name: sym("puts"),
+            params: vec![(sym("s"), Type::String)],
+            return_type: Type::Int32,
+            is_external: true,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        });
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        assert!(ir.contains("declare i32 @puts(i8*)"), "{ir}");
+        assert!(ir.contains("call i32 @puts("), "{ir}");
+    }
+
+    #[test]
+    fn test_call_to_variadic() {
+        // extern fn printf(fmt: string, ...) -> i32;
+        // printf("%d", 1);
+        let mut program = program(vec![
+            Stmt::Expr(Expr::Call(
+                sym("printf"),
+                vec![
+                    Expr::Const(Constant::String("%d".to_string())),
+                    Expr::Const(Constant::Int(1)),
+                ],
+            )),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        program.functions.push(Function {
+            name: sym("printf"),
+            params: vec![(sym("fmt"), Type::String)],
+            return_type: Type::Int32,
+            is_external: true,
+            is_variadic: true,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        });
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        assert!(ir.contains("declare i32 @printf(i8*, ...)"), "{ir}");
+        assert!(ir.contains("call i32 (i8*, ...) @printf("), "{ir}");
+    }
+
+    #[test]
+    fn test_call_through_function_variable() {
+        // let f: fn(int) -> int = double;
+        // return f(21);
+        let fn_type = Type::Function(vec![Type::Int], Box::new(Type::Int), false);
+        let program = program(vec![
+            Stmt::Declare(sym("f"), fn_type, Some(var("double"))),
+            Stmt::Return(Some(Expr::CallIndirect(
+                Box::new(var("f")),
+                vec![Expr::Const(Constant::Int(21))],
+            ))),
+        ]);
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        // The callee is loaded from `f`'s slot rather than named directly.
+        assert!(ir.contains("%f1 = load"), "{ir}");
+        assert!(ir.contains("call i64 %f1(i64 21)"), "{ir}");
+    }
+
+    #[test]
+    fn test_call_non_function() {
+        let program = program(vec![Stmt::Return(Some(Expr::CallIndirect(
+            Box::new(Expr::Const(Constant::Int(1))),
+            vec![],
+        )))]);
+
+        assert_eq!(
+            codegen_program(&program).err(),
+            Some(CodegenError::NotCallable(Type::Int))
+        );
+    }
+
+    #[test]
+    fn test_struct_fields_keep_declaration_order() {
+        // let p: struct { c: int, a: int, b: int };
+        // p.c = 1; p.a = 2; p.b = 3;
+        let point = Type::Struct(vec![
+            (sym("c"), Type::Int),
+            (sym("a"), Type::Int),
+            (sym("b"), Type::Int),
+        ]);
+        let field = |name: &str| Expr::FieldAccess(Box::new(var("p")), sym(name));
+        let program = program(vec![
+            Stmt::Declare(sym("p"), point, None),
+            Stmt::Assign(field("c"), Expr::Const(Constant::Int(1))),
+            Stmt::Assign(field("a"), Expr::Const(Constant::Int(2))),
+            Stmt::Assign(field("b"), Expr::Const(Constant::Int(3))),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        // Field indices follow the declaration, not the names.
+        for (index, name) in ["c", "a", "b"].into_iter().enumerate() {
+            let gep = format!("%{name} = getelementptr inbounds {{ i64, i64, i64 }}");
+            let line = ir.lines().find(|line| line.contains(&gep)).expect(&ir);
+            assert!(line.ends_with(&format!("i32 0, i32 {index}")), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_control_flow() {
+        // let i = 0;
+        // while i < 10 { if i == 5 { return i; } i = i + 1; }
+        // return i;
+        let program = program(vec![
+            Stmt::Declare(sym("i"), Type::Int, Some(Expr::Const(Constant::Int(0)))),
+            Stmt::While(
+                Expr::BinOp(
+                    BinOp::Lt,
+                    Box::new(var("i")),
+                    Box::new(Expr::Const(Constant::Int(10))),
+                ),
+                Box::new(Stmt::Block(vec![
+                    Stmt::If(
+                        Expr::BinOp(
+                            BinOp::Eq,
+                            Box::new(var("i")),
+                            Box::new(Expr::Const(Constant::Int(5))),
+                        ),
+                        Box::new(Stmt::Return(Some(var("i")))),
+                        None,
+                    ),
+                    Stmt::Assign(
+                        var("i"),
+                        Expr::BinOp(
+                            BinOp::Add,
+                            Box::new(var("i")),
+                            Box::new(Expr::Const(Constant::Int(1))),
+                        ),
+                    ),
+                ])),
+                None,
+            ),
+            Stmt::Return(Some(var("i"))),
+        ]);
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+    }
+
+    #[test]
+    fn test_logical_and_short_circuits() {
+        // fn f() -> bool { return true; }
+        // fn main(a: bool) -> bool { return a and f(); }
+        let program = Program {
+            functions: vec![
+                Function {
+                    name: sym("f"),
+                    params: vec![],
+                    return_type: Type::Bool,
+                    is_external: false,
+                    is_variadic: false,
+                    inline_hint: InlineHint::Default,
+                    body: Stmt::Block(vec![Stmt::Return(Some(Expr::Const(Constant::Bool(true))))]),
+                },
+                Function {
+                    name: sym("main"),
+                    params: vec![(sym("a"), Type::Bool)],
+                    return_type: Type::Bool,
+                    is_external: false,
+                    is_variadic: false,
+                    inline_hint: InlineHint::Default,
+                    body: Stmt::Block(vec![Stmt::Return(Some(Expr::BinOp(
+                        BinOp::And,
+                        Box::new(var("a")),
+                        Box::new(Expr::Call(sym("f"), vec![])),
+                    )))]),
+                },
+            ],
+            globals: vec![],
+            default_int: Type::Int,
+        };
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        assert!(
+            ir.contains("br i1 %a1, label %logic.rhs, label %logic.end"),
+            "{ir}"
+        );
+        // The call only happens once the branch has been taken.
+        let rhs = ir.find("\nlogic.rhs:").expect("missing rhs block");
+        let call = ir.find("call i1 @f()").expect("missing call");
+        assert!(call > rhs, "{ir}");
+    }
+
+    #[test]
+    fn test_void_call() {
+        // fn log() {}
+        // fn main() { log(); return log(); }
+        let void_function = |name: &str, body| Function {
+            name: sym(name),
+            params: vec![],
+            return_type: Type::Void,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(body),
+        };
+        let program = Program {
+            functions: vec![
+                void_function("log", vec![]),
+                void_function(
+                    "main",
+                    vec![
+                        Stmt::Expr(Expr::Call(sym("log"), vec![])),
+                        Stmt::Return(Some(Expr::Call(sym("log"), vec![]))),
+                    ],
+                ),
+            ],
+            globals: vec![],
+            default_int: Type::Int,
+        };
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+        assert!(
+            module
+                .to_llvm_ir_string()
+                .contains("call void @log()\n  ret void")
+        );
+    }
+
+    /// `'outer: while true { while true { <inner> } }` followed by `return 0;`.
+    fn nested_loops(inner: Stmt) -> Program {
+        let forever = |body, label: Option<&str>| {
+            Stmt::While(
+                Expr::Const(Constant::Bool(true)),
+                Box::new(Stmt::Block(vec![body])),
+                label.map(sym),
+            )
+        };
+        program(vec![
+            forever(forever(inner, None), Some("outer")),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ])
+    }
+
+    #[test]
+    fn test_break_to_outer_loop() {
+        let module = codegen_program(&nested_loops(Stmt::Break(Some(sym("outer"))))).unwrap();
+        assert_valid(&module);
+
+        // The outer loop's blocks are created first and keep the plain names,
+        // the inner body is uniqued to `while.body2`.
+        let ir = module.to_llvm_ir_string();
+        let inner_body = &ir[ir.find("while.body2:").unwrap()..];
+        assert!(inner_body.contains("br label %while.end\n"), "{}", ir);
+    }
+
+    #[test]
+    fn test_unlabeled_continue_targets_inner_loop() {
+        let module = codegen_program(&nested_loops(Stmt::Continue(None))).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        let inner_body = &ir[ir.find("while.body2:").unwrap()..];
+        assert!(inner_body.contains("br label %while.cond1\n"), "{}", ir);
+    }
+
+    #[test]
+    fn test_logical_not() {
+        // fn not(b: bool) -> bool { return !b; }
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.functions.push(Function {
+            name: sym("not"),
+            params: vec![(sym("b"), Type::Bool)],
+            return_type: Type::Bool,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![Stmt::Return(Some(Expr::UnOp(
+                UnOp::Not,
+                Box::new(var("b")),
+            )))]),
+        });
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        assert!(ir.contains("%not = xor i1 %b"), "{ir}");
+    }
+
+    #[test]
+    fn test_not_of_integer() {
+        let program = program(vec![Stmt::Return(Some(Expr::UnOp(
+            UnOp::Not,
+            Box::new(Expr::Const(Constant::Int(1))),
+        )))]);
+        assert_eq!(
+            codegen_program(&program).err(),
+            Some(CodegenError::InvalidOperand {
+                op: UnOp::Not,
+                ty: Type::Int,
+            })
+        );
+    }
+
+    #[test]
+    fn test_int_to_float_cast() {
+        // fn half(x: int) -> float { return x as float / 2.0; }
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.functions.push(Function {
+            name: sym("half"),
+            params: vec![(sym("x"), Type::Int)],
+            return_type: Type::Float,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![Stmt::Return(Some(Expr::BinOp(
+                BinOp::Div,
+                Box::new(Expr::Cast(Box::new(var("x")), Box::new(Type::Float))),
+                Box::new(Expr::Const(Constant::Float(2.0))),
+            )))]),
+        });
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        assert!(ir.contains("%conv = sitofp i64 %x1 to double"), "{ir}");
+        assert!(ir.contains("fdiv double %conv"), "{ir}");
+    }
+
+    #[test]
+    fn test_switch() {
+        // fn pick(x: int) -> int {
+        //     switch x { 1 => return 10, 2 => return 20, default => return 0 }
+        // }
+        let ret = |value| Stmt::Return(Some(Expr::Const(Constant::Int(value))));
+        let mut program = program(vec![ret(0)]);
+        program.functions.push(Function {
+            name: sym("pick"),
+            params: vec![(sym("x"), Type::Int)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![Stmt::Switch(
+                var("x"),
+                vec![(Constant::Int(1), ret(10)), (Constant::Int(2), ret(20))],
+                Some(Box::new(ret(0))),
+            )]),
+        });
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        assert!(
+            ir.contains("switch i64 %x1, label %switch.default ["),
+            "{ir}"
+        );
+        assert!(ir.contains("i64 1, label %switch.case\n"), "{ir}");
+        assert!(ir.contains("i64 2, label %switch.case2\n"), "{ir}");
+        let default_block = &ir[ir.find("switch.default:").unwrap()..];
+        assert_eq!(default_block.lines().nth(1), Some("  ret i64 0"), "{ir}");
+    }
+
+    #[test]
+    fn test_switch_on_i32() {
+        // fn pick(x: i32) -> int { switch x { -1 => return 10, default => return 0 } }
+        let ret = |value| Stmt::Return(Some(Expr::Const(Constant::Int(value))));
+        let mut program = program(vec![ret(0)]);
+        program.functions.push(Function {
+            name: sym("pick"),
+            params: vec![(sym("x"), Type::Int32)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![Stmt::Switch(
+                var("x"),
+                vec![(Constant::Int(-1), ret(10))],
+                Some(Box::new(ret(0))),
+            )]),
+        });
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        assert!(
+            ir.contains("switch i32 %x1, label %switch.default ["),
+            "{ir}"
+        );
+        assert!(ir.contains("i32 -1, label %switch.case\n"), "{ir}");
+    }
+
+    #[test]
+    fn test_inline_hint() {
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.functions[0].inline_hint = InlineHint::Always;
+        program.functions[1].inline_hint = InlineHint::Never;
+
+        let module = codegen_program(&program).unwrap();
+        assert_valid(&module);
+
+        let ir = module.to_llvm_ir_string();
+        let define = |name: &str| ir.lines().find(|line| line.contains(name)).unwrap();
+        assert!(define("@double(").ends_with("#0 {"), "{ir}");
+        assert!(define("@main(").ends_with("#1 {"), "{ir}");
+        assert!(ir.contains("attributes #0 = { alwaysinline }"), "{ir}");
+        assert!(ir.contains("attributes #1 = { noinline }"), "{ir}");
+    }
+
+    #[test]
+    fn test_unknown_label() {
+        let program = nested_loops(Stmt::Break(Some(sym("missing"))));
+        assert_eq!(
+            codegen_program(&program).err(),
+            Some(CodegenError::UnknownLabel(sym("missing")))
+        );
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        let program = program(vec![Stmt::Return(Some(Expr::Call(
+            sym("double"),
+            vec![Expr::Const(Constant::Int(21))],
+        )))]);
+        let mut reversed = program.clone();
+        reversed.functions.reverse();
+
+        let options = CodegenOptions {
+            deterministic_order: true,
+            ..CodegenOptions::default()
+        };
+        let module = codegen_program_with_options(&program, &options).unwrap();
+        let reversed = codegen_program_with_options(&reversed, &options).unwrap();
+        assert_valid(&reversed);
+        assert_eq!(module.to_llvm_ir_string(), reversed.to_llvm_ir_string());
+    }
+
+    #[test]
+    fn test_opt_level_promotes_allocas() {
+        // let y = double(3); return y;
+        let program = program(vec![
+            Stmt::Declare(
+                sym("y"),
+                Type::Int,
+                Some(Expr::Call(
+                    sym("double"),
+                    vec![Expr::Const(Constant::Int(3))],
+                )),
+            ),
+            Stmt::Return(Some(var("y"))),
+        ]);
+
+        let unoptimized = codegen_program(&program).unwrap();
+        assert!(unoptimized.to_llvm_ir_string().contains("alloca"));
+
+        let options = CodegenOptions {
+            opt_level: OptLevel::Default,
+            ..CodegenOptions::default()
+        };
+        let optimized = codegen_program_with_options(&program, &options).unwrap();
+        assert_valid(&optimized);
+        let ir = optimized.to_llvm_ir_string();
+        assert!(!ir.contains("alloca"), "{}", ir);
+    }
+
+    #[test]
+    fn test_break_outside_loop() {
+        let program = program(vec![Stmt::Break(None)]);
+        assert_eq!(
+            codegen_program(&program).err(),
+            Some(CodegenError::JumpOutsideLoop)
+        );
+    }
+}
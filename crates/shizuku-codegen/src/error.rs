@@ -0,0 +1,81 @@
+use shizuku_ir::BinOp;
+use shizuku_ir::Symbol;
+use shizuku_ir::Type;
+use shizuku_ir::UnOp;
+use std::fmt;
+
+/// An error raised while lowering IR into LLVM.
+///
+/// The IR is expected to be type checked before codegen, so these mostly
+/// indicate a malformed program that slipped past the checker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodegenError {
+    /// A variable or function with no definition.
+    UnresolvedSymbol(Symbol),
+    /// A call whose callee is not of function type.
+    NotCallable(Type),
+    /// A call to a built-in function with the wrong number of arguments.
+    ArityMismatch {
+        callee: Symbol,
+        expected: usize,
+        found: usize,
+    },
+    /// A binary operator applied to a type LLVM has no instruction for.
+    InvalidOperands { op: BinOp, ty: Type },
+    /// A unary operator applied to a type LLVM has no instruction for.
+    InvalidOperand { op: UnOp, ty: Type },
+    /// A cast between types other than the numeric ones.
+    InvalidCast { from: Type, to: Type },
+    /// An assignment or access target that does not name a storage location.
+    NotAddressable,
+    /// A `break` or `continue` naming a label no enclosing loop has.
+    UnknownLabel(Symbol),
+    /// A `break` or `continue` outside of any loop.
+    JumpOutsideLoop,
+    /// A function or global defined by more than one linked module.
+    DuplicateDefinition(Symbol),
+    /// LLVM failed to link a module, see its diagnostics.
+    LinkFailed,
+    /// LLVM rejected or failed to run an optimization pipeline.
+    PassFailed(String),
+    /// LLVM could not set up a JIT compiler for the host.
+    JitFailed(String),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::UnresolvedSymbol(sym) => write!(f, "undefined symbol `{}`", sym.0),
+            CodegenError::NotCallable(ty) => write!(f, "cannot call a value of type `{}`", ty),
+            CodegenError::ArityMismatch {
+                callee,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} arguments but {} were supplied",
+                callee.0, expected, found
+            ),
+            CodegenError::InvalidOperands { op, ty } => {
+                write!(f, "cannot emit `{:?}` for operands of type `{}`", op, ty)
+            }
+            CodegenError::InvalidOperand { op, ty } => {
+                write!(f, "cannot emit `{:?}` for an operand of type `{}`", op, ty)
+            }
+            CodegenError::InvalidCast { from, to } => {
+                write!(f, "cannot emit a cast from `{}` to `{}`", from, to)
+            }
+            CodegenError::NotAddressable => write!(f, "expression is not addressable"),
+            CodegenError::UnknownLabel(label) => write!(f, "undeclared label `'{}`", label.0),
+            CodegenError::JumpOutsideLoop => write!(f, "`break` or `continue` outside of a loop"),
+            CodegenError::DuplicateDefinition(sym) => {
+                write!(f, "`{}` is defined in more than one module", sym.0)
+            }
+            CodegenError::LinkFailed => write!(f, "failed to link modules"),
+            CodegenError::PassFailed(message) => write!(f, "optimization failed: {}", message),
+            CodegenError::JitFailed(message) => write!(f, "JIT compilation failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {}
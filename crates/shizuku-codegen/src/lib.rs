@@ -1,3 +1,18 @@
+mod codegen;
+mod error;
+mod link;
+mod module;
+mod optimize;
+
+pub use codegen::CodegenOptions;
+pub use codegen::codegen_program;
+pub use codegen::codegen_program_with_options;
+pub use error::CodegenError;
+pub use link::link_modules;
+pub use module::CodegenModule;
+pub use optimize::OptLevel;
+pub use optimize::run_optimizations;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -0,0 +1,195 @@
+//! Linking of separately generated modules.
+
+use crate::CodegenError;
+use crate::CodegenModule;
+use llvm_sys::LLVMLinkage;
+use llvm_sys::bit_reader::LLVMParseBitcodeInContext2;
+use llvm_sys::bit_writer::LLVMWriteBitcodeToMemoryBuffer;
+use llvm_sys::core::*;
+use llvm_sys::linker::LLVMLinkModules2;
+use llvm_sys::prelude::*;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashSet;
+use shizuku_ir::Symbol;
+
+/// Links `modules` into the first of them, e.g. one module per source file.
+///
+/// Every module lives in a context of its own, so each later module is copied
+/// into the first one's context through bitcode before being linked in. A
+/// function or global defined by two modules is an error, unless one of the
+/// definitions is `available_externally`, which only describes the other.
+/// Private and internal symbols, such as the `.str` globals holding string
+/// literals, are renamed by LLVM instead.
+pub fn link_modules(modules: Vec<CodegenModule>) -> Result<CodegenModule, CodegenError> {
+    let mut modules = modules.into_iter();
+    let Some(dest) = modules.next() else {
+        return Ok(CodegenModule::new("shizuku_module"));
+    };
+
+    for src in modules {
+        let defined = definitions(dest.module());
+        if let Some(name) = definitions(src.module())
+            .into_iter()
+            .find(|name| defined.contains(name))
+        {
+            return Err(CodegenError::DuplicateDefinition(Symbol(name)));
+        }
+
+        unsafe {
+            let bitcode = LLVMWriteBitcodeToMemoryBuffer(src.module());
+            let mut copy = std::ptr::null_mut();
+            let failed = LLVMParseBitcodeInContext2(dest.context(), bitcode, &mut copy);
+            LLVMDisposeMemoryBuffer(bitcode);
+            if failed != 0 {
+                return Err(CodegenError::LinkFailed);
+            }
+            // Destroys `copy`, whether linking succeeds or not.
+            if LLVMLinkModules2(dest.module(), copy) != 0 {
+                return Err(CodegenError::LinkFailed);
+            }
+        }
+    }
+
+    Ok(dest)
+}
+
+/// Names of the functions and globals `module` defines, leaving out
+/// declarations, `available_externally` definitions and local symbols.
+fn definitions(module: LLVMModuleRef) -> DHashSet<String> {
+    let mut names = dmap::new_set();
+    unsafe {
+        let mut function = LLVMGetFirstFunction(module);
+        while !function.is_null() {
+            insert_definition(function, &mut names);
+            function = LLVMGetNextFunction(function);
+        }
+        let mut global = LLVMGetFirstGlobal(module);
+        while !global.is_null() {
+            insert_definition(global, &mut names);
+            global = LLVMGetNextGlobal(global);
+        }
+    }
+    names
+}
+
+unsafe fn insert_definition(value: LLVMValueRef, names: &mut DHashSet<String>) {
+    unsafe {
+        if LLVMIsDeclaration(value) != 0
+            || matches!(
+                LLVMGetLinkage(value),
+                LLVMLinkage::LLVMAvailableExternallyLinkage
+                    | LLVMLinkage::LLVMPrivateLinkage
+                    | LLVMLinkage::LLVMInternalLinkage
+            )
+        {
+            return;
+        }
+        let mut len = 0;
+        let name = LLVMGetValueName2(value, &mut len);
+        let name = std::slice::from_raw_parts(name as *const u8, len);
+        names.insert(String::from_utf8_lossy(name).into_owned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen_program;
+    use shizuku_ir::Constant;
+    use shizuku_ir::Expr;
+    use shizuku_ir::Function;
+    use shizuku_ir::InlineHint;
+    use shizuku_ir::Program;
+    use shizuku_ir::Stmt;
+    use shizuku_ir::Type;
+    use std::ffi::CString;
+
+    /// A module defining `fn <name>() -> <type> { return <value>; }`.
+    fn module_returning(name: &str, value: Constant) -> CodegenModule {
+        let program = Program {
+            functions: vec![Function {
+                name: Symbol(name.to_string()),
+                params: vec![],
+                return_type: value.ty(),
+                is_external: false,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
+                body: Stmt::Block(vec![Stmt::Return(Some(Expr::Const(value)))]),
+            }],
+            globals: vec![],
+            default_int: Type::Int,
+        };
+        codegen_program(&program).unwrap()
+    }
+
+    /// A module defining `fn <name>() -> int { return <value>; }`.
+    fn module(name: &str, value: i64) -> CodegenModule {
+        module_returning(name, Constant::Int(value))
+    }
+
+    fn function(module: &CodegenModule, name: &str) -> LLVMValueRef {
+        let name = CString::new(name).unwrap();
+        unsafe { LLVMGetNamedFunction(module.module(), name.as_ptr()) }
+    }
+
+    #[test]
+    fn test_link_two_modules() {
+        let linked = link_modules(vec![module("one", 1), module("two", 2)]).unwrap();
+
+        assert_eq!(linked.verify(), Ok(()));
+        let defined = definitions(linked.module());
+        assert!(defined.contains("one"));
+        assert!(defined.contains("two"));
+    }
+
+    #[test]
+    fn test_link_duplicate_function() {
+        let result = link_modules(vec![module("main", 1), module("main", 2)]);
+
+        assert_eq!(
+            result.err(),
+            Some(CodegenError::DuplicateDefinition(Symbol(
+                "main".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_link_available_externally() {
+        let inlinable = module("f", 1);
+        unsafe {
+            LLVMSetLinkage(
+                function(&inlinable, "f"),
+                LLVMLinkage::LLVMAvailableExternallyLinkage,
+            );
+        }
+
+        let linked = link_modules(vec![module("f", 1), inlinable]).unwrap();
+
+        assert_eq!(linked.verify(), Ok(()));
+        let f = function(&linked, "f");
+        assert_eq!(
+            unsafe { LLVMGetLinkage(f) },
+            LLVMLinkage::LLVMExternalLinkage
+        );
+    }
+
+    #[test]
+    fn test_link_string_constants() {
+        let hello = module_returning("hello", Constant::String("hello".to_string()));
+        let world = module_returning("world", Constant::String("world".to_string()));
+
+        let linked = link_modules(vec![hello, world]).unwrap();
+
+        assert_eq!(linked.verify(), Ok(()));
+        let ir = linked.to_llvm_ir_string();
+        assert!(
+            ir.contains("@.str = private constant [6 x i8] c\"hello\\00\""),
+            "{ir}"
+        );
+        assert!(
+            ir.contains("@.str.1 = private constant [6 x i8] c\"world\\00\""),
+            "{ir}"
+        );
+    }
+}
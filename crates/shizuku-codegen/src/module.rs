@@ -0,0 +1,201 @@
+use crate::CodegenError;
+use llvm_sys::LLVMTypeKind;
+use llvm_sys::analysis::LLVMVerifierFailureAction;
+use llvm_sys::analysis::LLVMVerifyModule;
+use llvm_sys::core::*;
+use llvm_sys::execution_engine::*;
+use llvm_sys::prelude::*;
+use llvm_sys::target::LLVM_InitializeNativeAsmPrinter;
+use llvm_sys::target::LLVM_InitializeNativeTarget;
+use shizuku_ir::Symbol;
+use std::ffi::CStr;
+use std::ffi::CString;
+
+/// An LLVM module together with the context that owns it.
+pub struct CodegenModule {
+    context: LLVMContextRef,
+    module: LLVMModuleRef,
+}
+
+impl CodegenModule {
+    /// Creates an empty module named `name` in a fresh context.
+    pub fn new(name: &str) -> Self {
+        let name = CString::new(name).expect("module name contains a NUL byte");
+        unsafe {
+            let context = LLVMContextCreate();
+            let module = LLVMModuleCreateWithNameInContext(name.as_ptr(), context);
+            Self { context, module }
+        }
+    }
+
+    pub fn context(&self) -> LLVMContextRef {
+        self.context
+    }
+
+    pub fn module(&self) -> LLVMModuleRef {
+        self.module
+    }
+
+    /// Checks that the module is well formed, returning LLVM's description of
+    /// the problems if it is not.
+    pub fn verify(&self) -> Result<(), String> {
+        unsafe {
+            let mut message = std::ptr::null_mut();
+            let failed = LLVMVerifyModule(
+                self.module,
+                LLVMVerifierFailureAction::LLVMReturnStatusAction,
+                &mut message,
+            ) != 0;
+            let text = if message.is_null() {
+                String::new()
+            } else {
+                let text = CStr::from_ptr(message).to_string_lossy().into_owned();
+                LLVMDisposeMessage(message);
+                text
+            };
+            if failed { Err(text) } else { Ok(()) }
+        }
+    }
+
+    /// Compiles the module for the host and runs its `main`, returning what it
+    /// returns, or 0 if it returns `void`.
+    ///
+    /// The module is left as it is, the execution engine compiles a copy.
+    pub fn jit_run_main(&self) -> Result<i64, CodegenError> {
+        unsafe {
+            let main = LLVMGetNamedFunction(self.module, c"main".as_ptr());
+            if main.is_null() {
+                return Err(CodegenError::UnresolvedSymbol(Symbol("main".to_string())));
+            }
+            let return_type = LLVMGetReturnType(LLVMGlobalGetValueType(main));
+            let returns_void = LLVMGetTypeKind(return_type) == LLVMTypeKind::LLVMVoidTypeKind;
+
+            LLVMLinkInMCJIT();
+            if LLVM_InitializeNativeTarget() != 0 || LLVM_InitializeNativeAsmPrinter() != 0 {
+                return Err(CodegenError::JitFailed(
+                    "no native target to compile for".to_string(),
+                ));
+            }
+            // The engine owns the module it runs, also when creating it fails.
+            let copy = LLVMCloneModule(self.module);
+            let mut engine = std::ptr::null_mut();
+            let mut message = std::ptr::null_mut();
+            if LLVMCreateExecutionEngineForModule(&mut engine, copy, &mut message) != 0 {
+                let text = CStr::from_ptr(message).to_string_lossy().into_owned();
+                LLVMDisposeMessage(message);
+                return Err(CodegenError::JitFailed(text));
+            }
+
+            let main = LLVMGetNamedFunction(copy, c"main".as_ptr());
+            let result = LLVMRunFunction(engine, main, 0, std::ptr::null_mut());
+            let value = if returns_void {
+                0
+            } else {
+                LLVMGenericValueToInt(result, 1) as i64
+            };
+            LLVMDisposeGenericValue(result);
+            LLVMDisposeExecutionEngine(engine);
+            Ok(value)
+        }
+    }
+
+    /// The textual LLVM IR of the module, as in a `.ll` file.
+    pub fn to_llvm_ir_string(&self) -> String {
+        unsafe {
+            let ir = LLVMPrintModuleToString(self.module);
+            let text = CStr::from_ptr(ir).to_string_lossy().into_owned();
+            LLVMDisposeMessage(ir);
+            text
+        }
+    }
+}
+
+impl Drop for CodegenModule {
+    fn drop(&mut self) {
+        unsafe {
+            LLVMDisposeModule(self.module);
+            LLVMContextDispose(self.context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen_program;
+    use shizuku_ir::Constant;
+    use shizuku_ir::Expr;
+    use shizuku_ir::Function;
+    use shizuku_ir::InlineHint;
+    use shizuku_ir::Program;
+    use shizuku_ir::Stmt;
+    use shizuku_ir::Type;
+
+    #[test]
+    fn test_to_llvm_ir_string() {
+        let program = Program {
+            functions: vec![Function {
+                name: Symbol("nothing".to_string()),
+                params: vec![],
+                return_type: Type::Void,
+                is_external: false,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
+                body: Stmt::Block(vec![]),
+            }],
+            globals: vec![],
+            default_int: Type::Int,
+        };
+        let module = codegen_program(&program).unwrap();
+
+        let ir = module.to_llvm_ir_string();
+        assert!(ir.contains("define void @nothing()"), "{ir}");
+        assert_eq!(module.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_jit_run_main() {
+        // fn main() -> int { return 7; }
+        let program = Program {
+            functions: vec![Function {
+                name: Symbol("main".to_string()),
+                params: vec![],
+                return_type: Type::Int,
+                is_external: false,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
+                body: Stmt::Block(vec![Stmt::Return(Some(Expr::Const(Constant::Int(7))))]),
+            }],
+            globals: vec![],
+            default_int: Type::Int,
+        };
+        let module = codegen_program(&program).unwrap();
+
+        assert_eq!(module.jit_run_main(), Ok(7));
+        // The module is still usable afterwards.
+        assert_eq!(module.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_jit_run_main_without_main() {
+        let module = CodegenModule::new("empty");
+        assert_eq!(
+            module.jit_run_main(),
+            Err(CodegenError::UnresolvedSymbol(Symbol("main".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_verify_missing_terminator() {
+        let module = CodegenModule::new("broken");
+        unsafe {
+            let void = LLVMVoidTypeInContext(module.context());
+            let fn_type = LLVMFunctionType(void, std::ptr::null_mut(), 0, 0);
+            let function = LLVMAddFunction(module.module(), c"broken".as_ptr(), fn_type);
+            LLVMAppendBasicBlockInContext(module.context(), function, c"entry".as_ptr());
+        }
+
+        let error = module.verify().unwrap_err();
+        assert!(error.contains("terminator"), "{error}");
+    }
+}
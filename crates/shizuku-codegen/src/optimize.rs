@@ -0,0 +1,65 @@
+//! Running LLVM's optimization pipelines over a generated module.
+
+use crate::CodegenError;
+use crate::CodegenModule;
+use llvm_sys::error::LLVMDisposeErrorMessage;
+use llvm_sys::error::LLVMGetErrorMessage;
+use llvm_sys::transforms::pass_builder::*;
+use std::ffi::CStr;
+use std::ffi::CString;
+
+/// How much optimization to apply to a module, as in `-O0` to `-O3`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    /// Keep the module as generated.
+    #[default]
+    None,
+    Less,
+    Default,
+    Aggressive,
+}
+
+impl OptLevel {
+    /// The new pass manager pipeline for this level, if any.
+    fn pipeline(self) -> Option<&'static str> {
+        match self {
+            OptLevel::None => None,
+            OptLevel::Less => Some("default<O1>"),
+            OptLevel::Default => Some("default<O2>"),
+            OptLevel::Aggressive => Some("default<O3>"),
+        }
+    }
+}
+
+/// Runs the standard pipeline for `opt_level` over `module`.
+///
+/// Codegen keeps every local in an `alloca`, every pipeline starts by
+/// promoting those to SSA values (`mem2reg` and SROA) before optimizing
+/// further. No target machine is passed, so target specific passes are
+/// skipped.
+pub fn run_optimizations(module: &CodegenModule, opt_level: OptLevel) -> Result<(), CodegenError> {
+    let Some(pipeline) = opt_level.pipeline() else {
+        return Ok(());
+    };
+    let pipeline = CString::new(pipeline).unwrap();
+
+    unsafe {
+        let options = LLVMCreatePassBuilderOptions();
+        let error = LLVMRunPasses(
+            module.module(),
+            pipeline.as_ptr(),
+            std::ptr::null_mut(),
+            options,
+        );
+        LLVMDisposePassBuilderOptions(options);
+
+        if error.is_null() {
+            return Ok(());
+        }
+        // Consumes `error`.
+        let message = LLVMGetErrorMessage(error);
+        let text = CStr::from_ptr(message).to_string_lossy().into_owned();
+        LLVMDisposeErrorMessage(message);
+        Err(CodegenError::PassFailed(text))
+    }
+}
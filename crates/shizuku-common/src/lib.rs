@@ -1 +1,3 @@
 pub mod dmap;
+pub mod span;
+pub mod strsim;
@@ -0,0 +1,54 @@
+/// A byte range `start..end` in the source, shared by the lexer, the parser
+/// and diagnostics.
+///
+/// With the `serde` feature it serializes as `{ "start": u32, "end": u32 }`.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SrcSpan {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Maps byte offsets in a source to 1-based line and column numbers.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    /// Byte offset of the start of every line, the first one 0.
+    line_starts: Vec<u32>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i as u32 + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The line and column of byte `offset`, both counted from 1. Columns
+    /// count characters, and an offset past the end maps to the end.
+    pub fn line_col(&self, offset: u32) -> (u32, u32) {
+        let offset = offset.min(self.source.len() as u32);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line] as usize;
+        let column = self.source[line_start..offset as usize].chars().count();
+        (line as u32 + 1, column as u32 + 1)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_serde_round_trip() {
+        let span = SrcSpan { start: 3, end: 8 };
+
+        let json = serde_json::to_string(&span).unwrap();
+        assert_eq!(json, r#"{"start":3,"end":8}"#);
+        assert_eq!(serde_json::from_str::<SrcSpan>(&json).unwrap(), span);
+    }
+}
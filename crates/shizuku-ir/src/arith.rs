@@ -0,0 +1,255 @@
+//! Compile-time arithmetic on constants.
+//!
+//! Every pass that evaluates operators on constants goes through these
+//! methods, so they all agree with each other and with the generated code.
+
+use crate::BinOp;
+use crate::Constant;
+use crate::Type;
+use crate::UnOp;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArithError {
+    /// Operands of different types, such as `1 + 2.0`. There is no implicit
+    /// conversion between numeric types.
+    TypeMismatch { lhs: Type, rhs: Type },
+    /// An operator the operand type does not support, such as `true + false`.
+    InvalidOperator { op: BinOp, ty: Type },
+    /// A unary operator the operand type does not support, such as `!1`.
+    InvalidUnaryOperator { op: UnOp, ty: Type },
+    /// An integer division or remainder by zero.
+    DivisionByZero,
+    /// An integer division or remainder whose result does not fit, which is
+    /// only `i64::MIN / -1`.
+    Overflow,
+}
+
+impl fmt::Display for ArithError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArithError::TypeMismatch { lhs, rhs } => {
+                write!(f, "mismatched operand types `{}` and `{}`", lhs, rhs)
+            }
+            ArithError::InvalidOperator { op, ty } => {
+                write!(f, "cannot apply `{}` to `{}`", op, ty)
+            }
+            ArithError::InvalidUnaryOperator { op, ty } => {
+                write!(f, "cannot apply `{}` to `{}`", op, ty)
+            }
+            ArithError::DivisionByZero => write!(f, "attempt to divide by zero"),
+            ArithError::Overflow => write!(f, "attempt to divide with overflow"),
+        }
+    }
+}
+
+impl std::error::Error for ArithError {}
+
+impl Constant {
+    /// Evaluates `self op rhs`.
+    ///
+    /// Integer arithmetic wraps like the generated code does, except that a
+    /// division or remainder that would trap at run time is an error. Floats
+    /// follow IEEE semantics, so dividing by zero gives an infinity and
+    /// `NaN == NaN` is `false`, unlike `Constant`'s own `==`.
+    pub fn binop(&self, op: BinOp, rhs: &Constant) -> Result<Constant, ArithError> {
+        use Constant::Bool;
+
+        let invalid = || ArithError::InvalidOperator { op, ty: self.ty() };
+        match (self, rhs) {
+            (Constant::Int(a), Constant::Int(b)) => match op {
+                BinOp::Add => Ok(Constant::Int(a.wrapping_add(*b))),
+                BinOp::Sub => Ok(Constant::Int(a.wrapping_sub(*b))),
+                BinOp::Mul => Ok(Constant::Int(a.wrapping_mul(*b))),
+                BinOp::Div | BinOp::Mod if *b == 0 => Err(ArithError::DivisionByZero),
+                BinOp::Div => a
+                    .checked_div(*b)
+                    .map(Constant::Int)
+                    .ok_or(ArithError::Overflow),
+                BinOp::Mod => a
+                    .checked_rem(*b)
+                    .map(Constant::Int)
+                    .ok_or(ArithError::Overflow),
+                BinOp::Eq => Ok(Bool(a == b)),
+                BinOp::Neq => Ok(Bool(a != b)),
+                BinOp::Lt => Ok(Bool(a < b)),
+                BinOp::Gt => Ok(Bool(a > b)),
+                BinOp::Leq => Ok(Bool(a <= b)),
+                BinOp::Geq => Ok(Bool(a >= b)),
+                BinOp::And | BinOp::Or => Err(invalid()),
+            },
+            (Constant::Float(a), Constant::Float(b)) => match op {
+                BinOp::Add => Ok(Constant::Float(a + b)),
+                BinOp::Sub => Ok(Constant::Float(a - b)),
+                BinOp::Mul => Ok(Constant::Float(a * b)),
+                BinOp::Div => Ok(Constant::Float(a / b)),
+                BinOp::Mod => Ok(Constant::Float(a % b)),
+                BinOp::Eq => Ok(Bool(a == b)),
+                BinOp::Neq => Ok(Bool(a != b)),
+                BinOp::Lt => Ok(Bool(a < b)),
+                BinOp::Gt => Ok(Bool(a > b)),
+                BinOp::Leq => Ok(Bool(a <= b)),
+                BinOp::Geq => Ok(Bool(a >= b)),
+                BinOp::And | BinOp::Or => Err(invalid()),
+            },
+            (Bool(a), Bool(b)) => match op {
+                BinOp::Eq => Ok(Bool(a == b)),
+                BinOp::Neq => Ok(Bool(a != b)),
+                BinOp::And => Ok(Bool(*a && *b)),
+                BinOp::Or => Ok(Bool(*a || *b)),
+                _ => Err(invalid()),
+            },
+            (Constant::String(a), Constant::String(b)) => match op {
+                BinOp::Add => Ok(Constant::String(format!("{a}{b}"))),
+                BinOp::Eq => Ok(Bool(a == b)),
+                BinOp::Neq => Ok(Bool(a != b)),
+                _ => Err(invalid()),
+            },
+            _ => Err(ArithError::TypeMismatch {
+                lhs: self.ty(),
+                rhs: rhs.ty(),
+            }),
+        }
+    }
+
+    /// Evaluates `op self`.
+    pub fn unop(&self, op: UnOp) -> Result<Constant, ArithError> {
+        match (op, self) {
+            (UnOp::Not, Constant::Bool(value)) => Ok(Constant::Bool(!value)),
+            _ => Err(ArithError::InvalidUnaryOperator { op, ty: self.ty() }),
+        }
+    }
+
+    pub fn add(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Add, rhs)
+    }
+
+    pub fn sub(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Sub, rhs)
+    }
+
+    pub fn mul(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Mul, rhs)
+    }
+
+    pub fn div(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Div, rhs)
+    }
+
+    pub fn rem(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Mod, rhs)
+    }
+
+    /// `self == rhs` as a `Constant::Bool`, with IEEE semantics for floats.
+    /// Named apart from `PartialEq::eq`, which compares by bit pattern.
+    pub fn eq_value(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Eq, rhs)
+    }
+
+    pub fn ne_value(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Neq, rhs)
+    }
+
+    pub fn lt(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Lt, rhs)
+    }
+
+    pub fn gt(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Gt, rhs)
+    }
+
+    pub fn le(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Leq, rhs)
+    }
+
+    pub fn ge(&self, rhs: &Constant) -> Result<Constant, ArithError> {
+        self.binop(BinOp::Geq, rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(value: i64) -> Constant {
+        Constant::Int(value)
+    }
+
+    fn float(value: f64) -> Constant {
+        Constant::Float(value)
+    }
+
+    #[test]
+    fn test_int_arithmetic() {
+        assert_eq!(int(7).add(&int(2)), Ok(int(9)));
+        assert_eq!(int(7).sub(&int(2)), Ok(int(5)));
+        assert_eq!(int(7).mul(&int(2)), Ok(int(14)));
+        assert_eq!(int(7).div(&int(2)), Ok(int(3)));
+        assert_eq!(int(-7).rem(&int(2)), Ok(int(-1)));
+        assert_eq!(int(i64::MAX).add(&int(1)), Ok(int(i64::MIN)));
+    }
+
+    #[test]
+    fn test_float_arithmetic() {
+        assert_eq!(float(1.5).add(&float(1.0)), Ok(float(2.5)));
+        assert_eq!(float(1.5).sub(&float(1.0)), Ok(float(0.5)));
+        assert_eq!(float(1.5).mul(&float(2.0)), Ok(float(3.0)));
+        assert_eq!(float(1.0).div(&float(4.0)), Ok(float(0.25)));
+        assert_eq!(float(1.0).div(&float(0.0)), Ok(float(f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let t = Ok(Constant::Bool(true));
+        let f = Ok(Constant::Bool(false));
+
+        assert_eq!(int(1).lt(&int(2)), t);
+        assert_eq!(int(1).gt(&int(2)), f);
+        assert_eq!(int(2).le(&int(2)), t);
+        assert_eq!(int(1).ge(&int(2)), f);
+        assert_eq!(int(1).eq_value(&int(1)), t);
+        assert_eq!(int(1).ne_value(&int(1)), f);
+        assert_eq!(float(f64::NAN).eq_value(&float(f64::NAN)), f);
+        assert_eq!(float(0.0).eq_value(&float(-0.0)), t);
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        assert_eq!(int(1).div(&int(0)), Err(ArithError::DivisionByZero));
+        assert_eq!(int(1).rem(&int(0)), Err(ArithError::DivisionByZero));
+        assert_eq!(int(i64::MIN).div(&int(-1)), Err(ArithError::Overflow));
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        assert_eq!(
+            int(1).add(&float(2.0)),
+            Err(ArithError::TypeMismatch {
+                lhs: Type::Int,
+                rhs: Type::Float,
+            })
+        );
+        assert_eq!(
+            Constant::Bool(true).add(&Constant::Bool(false)),
+            Err(ArithError::InvalidOperator {
+                op: BinOp::Add,
+                ty: Type::Bool,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unary_operators() {
+        assert_eq!(
+            Constant::Bool(true).unop(UnOp::Not),
+            Ok(Constant::Bool(false))
+        );
+        assert_eq!(
+            int(1).unop(UnOp::Not),
+            Err(ArithError::InvalidUnaryOperator {
+                op: UnOp::Not,
+                ty: Type::Int,
+            })
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Building functions statement by statement.
+
+use crate::Function;
+use crate::InlineHint;
+use crate::Stmt;
+use crate::Symbol;
+use crate::Type;
+use crate::TypeError;
+use crate::check_returns;
+
+/// Builds a non-external `Function` from its signature and body statements.
+#[derive(Debug, Clone)]
+pub struct FunctionBuilder {
+    function: Function,
+    body: Vec<Stmt>,
+}
+
+impl FunctionBuilder {
+    pub fn new(name: &str, return_type: Type) -> Self {
+        Self {
+            function: Function {
+                name: Symbol(name.to_string()),
+                params: vec![],
+                return_type,
+                is_external: false,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
+                body: Stmt::Block(vec![]),
+            },
+            body: vec![],
+        }
+    }
+
+    pub fn param(mut self, name: &str, ty: Type) -> Self {
+        self.function.params.push((Symbol(name.to_string()), ty));
+        self
+    }
+
+    pub fn variadic(mut self) -> Self {
+        self.function.is_variadic = true;
+        self
+    }
+
+    pub fn inline_hint(mut self, hint: InlineHint) -> Self {
+        self.function.inline_hint = hint;
+        self
+    }
+
+    /// Appends `stmt` to the body.
+    pub fn stmt(mut self, stmt: Stmt) -> Self {
+        self.body.push(stmt);
+        self
+    }
+
+    /// Finishes the function.
+    ///
+    /// A `void` body not ending in a `return` gets one appended, as every
+    /// LLVM block needs a terminator. Any other body must return on every
+    /// path, see `check_returns`.
+    pub fn build(self) -> Result<Function, TypeError> {
+        let Self {
+            mut function,
+            mut body,
+        } = self;
+        if function.return_type == Type::Void && !matches!(body.last(), Some(Stmt::Return(_))) {
+            body.push(Stmt::Return(None));
+        }
+        function.body = Stmt::Block(body);
+        check_returns(&function)?;
+        Ok(function)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Constant;
+    use crate::Expr;
+
+    fn call(name: &str) -> Stmt {
+        Stmt::Expr(Expr::Call(Symbol(name.to_string()), vec![]))
+    }
+
+    #[test]
+    fn test_void_function_gets_return() {
+        let function = FunctionBuilder::new("f", Type::Void)
+            .stmt(call("g"))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            function.body,
+            Stmt::Block(vec![call("g"), Stmt::Return(None)])
+        );
+    }
+
+    #[test]
+    fn test_explicit_return_is_kept() {
+        let function = FunctionBuilder::new("f", Type::Int)
+            .param("x", Type::Int)
+            .stmt(Stmt::Return(Some(Expr::Var(Symbol("x".to_string())))))
+            .build()
+            .unwrap();
+
+        assert_eq!(function.params, vec![(Symbol("x".to_string()), Type::Int)]);
+        assert_eq!(
+            function.body,
+            Stmt::Block(vec![Stmt::Return(Some(Expr::Var(Symbol("x".to_string()))))])
+        );
+    }
+
+    #[test]
+    fn test_non_void_function_without_return() {
+        let result = FunctionBuilder::new("f", Type::Int)
+            .stmt(Stmt::Expr(Expr::Const(Constant::Int(1))))
+            .build();
+
+        assert_eq!(
+            result,
+            Err(TypeError::MissingReturn(Symbol("f".to_string())))
+        );
+    }
+}
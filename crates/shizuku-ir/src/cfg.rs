@@ -0,0 +1,356 @@
+//! Control-flow graphs of function bodies, for analyses that need explicit
+//! edges rather than the nesting of structured statements.
+
+use crate::Constant;
+use crate::Expr;
+use crate::Function;
+use crate::Stmt;
+use crate::Symbol;
+
+/// Index of a block in its `Cfg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockId(pub usize);
+
+/// Straight-line statements followed by a jump.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock<'a> {
+    /// Declarations, assignments and expression statements, in order.
+    pub stmts: Vec<&'a Stmt>,
+    pub terminator: Terminator<'a>,
+}
+
+/// How control leaves a block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator<'a> {
+    Goto(BlockId),
+    /// Condition, then the blocks for true and false
+    Branch(&'a Expr, BlockId, BlockId),
+    /// Scrutinee, the block for each arm, then the one for no match
+    Switch(&'a Expr, Vec<(&'a Constant, BlockId)>, BlockId),
+    /// A `return`, or `Return(None)` for falling off the end of the body.
+    Return(Option<&'a Expr>),
+    /// A `break` or `continue` with no matching loop, which codegen rejects.
+    Unreachable,
+}
+
+impl Terminator<'_> {
+    /// The blocks control may continue in.
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Goto(target) => vec![*target],
+            Terminator::Branch(_, then_block, else_block) => vec![*then_block, *else_block],
+            Terminator::Switch(_, arms, default) => arms
+                .iter()
+                .map(|(_, block)| *block)
+                .chain([*default])
+                .collect(),
+            Terminator::Return(_) | Terminator::Unreachable => vec![],
+        }
+    }
+}
+
+/// The basic blocks of a function body, starting at `Cfg::ENTRY`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg<'a> {
+    blocks: Vec<BasicBlock<'a>>,
+}
+
+impl<'a> Cfg<'a> {
+    pub const ENTRY: BlockId = BlockId(0);
+
+    /// Every block, indexed by `BlockId`. Statements after a jump land in
+    /// blocks no edge leads to.
+    pub fn blocks(&self) -> &[BasicBlock<'a>] {
+        &self.blocks
+    }
+
+    pub fn block(&self, block: BlockId) -> &BasicBlock<'a> {
+        &self.blocks[block.0]
+    }
+
+    pub fn successors(&self, block: BlockId) -> Vec<BlockId> {
+        self.block(block).terminator.successors()
+    }
+
+    /// The blocks reachable from the entry, in index order.
+    pub fn reachable_blocks(&self) -> Vec<BlockId> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = vec![Self::ENTRY];
+        while let Some(block) = stack.pop() {
+            if !std::mem::replace(&mut reachable[block.0], true) {
+                stack.extend(self.successors(block));
+            }
+        }
+        (0..self.blocks.len())
+            .filter(|&index| reachable[index])
+            .map(BlockId)
+            .collect()
+    }
+}
+
+/// Builds the control-flow graph of `function`'s body. An external function
+/// gets a single block returning nothing.
+pub fn build_cfg(function: &Function) -> Cfg<'_> {
+    let mut builder = Builder {
+        stmts: Vec::new(),
+        terminators: Vec::new(),
+        current: None,
+        loops: Vec::new(),
+    };
+    let entry = builder.new_block();
+    builder.current = Some(entry);
+    builder.stmt(&function.body);
+    if builder.current.is_some() {
+        builder.terminate(Terminator::Return(None));
+    }
+
+    let blocks = builder
+        .stmts
+        .into_iter()
+        .zip(builder.terminators)
+        .map(|(stmts, terminator)| BasicBlock {
+            stmts,
+            terminator: terminator.expect("every block is terminated"),
+        })
+        .collect();
+    Cfg { blocks }
+}
+
+struct Loop<'a> {
+    label: Option<&'a Symbol>,
+    header: BlockId,
+    exit: BlockId,
+}
+
+struct Builder<'a> {
+    stmts: Vec<Vec<&'a Stmt>>,
+    terminators: Vec<Option<Terminator<'a>>>,
+    /// The block being filled, `None` right after a jump.
+    current: Option<BlockId>,
+    loops: Vec<Loop<'a>>,
+}
+
+impl<'a> Builder<'a> {
+    fn new_block(&mut self) -> BlockId {
+        self.stmts.push(Vec::new());
+        self.terminators.push(None);
+        BlockId(self.stmts.len() - 1)
+    }
+
+    /// The block being filled, starting an unreachable one after a jump.
+    fn current(&mut self) -> BlockId {
+        match self.current {
+            Some(block) => block,
+            None => {
+                let block = self.new_block();
+                self.current = Some(block);
+                block
+            }
+        }
+    }
+
+    fn terminate(&mut self, terminator: Terminator<'a>) {
+        let block = self.current();
+        self.terminators[block.0] = Some(terminator);
+        self.current = None;
+    }
+
+    /// Falls through to `target`, unless the current block already jumped.
+    fn goto(&mut self, target: BlockId) {
+        if self.current.is_some() {
+            self.terminate(Terminator::Goto(target));
+        }
+    }
+
+    fn find_loop(&self, label: &Option<Symbol>) -> Option<&Loop<'a>> {
+        match label {
+            Some(label) => self.loops.iter().rev().find(|lp| lp.label == Some(label)),
+            None => self.loops.last(),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::Declare(..) | Stmt::Assign(..) | Stmt::Expr(_) => {
+                let block = self.current();
+                self.stmts[block.0].push(stmt);
+            }
+            Stmt::Return(value) => self.terminate(Terminator::Return(value.as_ref())),
+            Stmt::Block(stmts) => {
+                for stmt in stmts {
+                    self.stmt(stmt);
+                }
+            }
+            Stmt::If(cond, then_branch, else_branch) => {
+                let then_block = self.new_block();
+                let join = self.new_block();
+                let else_block = match else_branch {
+                    Some(_) => self.new_block(),
+                    None => join,
+                };
+                self.terminate(Terminator::Branch(cond, then_block, else_block));
+
+                self.current = Some(then_block);
+                self.stmt(then_branch);
+                self.goto(join);
+                if let Some(else_branch) = else_branch {
+                    self.current = Some(else_block);
+                    self.stmt(else_branch);
+                    self.goto(join);
+                }
+                self.current = Some(join);
+            }
+            Stmt::While(cond, body, label) => {
+                let header = self.new_block();
+                let body_block = self.new_block();
+                let exit = self.new_block();
+                self.goto(header);
+                self.current = Some(header);
+                self.terminate(Terminator::Branch(cond, body_block, exit));
+
+                self.loops.push(Loop {
+                    label: label.as_ref(),
+                    header,
+                    exit,
+                });
+                self.current = Some(body_block);
+                self.stmt(body);
+                self.goto(header);
+                self.loops.pop();
+                self.current = Some(exit);
+            }
+            Stmt::Break(label) => {
+                let terminator = match self.find_loop(label) {
+                    Some(lp) => Terminator::Goto(lp.exit),
+                    None => Terminator::Unreachable,
+                };
+                self.terminate(terminator);
+            }
+            Stmt::Continue(label) => {
+                let terminator = match self.find_loop(label) {
+                    Some(lp) => Terminator::Goto(lp.header),
+                    None => Terminator::Unreachable,
+                };
+                self.terminate(terminator);
+            }
+            Stmt::Switch(scrutinee, arms, default) => {
+                let join = self.new_block();
+                let arm_blocks: Vec<_> = arms.iter().map(|_| self.new_block()).collect();
+                let default_block = match default {
+                    Some(_) => self.new_block(),
+                    None => join,
+                };
+                let targets = arms
+                    .iter()
+                    .zip(&arm_blocks)
+                    .map(|((value, _), block)| (value, *block))
+                    .collect();
+                self.terminate(Terminator::Switch(scrutinee, targets, default_block));
+
+                for ((_, arm), block) in arms.iter().zip(arm_blocks) {
+                    self.current = Some(block);
+                    self.stmt(arm);
+                    self.goto(join);
+                }
+                if let Some(default) = default {
+                    self.current = Some(default_block);
+                    self.stmt(default);
+                    self.goto(join);
+                }
+                self.current = Some(join);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InlineHint;
+    use crate::Type;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn function(body: Vec<Stmt>) -> Function {
+        Function {
+            name: sym("f"),
+            params: vec![(sym("c"), Type::Bool)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(body),
+        }
+    }
+
+    fn assign(name: &str, value: i64) -> Stmt {
+        Stmt::Assign(Expr::Var(sym(name)), Expr::Const(Constant::Int(value)))
+    }
+
+    #[test]
+    fn test_if_else() {
+        // let x: int;
+        // if c { x = 1; } else { x = 2; }
+        // return x;
+        let function = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, None),
+            Stmt::If(
+                Expr::Var(sym("c")),
+                Box::new(Stmt::Block(vec![assign("x", 1)])),
+                Some(Box::new(Stmt::Block(vec![assign("x", 2)]))),
+            ),
+            Stmt::Return(Some(Expr::Var(sym("x")))),
+        ]);
+        let cfg = build_cfg(&function);
+
+        // entry, then, join, else
+        assert_eq!(cfg.blocks().len(), 4);
+        assert_eq!(cfg.successors(Cfg::ENTRY), vec![BlockId(1), BlockId(3)]);
+        assert_eq!(cfg.successors(BlockId(1)), vec![BlockId(2)]);
+        assert_eq!(cfg.successors(BlockId(3)), vec![BlockId(2)]);
+        assert_eq!(
+            cfg.block(BlockId(2)).terminator,
+            Terminator::Return(Some(&Expr::Var(sym("x"))))
+        );
+        assert_eq!(cfg.reachable_blocks().len(), 4);
+    }
+
+    #[test]
+    fn test_code_after_return_is_unreachable() {
+        // return 1;
+        // x = 2;
+        let function = function(vec![
+            Stmt::Return(Some(Expr::Const(Constant::Int(1)))),
+            assign("x", 2),
+        ]);
+        let cfg = build_cfg(&function);
+
+        assert_eq!(cfg.blocks().len(), 2);
+        assert_eq!(cfg.block(BlockId(1)).stmts, vec![&assign("x", 2)]);
+        assert_eq!(cfg.reachable_blocks(), vec![Cfg::ENTRY]);
+    }
+
+    #[test]
+    fn test_while_break_and_continue() {
+        // while c { if c { break; } continue; }
+        let function = function(vec![Stmt::While(
+            Expr::Var(sym("c")),
+            Box::new(Stmt::Block(vec![
+                Stmt::If(Expr::Var(sym("c")), Box::new(Stmt::Break(None)), None),
+                Stmt::Continue(None),
+            ])),
+            None,
+        )]);
+        let cfg = build_cfg(&function);
+
+        let (header, body, exit) = (BlockId(1), BlockId(2), BlockId(3));
+        assert_eq!(cfg.successors(Cfg::ENTRY), vec![header]);
+        assert_eq!(cfg.successors(header), vec![body, exit]);
+        // The `break` jumps to the exit, the `continue` back to the header.
+        assert_eq!(cfg.successors(BlockId(4)), vec![exit]);
+        assert_eq!(cfg.successors(BlockId(5)), vec![header]);
+        assert_eq!(cfg.block(exit).terminator, Terminator::Return(None));
+    }
+}
@@ -0,0 +1,58 @@
+//! Built-in functions implemented directly by the backend.
+
+use crate::Symbol;
+use crate::Type;
+
+/// A built-in function, called through `Expr::Call` with `Intrinsic::symbol`.
+///
+/// Intrinsic symbols contain a `.`, which no source identifier can, so a user
+/// function named `assert` or `panic` never collides with them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intrinsic {
+    /// `assert(cond: bool)`, traps if `cond` is false.
+    Assert,
+    /// `panic(msg: string)`, prints `msg` and aborts.
+    Panic,
+}
+
+impl Intrinsic {
+    pub const ALL: [Intrinsic; 2] = [Intrinsic::Assert, Intrinsic::Panic];
+
+    /// The intrinsic a call to `name` refers to in source, if no user function
+    /// of that name exists.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "assert" => Some(Intrinsic::Assert),
+            "panic" => Some(Intrinsic::Panic),
+            _ => None,
+        }
+    }
+
+    /// The intrinsic called through `sym`, if any.
+    pub fn from_symbol(sym: &Symbol) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|intrinsic| intrinsic.symbol_name() == sym.0)
+    }
+
+    /// The symbol calls to this intrinsic use in the IR.
+    pub fn symbol(self) -> Symbol {
+        Symbol(self.symbol_name().to_string())
+    }
+
+    fn symbol_name(self) -> &'static str {
+        match self {
+            Intrinsic::Assert => "shizuku.assert",
+            Intrinsic::Panic => "shizuku.panic",
+        }
+    }
+
+    /// The `Type::Function` the type checker checks calls against.
+    pub fn signature(self) -> Type {
+        let param = match self {
+            Intrinsic::Assert => Type::Bool,
+            Intrinsic::Panic => Type::String,
+        };
+        Type::Function(vec![param], Box::new(Type::Void), false)
+    }
+}
@@ -3,28 +3,87 @@
 //! This module defines the core data structures used to represent
 //! the program in a language-independent way after parsing.
 
-use std::collections::HashMap;
+mod arith;
+mod builder;
+mod cfg;
+mod intrinsic;
+mod link;
+mod liveness;
+pub mod opt;
+mod parse;
+mod stats;
+mod temp;
+mod typeck;
+mod validate;
+
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+pub use arith::ArithError;
+pub use builder::FunctionBuilder;
+pub use cfg::BasicBlock;
+pub use cfg::BlockId;
+pub use cfg::Cfg;
+pub use cfg::Terminator;
+pub use cfg::build_cfg;
+pub use intrinsic::Intrinsic;
+pub use link::LinkError;
+pub use liveness::LivenessResult;
+pub use liveness::liveness;
+pub use parse::IrParseError;
+pub use parse::IrParseErrorType;
+pub use parse::parse_ir;
+pub use stats::ProgramStats;
+pub use temp::TempAllocator;
+pub use typeck::TypeCheckConfig;
+pub use typeck::TypeError;
+pub use typeck::Warning;
+pub use typeck::check_program;
+pub use typeck::check_program_with_config;
+pub use typeck::check_returns;
+pub use typeck::detect_unconditional_self_recursion;
+pub use typeck::unify;
+pub use validate::StructuralError;
 
 /// Unique identifier for variables and functions
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// Symbols order by their names.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Symbol(pub String);
 
 /// Supported primitive types
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
+    /// 64-bit signed integer, the default `int`
     Int,
+    Int8,
+    Int16,
+    Int32,
     Float,
     Bool,
     String,
     Void,
-    Function(Vec<Type>, Box<Type>), // Argument types and return type
-    Array(Box<Type>, usize),        // Element type and size
-    Struct(HashMap<Symbol, Type>),  // Field name to type mapping
+    /// Argument types, return type, and whether further arguments of any
+    /// type may follow, as in C's `printf`
+    Function(Vec<Type>, Box<Type>, bool),
+    Array(Box<Type>, usize),     // Element type and size
+    Struct(Vec<(Symbol, Type)>), // Fields in declaration order
+    /// Placeholder for a `let` without annotation, replaced by the type checker
+    Infer,
+    /// Type variable of a generic item, bound by `unify`
+    TypeVar(Symbol),
 }
 
 /// Constant values
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Equality and hashing compare floats by bit pattern (see `bit_eq`), so
+/// unlike `f64` a `NaN` equals itself and `0.0` differs from `-0.0`. This
+/// makes `Constant` usable as a map key, e.g. to find repeated expressions.
+/// Code that needs IEEE semantics, such as constant folding a comparison,
+/// must compare the inner `f64`s instead.
+#[derive(Debug, Clone)]
 pub enum Constant {
     Int(i64),
     Float(f64),
@@ -33,7 +92,7 @@ pub enum Constant {
 }
 
 /// Expressions in the IR
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Expr {
     /// Variable reference
     Var(Symbol),
@@ -41,8 +100,15 @@ pub enum Expr {
     Const(Constant),
     /// Binary operation
     BinOp(BinOp, Box<Expr>, Box<Expr>),
+    /// Unary operation
+    UnOp(UnOp, Box<Expr>),
+    /// Conversion of a numeric value to another numeric type, the type boxed
+    /// to keep `Expr` small
+    Cast(Box<Expr>, Box<Type>),
     /// Function call
     Call(Symbol, Vec<Expr>),
+    /// Call through a value of function type
+    CallIndirect(Box<Expr>, Vec<Expr>),
     /// Array access
     ArrayAccess(Box<Expr>, Box<Expr>),
     /// Field access
@@ -52,12 +118,13 @@ pub enum Expr {
 }
 
 /// Binary operators
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BinOp {
     Add,
     Sub,
     Mul,
     Div,
+    Mod,
     Eq,
     Neq,
     Lt,
@@ -68,8 +135,15 @@ pub enum BinOp {
     Or,
 }
 
+/// Unary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnOp {
+    /// Logical not, of a `bool` only
+    Not,
+}
+
 /// Statements in the IR
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Stmt {
     /// Variable declaration
     Declare(Symbol, Type, Option<Expr>),
@@ -83,8 +157,16 @@ pub enum Stmt {
     Block(Vec<Stmt>),
     /// If statement
     If(Expr, Box<Stmt>, Option<Box<Stmt>>),
-    /// While loop
-    While(Expr, Box<Stmt>),
+    /// While loop, with its optional label
+    While(Expr, Box<Stmt>, Option<Symbol>),
+    /// Exit the innermost loop, or the one with the given label
+    Break(Option<Symbol>),
+    /// Jump to the next iteration of the innermost loop, or the one with the given label
+    Continue(Option<Symbol>),
+    /// C-style switch on an integer: runs the arm whose constant equals the
+    /// scrutinee, or else the default, if any. Arms do not fall through, and
+    /// a `break` in one leaves the enclosing loop.
+    Switch(Expr, Vec<(Constant, Stmt)>, Option<Box<Stmt>>),
 }
 
 /// Function definition
@@ -93,14 +175,292 @@ pub struct Function {
     pub name: Symbol,
     pub params: Vec<(Symbol, Type)>,
     pub return_type: Type,
+    /// Declared with `extern` and defined outside the program, `body` is then
+    /// an empty block.
+    pub is_external: bool,
+    /// Accepts further arguments after `params`, see `Type::Function`.
+    pub is_variadic: bool,
+    /// Emitted as the `alwaysinline` or `noinline` LLVM attribute.
+    pub inline_hint: InlineHint,
     pub body: Stmt,
 }
 
+/// Whether calls to a function should be inlined, passed on to LLVM
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlineHint {
+    /// Left to the optimizer
+    #[default]
+    Default,
+    /// Inline at every call site, `#[inline]`
+    Always,
+    /// Never inline, `#[inline(never)]`
+    Never,
+}
+
 /// Complete program representation
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub functions: Vec<Function>,
-    pub globals: Vec<(Symbol, Type, Option<Constant>)>,
+    /// Name, type, initializer and whether the global may be assigned.
+    pub globals: Vec<(Symbol, Type, Option<Constant>, Mutability)>,
+    /// The type of an integer literal whose context gives it none, such as
+    /// the initializer of `let x = 1;`. Type checking gives every literal its
+    /// type and resets this to `int`.
+    pub default_int: Type,
+}
+
+impl Default for Program {
+    fn default() -> Self {
+        Self {
+            functions: Vec::new(),
+            globals: Vec::new(),
+            default_int: Type::Int,
+        }
+    }
+}
+
+/// Whether a global may be assigned after initialization
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mutability {
+    /// Declared with `let`
+    #[default]
+    Mutable,
+    /// Declared with `const`, only ever read
+    Const,
+}
+
+impl Constant {
+    /// The type of this constant.
+    pub fn ty(&self) -> Type {
+        match self {
+            Constant::Int(_) => Type::Int,
+            Constant::Float(_) => Type::Float,
+            Constant::Bool(_) => Type::Bool,
+            Constant::String(_) => Type::String,
+        }
+    }
+
+    /// Compares two constants, with floats compared by bit pattern.
+    ///
+    /// This is the equality `==` uses. It is reflexive, so a `NaN` equals
+    /// itself, and it tells `0.0` and `-0.0` apart.
+    pub fn bit_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constant::Int(a), Constant::Int(b)) => a == b,
+            (Constant::Float(a), Constant::Float(b)) => a.to_bits() == b.to_bits(),
+            (Constant::Bool(a), Constant::Bool(b)) => a == b,
+            (Constant::String(a), Constant::String(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Orders two constants of the same kind, or returns `None` for different
+    /// kinds.
+    ///
+    /// Floats are ordered by `f64::total_cmp`, so `-0.0 < 0.0` and a positive
+    /// `NaN` sorts above infinity. This agrees with `bit_eq`, which makes it
+    /// usable for sorting and ordered maps.
+    pub fn partial_cmp_total(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Constant::Int(a), Constant::Int(b)) => Some(a.cmp(b)),
+            (Constant::Float(a), Constant::Float(b)) => Some(a.total_cmp(b)),
+            (Constant::Bool(a), Constant::Bool(b)) => Some(a.cmp(b)),
+            (Constant::String(a), Constant::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+impl BinOp {
+    /// Binding power of the operator, higher binds tighter. Matches the
+    /// parser's table, so printed IR reads back into the same tree.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinOp::Or => 1,
+            BinOp::And => 2,
+            BinOp::Eq | BinOp::Neq | BinOp::Lt | BinOp::Gt | BinOp::Leq | BinOp::Geq => 3,
+            BinOp::Add | BinOp::Sub => 4,
+            BinOp::Mul | BinOp::Div | BinOp::Mod => 5,
+        }
+    }
+
+    /// Whether `a op b op c` groups as `(a op b) op c`. Every operator the
+    /// parser accepts is left-associative.
+    pub fn is_left_associative(&self) -> bool {
+        true
+    }
+
+    /// The operator as written in source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Eq => "==",
+            BinOp::Neq => "!=",
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::Leq => "<=",
+            BinOp::Geq => ">=",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+        }
+    }
+}
+
+impl UnOp {
+    /// The operator as written in source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnOp::Not => "!",
+        }
+    }
+}
+
+impl Expr {
+    /// Whether evaluating this expression has no side effects.
+    ///
+    /// Every call counts as impure, whatever its callee does.
+    pub fn is_pure(&self) -> bool {
+        match self {
+            Expr::Var(_) | Expr::Const(_) => true,
+            Expr::Call(..) | Expr::CallIndirect(..) => false,
+            Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+                lhs.is_pure() && rhs.is_pure()
+            }
+            Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+                operand.is_pure()
+            }
+            Expr::If(cond, then_expr, else_expr) => {
+                cond.is_pure() && then_expr.is_pure() && else_expr.is_pure()
+            }
+        }
+    }
+}
+
+impl Function {
+    /// The `Type::Function` of this function.
+    pub fn signature(&self) -> Type {
+        Type::Function(
+            self.params.iter().map(|(_, ty)| ty.clone()).collect(),
+            Box::new(self.return_type.clone()),
+            self.is_variadic,
+        )
+    }
+}
+
+impl Program {
+    /// An empty program, the same as `Program::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Size in bytes of a pointer on the target, used for strings and function values.
+const POINTER_SIZE: usize = 8;
+
+impl Type {
+    /// One of the signed integer types, `Int` or a narrower one.
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::Int | Type::Int8 | Type::Int16 | Type::Int32)
+    }
+
+    pub fn is_float(&self) -> bool {
+        *self == Type::Float
+    }
+
+    /// Whether `value` is in the range of this integer type. Always false
+    /// for a type other than an integer.
+    pub fn fits_int(&self, value: i64) -> bool {
+        match self {
+            Type::Int => true,
+            Type::Int32 => i32::try_from(value).is_ok(),
+            Type::Int16 => i16::try_from(value).is_ok(),
+            Type::Int8 => i8::try_from(value).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// A type arithmetic and ordering operators apply to.
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// A type made of other values, which codegen handles through memory.
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Type::Struct(_) | Type::Array(..))
+    }
+
+    /// A type whose values fit in a register: numbers, `bool`, and the
+    /// pointers behind strings and functions.
+    pub fn is_scalar(&self) -> bool {
+        self.is_numeric() || matches!(self, Type::Bool | Type::String | Type::Function(..))
+    }
+
+    /// Size in bytes of a value of this type, including trailing padding.
+    ///
+    /// Assumes a fixed 64-bit data layout until codegen queries the target.
+    /// Struct fields are laid out in declaration order.
+    pub fn size_of(&self) -> usize {
+        match self {
+            Type::Int | Type::Float => 8,
+            Type::Int8 | Type::Bool => 1,
+            Type::Int16 => 2,
+            Type::Int32 => 4,
+            Type::String | Type::Function(..) => POINTER_SIZE,
+            Type::Void => 0,
+            Type::Infer => panic!("size_of an uninferred type"),
+            Type::TypeVar(name) => panic!("size_of type variable `{}`", name.0),
+            Type::Array(elem, size) => elem.size_of() * size,
+            Type::Struct(fields) => {
+                let mut offset = 0;
+                for (_, ty) in fields {
+                    offset = align_to(offset, ty.align_of()) + ty.size_of();
+                }
+                align_to(offset, self.align_of())
+            }
+        }
+    }
+
+    /// Required alignment in bytes of a value of this type.
+    pub fn align_of(&self) -> usize {
+        match self {
+            Type::Array(elem, _) => elem.align_of(),
+            Type::Struct(fields) => fields
+                .iter()
+                .map(|(_, ty)| ty.align_of())
+                .max()
+                .unwrap_or(1),
+            Type::Void => 1,
+            ty => ty.size_of(),
+        }
+    }
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+    offset.next_multiple_of(align)
+}
+
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        self.bit_eq(other)
+    }
+}
+
+impl Eq for Constant {}
+
+impl Hash for Constant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Constant::Int(value) => value.hash(state),
+            Constant::Float(value) => value.to_bits().hash(state),
+            Constant::Bool(value) => value.hash(state),
+            Constant::String(value) => value.hash(state),
+        }
+    }
 }
 
 // Implement Display for better debugging
@@ -108,11 +468,16 @@ impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Type::Int => write!(f, "int"),
+            Type::Int8 => write!(f, "i8"),
+            Type::Int16 => write!(f, "i16"),
+            Type::Int32 => write!(f, "i32"),
             Type::Float => write!(f, "float"),
             Type::Bool => write!(f, "bool"),
             Type::String => write!(f, "string"),
             Type::Void => write!(f, "void"),
-            Type::Function(args, ret) => {
+            Type::Infer => write!(f, "_"),
+            Type::TypeVar(name) => write!(f, "{}", name.0),
+            Type::Function(args, ret, variadic) => {
                 write!(f, "fn(")?;
                 for (i, arg) in args.iter().enumerate() {
                     if i > 0 {
@@ -120,6 +485,9 @@ impl fmt::Display for Type {
                     }
                     write!(f, "{}", arg)?;
                 }
+                if *variadic {
+                    write!(f, "{}...", if args.is_empty() { "" } else { ", " })?;
+                }
                 write!(f, ") -> {}", ret)
             }
             Type::Array(elem, size) => write!(f, "[{}; {}]", elem, size),
@@ -137,12 +505,351 @@ impl fmt::Display for Type {
     }
 }
 
-// Implement Display for other types as needed...
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Int(value) => write!(f, "{}", value),
+            // Debug keeps the `.0` of whole floats.
+            Constant::Float(value) => write!(f, "{:?}", value),
+            Constant::Bool(value) => write!(f, "{}", value),
+            Constant::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// Prints the expression as source, with only the parentheses needed to keep
+/// the tree's shape under the operators' precedence and associativity.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Var(name) => write!(f, "{}", name.0),
+            Expr::Const(value) => write!(f, "{}", value),
+            Expr::BinOp(op, left, right) => {
+                let (left_min, right_min) = if op.is_left_associative() {
+                    (op.precedence(), op.precedence() + 1)
+                } else {
+                    (op.precedence() + 1, op.precedence())
+                };
+                fmt_operand(left, left_min, f)?;
+                write!(f, " {} ", op)?;
+                fmt_operand(right, right_min, f)
+            }
+            Expr::UnOp(op, operand) => {
+                write!(f, "{}", op)?;
+                fmt_operand(operand, UNARY_PRECEDENCE, f)
+            }
+            Expr::Cast(value, ty) => {
+                fmt_operand(value, CAST_PRECEDENCE, f)?;
+                write!(f, " as {}", ty)
+            }
+            Expr::Call(name, args) => {
+                write!(f, "{}", name.0)?;
+                fmt_args(args, f)
+            }
+            Expr::CallIndirect(callee, args) => {
+                // A bare name is parenthesized to tell it from a direct call.
+                match **callee {
+                    Expr::Var(_) => write!(f, "({})", callee)?,
+                    _ => fmt_operand(callee, u8::MAX, f)?,
+                }
+                fmt_args(args, f)
+            }
+            Expr::ArrayAccess(array, index) => {
+                fmt_operand(array, u8::MAX, f)?;
+                write!(f, "[{}]", index)
+            }
+            Expr::FieldAccess(object, field) => {
+                fmt_operand(object, u8::MAX, f)?;
+                write!(f, ".{}", field.0)
+            }
+            // The AST's ternary, the only expression form of a conditional.
+            Expr::If(cond, then_value, else_value) => {
+                fmt_operand(cond, 1, f)?;
+                write!(f, " ? {} : {}", then_value, else_value)
+            }
+        }
+    }
+}
+
+/// Binding power of a unary operator, tighter than any binary operator but
+/// looser than calls, indexing and field accesses.
+const UNARY_PRECEDENCE: u8 = u8::MAX - 1;
+
+/// Binding power of `as`, between the binary and the unary operators.
+const CAST_PRECEDENCE: u8 = 6;
+
+/// Binding power of `expr` as an operand; anything that is not an operator
+/// expression binds tightest.
+fn expr_precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::BinOp(op, ..) => op.precedence(),
+        Expr::UnOp(..) => UNARY_PRECEDENCE,
+        Expr::Cast(..) => CAST_PRECEDENCE,
+        Expr::If(..) => 0,
+        _ => u8::MAX,
+    }
+}
+
+/// Prints `expr`, parenthesized if it binds looser than `min_precedence`.
+fn fmt_operand(expr: &Expr, min_precedence: u8, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if expr_precedence(expr) < min_precedence {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+fn fmt_args(args: &[Expr], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", arg)?;
+    }
+    write!(f, ")")
+}
+
+const INDENT: &str = "    ";
+
+/// Prints the statement as source, with nested blocks indented.
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_stmt(self, 0, f)
+    }
+}
+
+/// Prints `stmt` without leading indentation, the lines of nested blocks
+/// indented one level past `depth`.
+fn fmt_stmt(stmt: &Stmt, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match stmt {
+        Stmt::Declare(name, ty, init) => {
+            write!(f, "let {}: {}", name.0, ty)?;
+            if let Some(init) = init {
+                write!(f, " = {}", init)?;
+            }
+            write!(f, ";")
+        }
+        Stmt::Assign(target, value) => write!(f, "{} = {};", target, value),
+        Stmt::Expr(expr) => write!(f, "{};", expr),
+        Stmt::Return(Some(value)) => write!(f, "return {};", value),
+        Stmt::Return(None) => write!(f, "return;"),
+        Stmt::Block(stmts) => {
+            writeln!(f, "{{")?;
+            for stmt in stmts {
+                write!(f, "{}", INDENT.repeat(depth + 1))?;
+                fmt_stmt(stmt, depth + 1, f)?;
+                writeln!(f)?;
+            }
+            write!(f, "{}}}", INDENT.repeat(depth))
+        }
+        Stmt::If(cond, then_branch, else_branch) => {
+            write!(f, "if {} ", cond)?;
+            fmt_stmt(then_branch, depth, f)?;
+            if let Some(else_branch) = else_branch {
+                write!(f, " else ")?;
+                fmt_stmt(else_branch, depth, f)?;
+            }
+            Ok(())
+        }
+        Stmt::While(cond, body, label) => {
+            if let Some(label) = label {
+                write!(f, "'{}: ", label.0)?;
+            }
+            write!(f, "while {} ", cond)?;
+            fmt_stmt(body, depth, f)
+        }
+        Stmt::Break(label) => {
+            write!(f, "break")?;
+            fmt_label(label, f)
+        }
+        Stmt::Continue(label) => {
+            write!(f, "continue")?;
+            fmt_label(label, f)
+        }
+        Stmt::Switch(scrutinee, arms, default) => {
+            writeln!(f, "switch {} {{", scrutinee)?;
+            let arms = arms.iter().map(|(value, arm)| (value.to_string(), arm));
+            let default = default.iter().map(|arm| ("default".to_string(), &**arm));
+            for (pattern, arm) in arms.chain(default) {
+                write!(f, "{}{} => ", INDENT.repeat(depth + 1), pattern)?;
+                fmt_stmt(arm, depth + 1, f)?;
+                writeln!(f)?;
+            }
+            write!(f, "{}}}", INDENT.repeat(depth))
+        }
+    }
+}
+
+fn fmt_label(label: &Option<Symbol>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match label {
+        Some(label) => write!(f, " '{};", label.0),
+        None => write!(f, ";"),
+    }
+}
+
+/// Prints the function as source, an external one as a declaration ending in
+/// `;`. The return type is always written, even `void`.
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inline_hint {
+            InlineHint::Default => {}
+            InlineHint::Always => write!(f, "#[inline] ")?,
+            InlineHint::Never => write!(f, "#[inline(never)] ")?,
+        }
+        if self.is_external {
+            write!(f, "extern ")?;
+        }
+        write!(f, "fn {}(", self.name.0)?;
+        for (i, (name, ty)) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", name.0, ty)?;
+        }
+        if self.is_variadic {
+            write!(f, "{}...", if self.params.is_empty() { "" } else { ", " })?;
+        }
+        write!(f, ") -> {}", self.return_type)?;
+        if self.is_external {
+            write!(f, ";")
+        } else {
+            write!(f, " {}", self.body)
+        }
+    }
+}
+
+/// Prints the program in the textual IR that `parse_ir` reads back: the
+/// globals as `let` or `const` declarations, then the functions, one blank
+/// line apart.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, ty, value, mutability) in &self.globals {
+            let keyword = match mutability {
+                Mutability::Mutable => "let",
+                Mutability::Const => "const",
+            };
+            write!(f, "{} {}: {}", keyword, name.0, ty)?;
+            if let Some(value) = value {
+                write!(f, " = {}", value)?;
+            }
+            writeln!(f, ";")?;
+        }
+        for (i, function) in self.functions.iter().enumerate() {
+            if i > 0 || !self.globals.is_empty() {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// `Expr` and `Stmt` make up every function body, so a variant that grows
+    /// them grows the whole tree. If this fails, box the new variant's payload
+    /// or, if the growth is deliberate, update the sizes here.
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_node_sizes() {
+        assert_eq!(std::mem::size_of::<Expr>(), 48);
+        assert_eq!(std::mem::size_of::<Stmt>(), 112);
+        assert_eq!(std::mem::size_of::<Type>(), 40);
+    }
+
+    #[test]
+    fn test_program_default_is_empty() {
+        let program = Program::default();
+
+        assert!(program.functions.is_empty());
+        assert!(program.globals.is_empty());
+        assert_eq!(Program::new(), program);
+    }
+
+    #[test]
+    fn test_expr_is_pure() {
+        let var = |name: &str| Box::new(Expr::Var(Symbol(name.to_string())));
+
+        // a + b * c
+        let arith = Expr::BinOp(
+            BinOp::Add,
+            var("a"),
+            Box::new(Expr::BinOp(BinOp::Mul, var("b"), var("c"))),
+        );
+        assert!(arith.is_pure());
+
+        // f(a) + b
+        let call = Expr::BinOp(
+            BinOp::Add,
+            Box::new(Expr::Call(Symbol("f".to_string()), vec![*var("a")])),
+            var("b"),
+        );
+        assert!(!call.is_pure());
+    }
+
+    #[test]
+    fn test_type_predicates() {
+        // (type, integer, float, numeric, aggregate, scalar)
+        let cases = [
+            (Type::Int, true, false, true, false, true),
+            (Type::Int8, true, false, true, false, true),
+            (Type::Int16, true, false, true, false, true),
+            (Type::Int32, true, false, true, false, true),
+            (Type::Float, false, true, true, false, true),
+            (Type::Bool, false, false, false, false, true),
+            (Type::String, false, false, false, false, true),
+            (Type::Void, false, false, false, false, false),
+            (
+                Type::Function(vec![], Box::new(Type::Void), false),
+                false,
+                false,
+                false,
+                false,
+                true,
+            ),
+            (
+                Type::Array(Box::new(Type::Int), 4),
+                false,
+                false,
+                false,
+                true,
+                false,
+            ),
+            (Type::Struct(vec![]), false, false, false, true, false),
+            (Type::Infer, false, false, false, false, false),
+            (
+                Type::TypeVar(Symbol("T".to_string())),
+                false,
+                false,
+                false,
+                false,
+                false,
+            ),
+        ];
+        for (ty, integer, float, numeric, aggregate, scalar) in cases {
+            assert_eq!(ty.is_integer(), integer, "{ty}");
+            assert_eq!(ty.is_float(), float, "{ty}");
+            assert_eq!(ty.is_numeric(), numeric, "{ty}");
+            assert_eq!(ty.is_aggregate(), aggregate, "{ty}");
+            assert_eq!(ty.is_scalar(), scalar, "{ty}");
+        }
+    }
+
     #[test]
     fn test_type_display() {
         assert_eq!(format!("{}", Type::Int), "int");
@@ -151,18 +858,124 @@ mod tests {
         assert_eq!(format!("{}", Type::String), "string");
         assert_eq!(format!("{}", Type::Void), "void");
 
-        let fn_type = Type::Function(vec![Type::Int, Type::Float], Box::new(Type::Bool));
+        let fn_type = Type::Function(vec![Type::Int, Type::Float], Box::new(Type::Bool), false);
         assert_eq!(format!("{}", fn_type), "fn(int, float) -> bool");
 
+        let fn_type = Type::Function(vec![Type::String], Box::new(Type::Int32), true);
+        assert_eq!(format!("{}", fn_type), "fn(string, ...) -> i32");
+
         let array_type = Type::Array(Box::new(Type::Int), 10);
         assert_eq!(format!("{}", array_type), "[int; 10]");
 
-        let mut fields = HashMap::new();
-        fields.insert(Symbol("x".to_string()), Type::Int);
-        fields.insert(Symbol("y".to_string()), Type::Float);
-        let struct_type = Type::Struct(fields);
-        assert!(format!("{}", struct_type).contains("x: int"));
-        assert!(format!("{}", struct_type).contains("y: float"));
+        let struct_type = Type::Struct(vec![
+            (Symbol("x".to_string()), Type::Int),
+            (Symbol("y".to_string()), Type::Float),
+        ]);
+        assert_eq!(format!("{}", struct_type), "struct { x: int, y: float }");
+    }
+
+    fn var(name: &str) -> Box<Expr> {
+        Box::new(Expr::Var(Symbol(name.to_string())))
+    }
+
+    #[test]
+    fn test_expr_display_minimal_parens() {
+        // (a - b) - c
+        let left = Expr::BinOp(
+            BinOp::Sub,
+            Box::new(Expr::BinOp(BinOp::Sub, var("a"), var("b"))),
+            var("c"),
+        );
+        assert_eq!(left.to_string(), "a - b - c");
+
+        // a - (b - c)
+        let right = Expr::BinOp(
+            BinOp::Sub,
+            var("a"),
+            Box::new(Expr::BinOp(BinOp::Sub, var("b"), var("c"))),
+        );
+        assert_eq!(right.to_string(), "a - (b - c)");
+
+        // (a + b) * c
+        let looser = Expr::BinOp(
+            BinOp::Mul,
+            Box::new(Expr::BinOp(BinOp::Add, var("a"), var("b"))),
+            var("c"),
+        );
+        assert_eq!(looser.to_string(), "(a + b) * c");
+
+        // a + b * c
+        let tighter = Expr::BinOp(
+            BinOp::Add,
+            var("a"),
+            Box::new(Expr::BinOp(BinOp::Mul, var("b"), var("c"))),
+        );
+        assert_eq!(tighter.to_string(), "a + b * c");
+    }
+
+    #[test]
+    fn test_expr_display_atoms() {
+        let call = Expr::Call(
+            Symbol("f".to_string()),
+            vec![
+                Expr::Const(Constant::Float(1.0)),
+                Expr::Const(Constant::String("hi".to_string())),
+            ],
+        );
+        assert_eq!(call.to_string(), "f(1.0, \"hi\")");
+
+        let field = Expr::FieldAccess(
+            Box::new(Expr::BinOp(BinOp::Add, var("a"), var("b"))),
+            Symbol("x".to_string()),
+        );
+        assert_eq!(field.to_string(), "(a + b).x");
+    }
+
+    #[test]
+    fn test_primitive_layout() {
+        assert_eq!(Type::Int.size_of(), 8);
+        assert_eq!(Type::Int8.size_of(), 1);
+        assert_eq!(Type::Int16.size_of(), 2);
+        assert_eq!(Type::Int32.size_of(), 4);
+        assert_eq!(Type::Float.size_of(), 8);
+        assert_eq!(Type::Bool.size_of(), 1);
+        assert_eq!(Type::Void.size_of(), 0);
+        assert_eq!(Type::Int32.align_of(), 4);
+        assert_eq!(Type::Void.align_of(), 1);
+    }
+
+    #[test]
+    fn test_array_layout() {
+        let array_type = Type::Array(Box::new(Type::Int32), 4);
+        assert_eq!(array_type.size_of(), 16);
+        assert_eq!(array_type.align_of(), 4);
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        let struct_type = Type::Struct(vec![
+            (Symbol("a".to_string()), Type::Int8),
+            (Symbol("b".to_string()), Type::Int),
+        ]);
+        assert_eq!(struct_type.size_of(), 16);
+        assert_eq!(struct_type.align_of(), 8);
+
+        // Trailing padding rounds the size up to the struct alignment.
+        let struct_type = Type::Struct(vec![
+            (Symbol("a".to_string()), Type::Int32),
+            (Symbol("b".to_string()), Type::Int8),
+        ]);
+        assert_eq!(struct_type.size_of(), 8);
+
+        // Fields keep their declaration order, so `b` is not moved before `a`.
+        let struct_type = Type::Struct(vec![
+            (Symbol("b".to_string()), Type::Int8),
+            (Symbol("a".to_string()), Type::Int),
+            (Symbol("c".to_string()), Type::Int8),
+        ]);
+        assert_eq!(struct_type.size_of(), 24);
+
+        assert_eq!(Type::Struct(vec![]).size_of(), 0);
     }
 
     #[test]
@@ -174,14 +987,74 @@ mod tests {
         assert_ne!(c1, c3);
     }
 
+    #[test]
+    fn test_float_constant_equality() {
+        assert_eq!(Constant::Float(f64::NAN), Constant::Float(f64::NAN));
+        assert_ne!(Constant::Float(0.0), Constant::Float(-0.0));
+        assert_eq!(Constant::Float(1.5), Constant::Float(1.5));
+    }
+
+    #[test]
+    fn test_float_constant_bit_eq() {
+        let nan = Constant::Float(f64::NAN);
+        assert!(nan.bit_eq(&nan));
+        assert!(!Constant::Float(0.0).bit_eq(&Constant::Float(-0.0)));
+        assert!(Constant::Float(1.5).bit_eq(&Constant::Float(1.5)));
+        assert!(!Constant::Float(1.0).bit_eq(&Constant::Int(1)));
+    }
+
+    #[test]
+    fn test_float_constant_total_order() {
+        let cmp = |a: f64, b: f64| Constant::Float(a).partial_cmp_total(&Constant::Float(b));
+
+        assert_eq!(cmp(f64::NAN, f64::NAN), Some(Ordering::Equal));
+        assert_eq!(cmp(f64::NAN, f64::INFINITY), Some(Ordering::Greater));
+        assert_eq!(cmp(-0.0, 0.0), Some(Ordering::Less));
+        assert_eq!(cmp(1.0, 2.0), Some(Ordering::Less));
+        assert_eq!(
+            Constant::Float(1.0).partial_cmp_total(&Constant::Int(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_expr_as_map_key() {
+        use shizuku_common::dmap;
+        use std::hash::BuildHasher;
+
+        let product = || {
+            Expr::BinOp(
+                BinOp::Mul,
+                Box::new(Expr::Var(Symbol("a".to_string()))),
+                Box::new(Expr::Const(Constant::Float(2.0))),
+            )
+        };
+
+        let state = dmap::DeterministicState;
+        assert_eq!(state.hash_one(product()), state.hash_one(product()));
+
+        let mut seen = dmap::new();
+        seen.insert(product(), 1);
+        assert_eq!(seen.get(&product()), Some(&1));
+    }
+
+    #[test]
+    fn test_struct_field_order_is_part_of_type() {
+        let field = |name: &str| (Symbol(name.to_string()), Type::Int);
+        let a = Type::Struct(vec![field("x"), field("y")]);
+        let b = Type::Struct(vec![field("y"), field("x")]);
+
+        assert_ne!(a, b);
+    }
+
     #[test]
     fn test_expr_construction() {
         let var_expr = Expr::Var(Symbol("x".to_string()));
         let const_expr = Expr::Const(Constant::Int(42));
         let binop_expr = Expr::BinOp(
             BinOp::Add,
-            Box::new(Expr::Var(Symbol("x".to_string()))),
-            Box::new(Expr::Const(Constant::Int(1))),
+            Box::new(var_expr.clone()),
+            Box::new(const_expr.clone()),
         );
 
         match binop_expr {
@@ -203,6 +1076,9 @@ mod tests {
                 (Symbol("b".to_string()), Type::Int),
             ],
             return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
             body: Stmt::Block(vec![Stmt::Return(Some(Expr::BinOp(
                 BinOp::Add,
                 Box::new(Expr::Var(Symbol("a".to_string()))),
@@ -218,13 +1094,22 @@ mod tests {
     #[test]
     fn test_program_structure() {
         let program = Program {
-            globals: vec![(Symbol("x".to_string()), Type::Int, Some(Constant::Int(42)))],
+            globals: vec![(
+                Symbol("x".to_string()),
+                Type::Int,
+                Some(Constant::Int(42)),
+                Mutability::Mutable,
+            )],
             functions: vec![Function {
                 name: Symbol("main".to_string()),
                 params: vec![],
                 return_type: Type::Void,
+                is_external: false,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
                 body: Stmt::Block(vec![]),
             }],
+            default_int: Type::Int,
         };
 
         assert_eq!(program.globals.len(), 1);
@@ -244,6 +1129,7 @@ mod tests {
         let while_stmt = Stmt::While(
             Expr::Const(Constant::Bool(true)),
             Box::new(Stmt::Block(vec![])),
+            None,
         );
 
         match if_stmt {
@@ -255,7 +1141,7 @@ mod tests {
         }
 
         match while_stmt {
-            Stmt::While(cond, body) => {
+            Stmt::While(cond, body, _) => {
                 assert_eq!(cond, Expr::Const(Constant::Bool(true)));
                 match *body {
                     Stmt::Block(stmts) => assert!(stmts.is_empty()),
@@ -0,0 +1,212 @@
+//! Combining the programs of several modules into one.
+
+use crate::Function;
+use crate::Program;
+use crate::Symbol;
+use shizuku_common::dmap::DHashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkError {
+    /// A function or global defined by both programs.
+    DuplicateSymbol(Symbol),
+    /// A function declared by one program with a signature other than the
+    /// one it is declared or defined with by the other.
+    SignatureMismatch(Symbol),
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkError::DuplicateSymbol(sym) => {
+                write!(f, "symbol `{}` is defined in more than one module", sym.0)
+            }
+            LinkError::SignatureMismatch(sym) => write!(
+                f,
+                "function `{}` is declared with different signatures",
+                sym.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl Program {
+    /// Appends the functions and globals of `other` to those of `self`.
+    ///
+    /// Functions and globals share one namespace, so a name defined in both
+    /// programs is an error, whichever kind of item each definition is. An
+    /// `extern` function may be declared by both programs, or declared by
+    /// one and defined by the other, if the signatures agree, and is kept
+    /// once, as the definition if there is one. Items keep their order,
+    /// those of `self` first, so merging the same programs always gives the
+    /// same result. Duplicates within one program are left to `validate`.
+    pub fn merge(mut self, other: Program) -> Result<Program, LinkError> {
+        let globals: DHashSet<&Symbol> = self.globals.iter().map(|(name, ..)| name).collect();
+        if let Some((name, ..)) = other
+            .globals
+            .iter()
+            .find(|(name, ..)| globals.contains(name) || self.function(name).is_some())
+        {
+            return Err(LinkError::DuplicateSymbol(name.clone()));
+        }
+
+        let mut functions = Vec::with_capacity(other.functions.len());
+        for function in other.functions {
+            if globals.contains(&function.name) {
+                return Err(LinkError::DuplicateSymbol(function.name));
+            }
+            let Some(index) = self
+                .functions
+                .iter()
+                .position(|existing| existing.name == function.name)
+            else {
+                functions.push(function);
+                continue;
+            };
+            let existing = &self.functions[index];
+            if !existing.is_external && !function.is_external {
+                return Err(LinkError::DuplicateSymbol(function.name));
+            }
+            if existing.signature() != function.signature() {
+                return Err(LinkError::SignatureMismatch(function.name));
+            }
+            if existing.is_external && !function.is_external {
+                self.functions.remove(index);
+                functions.push(function);
+            }
+        }
+
+        self.functions.extend(functions);
+        self.globals.extend(other.globals);
+        Ok(self)
+    }
+
+    fn function(&self, name: &Symbol) -> Option<&Function> {
+        self.functions
+            .iter()
+            .find(|function| function.name == *name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InlineHint;
+    use crate::Mutability;
+    use crate::Stmt;
+    use crate::Type;
+
+    fn function(name: &str) -> Function {
+        Function {
+            name: Symbol(name.to_string()),
+            params: vec![],
+            return_type: Type::Void,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        }
+    }
+
+    fn program(functions: &[&str], globals: &[&str]) -> Program {
+        Program {
+            functions: functions.iter().map(|name| function(name)).collect(),
+            globals: globals
+                .iter()
+                .map(|name| {
+                    (
+                        Symbol(name.to_string()),
+                        Type::Int,
+                        None,
+                        Mutability::Mutable,
+                    )
+                })
+                .collect(),
+            default_int: Type::Int,
+        }
+    }
+
+    #[test]
+    fn test_merge_distinct() {
+        let merged = program(&["f"], &["x"])
+            .merge(program(&["g", "h"], &["y"]))
+            .unwrap();
+
+        assert_eq!(merged, program(&["f", "g", "h"], &["x", "y"]));
+    }
+
+    #[test]
+    fn test_merge_duplicate_function() {
+        let result = program(&["f", "main"], &[]).merge(program(&["main"], &[]));
+
+        assert_eq!(
+            result,
+            Err(LinkError::DuplicateSymbol(Symbol("main".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_merge_function_colliding_with_global() {
+        let result = program(&[], &["x"]).merge(program(&["x"], &[]));
+
+        assert_eq!(
+            result,
+            Err(LinkError::DuplicateSymbol(Symbol("x".to_string())))
+        );
+    }
+
+    fn printf() -> Function {
+        Function {
+            name: Symbol("printf".to_string()),
+            params: vec![(Symbol("fmt".to_string()), Type::String)],
+            return_type: Type::Int32,
+            is_external: true,
+            is_variadic: true,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        }
+    }
+
+    #[test]
+    fn test_merge_shared_extern() {
+        let mut a = program(&["f"], &[]);
+        a.functions.push(printf());
+        let mut b = program(&["g"], &[]);
+        b.functions.push(printf());
+
+        let merged = a.merge(b).unwrap();
+
+        let names: Vec<_> = merged.functions.iter().map(|f| &f.name.0[..]).collect();
+        assert_eq!(names, ["f", "printf", "g"]);
+    }
+
+    #[test]
+    fn test_merge_extern_with_definition() {
+        let mut prototype = function("helper");
+        prototype.is_external = true;
+        let mut a = program(&["main"], &[]);
+        a.functions.push(prototype);
+
+        let merged = a.merge(program(&["helper"], &[])).unwrap();
+
+        assert_eq!(merged, program(&["main", "helper"], &[]));
+    }
+
+    #[test]
+    fn test_merge_extern_signature_mismatch() {
+        let mut prototype = function("helper");
+        prototype.is_external = true;
+        prototype.return_type = Type::Int;
+        let mut b = program(&[], &[]);
+        b.functions.push(prototype);
+
+        let result = program(&["helper"], &[]).merge(b);
+
+        assert_eq!(
+            result,
+            Err(LinkError::SignatureMismatch(Symbol("helper".to_string())))
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Which variables may still be read, per block of a `Cfg`.
+
+use crate::BlockId;
+use crate::Cfg;
+use crate::Expr;
+use crate::Stmt;
+use crate::Symbol;
+use crate::Terminator;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashSet;
+
+/// The variables live on entry to and exit from each block of a `Cfg`.
+///
+/// A variable is live at a point if some path from there reads it before
+/// assigning it. Globals are tracked like locals, so a pass removing dead
+/// stores must still keep the ones to globals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LivenessResult {
+    live_in: Vec<DHashSet<Symbol>>,
+    live_out: Vec<DHashSet<Symbol>>,
+}
+
+impl LivenessResult {
+    pub fn live_in(&self, block: BlockId) -> &DHashSet<Symbol> {
+        &self.live_in[block.0]
+    }
+
+    pub fn live_out(&self, block: BlockId) -> &DHashSet<Symbol> {
+        &self.live_out[block.0]
+    }
+
+    /// The variables live right after statement `index` of `block`, so a
+    /// store that statement makes to a variable not in the set is dead.
+    pub fn live_after(&self, cfg: &Cfg, block: BlockId, index: usize) -> DHashSet<Symbol> {
+        let block_ref = cfg.block(block);
+        let mut live = self.live_out(block).clone();
+        terminator_uses(&block_ref.terminator, &mut live);
+        for stmt in block_ref.stmts[index + 1..].iter().rev() {
+            transfer(stmt, &mut live);
+        }
+        live
+    }
+}
+
+/// Computes the live variables of every block of `cfg` by iterating the
+/// backward dataflow equations to a fixpoint.
+pub fn liveness(cfg: &Cfg) -> LivenessResult {
+    let count = cfg.blocks().len();
+    let mut result = LivenessResult {
+        live_in: vec![dmap::new_set(); count],
+        live_out: vec![dmap::new_set(); count],
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        // Blocks mostly jump forward, so going backward converges faster.
+        for index in (0..count).rev() {
+            let block = BlockId(index);
+            let mut live: DHashSet<Symbol> = dmap::new_set();
+            for successor in cfg.successors(block) {
+                live.extend(result.live_in(successor).iter().cloned());
+            }
+            if live != result.live_out[index] {
+                result.live_out[index] = live.clone();
+                changed = true;
+            }
+
+            let block_ref = cfg.block(block);
+            terminator_uses(&block_ref.terminator, &mut live);
+            for stmt in block_ref.stmts.iter().rev() {
+                transfer(stmt, &mut live);
+            }
+            if live != result.live_in[index] {
+                result.live_in[index] = live;
+                changed = true;
+            }
+        }
+    }
+    result
+}
+
+fn terminator_uses(terminator: &Terminator, live: &mut DHashSet<Symbol>) {
+    match terminator {
+        Terminator::Branch(expr, _, _) | Terminator::Switch(expr, _, _) => uses(expr, live),
+        Terminator::Return(Some(expr)) => uses(expr, live),
+        Terminator::Goto(_) | Terminator::Return(None) | Terminator::Unreachable => {}
+    }
+}
+
+/// Turns the variables live after `stmt` into those live before it.
+fn transfer(stmt: &Stmt, live: &mut DHashSet<Symbol>) {
+    match stmt {
+        Stmt::Declare(sym, _, init) => {
+            live.remove(sym);
+            if let Some(init) = init {
+                uses(init, live);
+            }
+        }
+        Stmt::Assign(Expr::Var(sym), value) => {
+            live.remove(sym);
+            uses(value, live);
+        }
+        // Storing to a field or element keeps the rest of the variable.
+        Stmt::Assign(target, value) => {
+            uses(target, live);
+            uses(value, live);
+        }
+        Stmt::Expr(expr) => uses(expr, live),
+        // A `Cfg` holds no control flow in its statements.
+        _ => {}
+    }
+}
+
+fn uses(expr: &Expr, live: &mut DHashSet<Symbol>) {
+    match expr {
+        Expr::Var(sym) => {
+            live.insert(sym.clone());
+        }
+        Expr::Const(_) => {}
+        Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+            uses(operand, live)
+        }
+        Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+            uses(lhs, live);
+            uses(rhs, live);
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| uses(arg, live)),
+        Expr::CallIndirect(callee, args) => {
+            uses(callee, live);
+            args.iter().for_each(|arg| uses(arg, live));
+        }
+        Expr::If(cond, then_value, else_value) => {
+            uses(cond, live);
+            uses(then_value, live);
+            uses(else_value, live);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinOp;
+    use crate::Constant;
+    use crate::Function;
+    use crate::InlineHint;
+    use crate::Type;
+    use crate::build_cfg;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn int(value: i64) -> Expr {
+        Expr::Const(Constant::Int(value))
+    }
+
+    fn function(body: Vec<Stmt>) -> Function {
+        Function {
+            name: sym("f"),
+            params: vec![(sym("c"), Type::Bool)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(body),
+        }
+    }
+
+    #[test]
+    fn test_overwritten_value_is_dead() {
+        // let x = 1; x = 2; return x;
+        let function = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(1))),
+            Stmt::Assign(Expr::Var(sym("x")), int(2)),
+            Stmt::Return(Some(Expr::Var(sym("x")))),
+        ]);
+        let cfg = build_cfg(&function);
+        let result = liveness(&cfg);
+
+        assert!(!result.live_after(&cfg, Cfg::ENTRY, 0).contains(&sym("x")));
+        assert!(result.live_after(&cfg, Cfg::ENTRY, 1).contains(&sym("x")));
+        assert!(result.live_in(Cfg::ENTRY).is_empty());
+    }
+
+    #[test]
+    fn test_read_value_is_live() {
+        // let x = 1; return x;
+        let function = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(1))),
+            Stmt::Return(Some(Expr::Var(sym("x")))),
+        ]);
+        let cfg = build_cfg(&function);
+        let result = liveness(&cfg);
+
+        assert!(result.live_after(&cfg, Cfg::ENTRY, 0).contains(&sym("x")));
+    }
+
+    #[test]
+    fn test_live_around_loop() {
+        // let x = 0; while c { x = x + 1; } return x;
+        let function = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(0))),
+            Stmt::While(
+                Expr::Var(sym("c")),
+                Box::new(Stmt::Assign(
+                    Expr::Var(sym("x")),
+                    Expr::BinOp(BinOp::Add, Box::new(Expr::Var(sym("x"))), Box::new(int(1))),
+                )),
+                None,
+            ),
+            Stmt::Return(Some(Expr::Var(sym("x")))),
+        ]);
+        let cfg = build_cfg(&function);
+        let result = liveness(&cfg);
+
+        // The loop header, reached again from the body, needs `c` and `x`.
+        let header = BlockId(1);
+        let expected: DHashSet<Symbol> = [sym("c"), sym("x")].into_iter().collect();
+        assert_eq!(result.live_in(header), &expected);
+        assert_eq!(
+            result.live_in(Cfg::ENTRY),
+            &[sym("c")].into_iter().collect()
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Common-subexpression elimination within straight-line runs of statements.
+
+use crate::BinOp;
+use crate::Expr;
+use crate::Function;
+use crate::Stmt;
+use crate::Symbol;
+use crate::TempAllocator;
+use crate::Type;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashMap;
+
+/// Computes each pure expression repeated within a straight-line run of
+/// statements only once, storing it in a temporary declared before its first
+/// use.
+///
+/// Temporaries are declared with `Type::Infer`, so the pass runs before
+/// `check_program`. Expressions containing a call are never shared, and a
+/// statement with a call ends the run since the callee may write globals.
+/// Only subexpressions that are always evaluated are considered, so nothing
+/// is hoisted out of the branches of an `if` or the right side of `and`/`or`.
+pub fn cse(function: &mut Function) {
+    let mut pass = Cse {
+        temps: TempAllocator::for_function(function),
+    };
+    pass.stmt(&mut function.body);
+}
+
+struct Cse {
+    temps: TempAllocator,
+}
+
+impl Cse {
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Block(stmts) => self.block(stmts),
+            Stmt::If(_, then_branch, else_branch) => {
+                self.stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            Stmt::While(_, body, _) => self.stmt(body),
+            Stmt::Switch(_, arms, default) => {
+                for (_, arm) in arms {
+                    self.stmt(arm);
+                }
+                if let Some(default) = default {
+                    self.stmt(default);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn block(&mut self, stmts: &mut Vec<Stmt>) {
+        let mut start = 0;
+        while start < stmts.len() {
+            let mut end = start;
+            while end < stmts.len() && !is_control_flow(&stmts[end]) {
+                end += 1;
+            }
+            end = self.straight_line(stmts, start, end);
+            if end < stmts.len() {
+                self.stmt(&mut stmts[end]);
+            }
+            start = end + 1;
+        }
+    }
+
+    /// Eliminates repeated expressions in `stmts[start..end]`, returning the
+    /// end of the run after temporaries have been inserted.
+    fn straight_line(&mut self, stmts: &mut Vec<Stmt>, start: usize, mut end: usize) -> usize {
+        while let Some((expr, uses)) = find_repeated(&stmts[start..end]) {
+            let temp = self.temps.fresh();
+
+            let first = start + uses[0];
+            stmts.insert(
+                first,
+                Stmt::Declare(temp.clone(), Type::Infer, Some(expr.clone())),
+            );
+            end += 1;
+            for index in uses {
+                for_each_read_mut(&mut stmts[start + index + 1], &mut |e| {
+                    replace_evaluated(e, &expr, &temp)
+                });
+            }
+        }
+        end
+    }
+}
+
+fn is_control_flow(stmt: &Stmt) -> bool {
+    matches!(
+        stmt,
+        Stmt::Block(_) | Stmt::If(..) | Stmt::While(..) | Stmt::Switch(..)
+    )
+}
+
+/// Finds the largest expression evaluated more than once in a run of simple
+/// statements without an intervening write to a variable it reads.
+///
+/// Returns the expression and the sorted, deduplicated indices of the
+/// statements that evaluate it.
+fn find_repeated(stmts: &[Stmt]) -> Option<(Expr, Vec<usize>)> {
+    let mut best: Option<(Expr, Vec<usize>)> = None;
+    let mut consider = |expr: Expr, uses: Vec<usize>| {
+        if uses.len() < 2 {
+            return;
+        }
+        let better = match &best {
+            Some((current, current_uses)) => {
+                (size(&expr), std::cmp::Reverse(uses[0]))
+                    > (size(current), std::cmp::Reverse(current_uses[0]))
+            }
+            None => true,
+        };
+        if better {
+            best = Some((expr, uses));
+        }
+    };
+
+    let mut live: DHashMap<Expr, Vec<usize>> = dmap::new();
+    for (index, stmt) in stmts.iter().enumerate() {
+        let mut has_call = false;
+        for_each_read(stmt, &mut |expr| has_call |= !expr.is_pure());
+        if has_call {
+            for (expr, uses) in live.drain() {
+                consider(expr, uses);
+            }
+            continue;
+        }
+
+        for_each_read(stmt, &mut |expr| {
+            for_each_evaluated(expr, &mut |sub| {
+                if is_candidate(sub) {
+                    live.entry(sub.clone()).or_default().push(index);
+                }
+            })
+        });
+
+        if let Some(written) = written(stmt) {
+            let killed: Vec<Expr> = live
+                .keys()
+                .filter(|expr| reads(expr, written))
+                .cloned()
+                .collect();
+            for expr in killed {
+                let uses = live.remove(&expr).unwrap();
+                consider(expr, uses);
+            }
+        }
+    }
+    for (expr, uses) in live {
+        consider(expr, uses);
+    }
+
+    best.map(|(expr, mut uses)| {
+        uses.dedup();
+        (expr, uses)
+    })
+}
+
+/// Calls `f` on the expressions a simple statement evaluates.
+fn for_each_read(stmt: &Stmt, f: &mut impl FnMut(&Expr)) {
+    match stmt {
+        Stmt::Declare(_, _, Some(expr)) | Stmt::Expr(expr) | Stmt::Return(Some(expr)) => f(expr),
+        Stmt::Assign(_, value) => f(value),
+        _ => {}
+    }
+}
+
+fn for_each_read_mut(stmt: &mut Stmt, f: &mut impl FnMut(&mut Expr)) {
+    match stmt {
+        Stmt::Declare(_, _, Some(expr)) | Stmt::Expr(expr) | Stmt::Return(Some(expr)) => f(expr),
+        Stmt::Assign(_, value) => f(value),
+        _ => {}
+    }
+}
+
+/// The variable whose value a simple statement changes, if any.
+fn written(stmt: &Stmt) -> Option<&Symbol> {
+    match stmt {
+        Stmt::Declare(sym, _, _) => Some(sym),
+        Stmt::Assign(target, _) => root(target),
+        _ => None,
+    }
+}
+
+fn root(target: &Expr) -> Option<&Symbol> {
+    match target {
+        Expr::Var(sym) => Some(sym),
+        Expr::ArrayAccess(base, _) | Expr::FieldAccess(base, _) => root(base),
+        _ => None,
+    }
+}
+
+fn is_candidate(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::BinOp(..) | Expr::ArrayAccess(..) | Expr::FieldAccess(..)
+    )
+}
+
+/// Calls `f` on `expr` and every subexpression evaluated whenever `expr` is,
+/// innermost first.
+fn for_each_evaluated(expr: &Expr, f: &mut impl FnMut(&Expr)) {
+    match expr {
+        Expr::Var(_) | Expr::Const(_) => {}
+        Expr::BinOp(BinOp::And | BinOp::Or, lhs, _) => for_each_evaluated(lhs, f),
+        Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+            for_each_evaluated(lhs, f);
+            for_each_evaluated(rhs, f);
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| for_each_evaluated(arg, f)),
+        Expr::CallIndirect(callee, args) => {
+            for_each_evaluated(callee, f);
+            args.iter().for_each(|arg| for_each_evaluated(arg, f));
+        }
+        Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+            for_each_evaluated(operand, f)
+        }
+        Expr::If(cond, _, _) => for_each_evaluated(cond, f),
+    }
+    f(expr);
+}
+
+/// Replaces the outermost evaluated occurrences of `target` in `expr`.
+fn replace_evaluated(expr: &mut Expr, target: &Expr, temp: &Symbol) {
+    if expr == target {
+        *expr = Expr::Var(temp.clone());
+        return;
+    }
+    match expr {
+        Expr::Var(_) | Expr::Const(_) => {}
+        Expr::BinOp(BinOp::And | BinOp::Or, lhs, _) => replace_evaluated(lhs, target, temp),
+        Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+            replace_evaluated(lhs, target, temp);
+            replace_evaluated(rhs, target, temp);
+        }
+        Expr::Call(_, args) => args
+            .iter_mut()
+            .for_each(|arg| replace_evaluated(arg, target, temp)),
+        Expr::CallIndirect(callee, args) => {
+            replace_evaluated(callee, target, temp);
+            args.iter_mut()
+                .for_each(|arg| replace_evaluated(arg, target, temp));
+        }
+        Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+            replace_evaluated(operand, target, temp)
+        }
+        Expr::If(cond, _, _) => replace_evaluated(cond, target, temp),
+    }
+}
+
+fn reads(expr: &Expr, sym: &Symbol) -> bool {
+    match expr {
+        Expr::Var(var) => var == sym,
+        Expr::Const(_) => false,
+        Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+            reads(lhs, sym) || reads(rhs, sym)
+        }
+        Expr::Call(_, args) => args.iter().any(|arg| reads(arg, sym)),
+        Expr::CallIndirect(callee, args) => {
+            reads(callee, sym) || args.iter().any(|arg| reads(arg, sym))
+        }
+        Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+            reads(operand, sym)
+        }
+        Expr::If(cond, then_expr, else_expr) => {
+            reads(cond, sym) || reads(then_expr, sym) || reads(else_expr, sym)
+        }
+    }
+}
+
+/// Number of nodes in `expr`.
+fn size(expr: &Expr) -> usize {
+    let mut size = 0;
+    for_each_evaluated(expr, &mut |_| size += 1);
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Constant;
+    use crate::InlineHint;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(sym(name))
+    }
+
+    fn mul(lhs: Expr, rhs: Expr) -> Expr {
+        Expr::BinOp(BinOp::Mul, Box::new(lhs), Box::new(rhs))
+    }
+
+    fn add(lhs: Expr, rhs: Expr) -> Expr {
+        Expr::BinOp(BinOp::Add, Box::new(lhs), Box::new(rhs))
+    }
+
+    fn function(body: Vec<Stmt>) -> Function {
+        Function {
+            name: sym("f"),
+            params: vec![(sym("a"), Type::Int), (sym("b"), Type::Int)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(body),
+        }
+    }
+
+    #[test]
+    fn test_repeated_expression() {
+        // return a * b + a * b;
+        let mut f = function(vec![Stmt::Return(Some(add(
+            mul(var("a"), var("b")),
+            mul(var("a"), var("b")),
+        )))]);
+        cse(&mut f);
+
+        // let t = a * b;
+        // return t + t;
+        assert_eq!(
+            f.body,
+            Stmt::Block(vec![
+                Stmt::Declare(sym("__tmp0"), Type::Infer, Some(mul(var("a"), var("b")))),
+                Stmt::Return(Some(add(var("__tmp0"), var("__tmp0")))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_repeated_across_statements() {
+        // let x = a * b;
+        // return a * b + 1;
+        let one = Expr::Const(Constant::Int(1));
+        let mut f = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(mul(var("a"), var("b")))),
+            Stmt::Return(Some(add(mul(var("a"), var("b")), one.clone()))),
+        ]);
+        cse(&mut f);
+
+        assert_eq!(
+            f.body,
+            Stmt::Block(vec![
+                Stmt::Declare(sym("__tmp0"), Type::Infer, Some(mul(var("a"), var("b")))),
+                Stmt::Declare(sym("x"), Type::Int, Some(var("__tmp0"))),
+                Stmt::Return(Some(add(var("__tmp0"), one))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_calls_are_not_shared() {
+        // return g(a) + g(a);
+        let call = || Expr::Call(sym("g"), vec![var("a")]);
+        let mut f = function(vec![Stmt::Return(Some(add(call(), call())))]);
+        let expected = f.body.clone();
+        cse(&mut f);
+
+        assert_eq!(f.body, expected);
+    }
+
+    #[test]
+    fn test_assignment_invalidates() {
+        // let x = a * b;
+        // a = 1;
+        // return a * b;
+        let mut f = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(mul(var("a"), var("b")))),
+            Stmt::Assign(var("a"), Expr::Const(Constant::Int(1))),
+            Stmt::Return(Some(mul(var("a"), var("b")))),
+        ]);
+        let expected = f.body.clone();
+        cse(&mut f);
+
+        assert_eq!(f.body, expected);
+    }
+
+    #[test]
+    fn test_conditional_operand_not_hoisted() {
+        // return a * b and a * b;
+        let mut f = function(vec![Stmt::Return(Some(Expr::BinOp(
+            BinOp::And,
+            Box::new(mul(var("a"), var("b"))),
+            Box::new(mul(var("a"), var("b"))),
+        )))]);
+        let expected = f.body.clone();
+        cse(&mut f);
+
+        assert_eq!(f.body, expected);
+    }
+}
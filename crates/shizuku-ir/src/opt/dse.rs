@@ -0,0 +1,309 @@
+//! Dead-store elimination.
+
+use crate::BlockId;
+use crate::Expr;
+use crate::Function;
+use crate::Stmt;
+use crate::Symbol;
+use crate::Type;
+use crate::build_cfg;
+use crate::liveness;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashMap;
+use shizuku_common::dmap::DHashSet;
+
+/// Removes assignments, and drops `let` initializers, whose value is never
+/// read before the variable is assigned again or goes out of scope.
+///
+/// Stores whose value contains a call are kept for its side effects, and so
+/// are initializers of `Type::Infer` declarations, which the type checker
+/// needs. Liveness works on names, so only variables declared exactly once
+/// in the function, and not also as a parameter, are considered, and only
+/// stores within the scope of that declaration. A store to a global of the
+/// same name before the `let` is thus kept.
+pub fn dead_store_elimination(function: &mut Function) {
+    let dead = dead_stores(function);
+    if !dead.is_empty() {
+        remove(&mut function.body, &dead);
+    }
+}
+
+/// The addresses of the dead stores in `function`'s body.
+fn dead_stores(function: &Function) -> Vec<*const Stmt> {
+    let mut declarations: DHashMap<&Symbol, usize> = dmap::new();
+    for (name, _) in &function.params {
+        *declarations.entry(name).or_default() += 1;
+    }
+    count_declarations(&function.body, &mut declarations);
+    let locals: DHashSet<&Symbol> = declarations
+        .into_iter()
+        .filter(|&(_, count)| count == 1)
+        .map(|(name, _)| name)
+        .collect();
+    let mut scoped = dmap::new_set();
+    collect_scoped_stores(&function.body, &mut dmap::new_set(), &mut scoped);
+
+    let cfg = build_cfg(function);
+    let liveness = liveness(&cfg);
+    let mut dead = Vec::new();
+    for (index, block) in cfg.blocks().iter().enumerate() {
+        for (position, stmt) in block.stmts.iter().enumerate() {
+            let (sym, value) = match stmt {
+                Stmt::Assign(Expr::Var(sym), value) => (sym, value),
+                Stmt::Declare(sym, ty, Some(value)) if *ty != Type::Infer => (sym, value),
+                _ => continue,
+            };
+            if locals.contains(sym)
+                && scoped.contains(&(*stmt as *const Stmt))
+                && value.is_pure()
+                && !liveness
+                    .live_after(&cfg, BlockId(index), position)
+                    .contains(sym)
+            {
+                dead.push(*stmt as *const Stmt);
+            }
+        }
+    }
+    dead
+}
+
+fn count_declarations<'a>(stmt: &'a Stmt, declarations: &mut DHashMap<&'a Symbol, usize>) {
+    match stmt {
+        Stmt::Declare(name, _, _) => *declarations.entry(name).or_default() += 1,
+        Stmt::Block(stmts) => {
+            for stmt in stmts {
+                count_declarations(stmt, declarations);
+            }
+        }
+        Stmt::If(_, then_branch, else_branch) => {
+            count_declarations(then_branch, declarations);
+            if let Some(else_branch) = else_branch {
+                count_declarations(else_branch, declarations);
+            }
+        }
+        Stmt::While(_, body, _) => count_declarations(body, declarations),
+        Stmt::Switch(_, arms, default) => {
+            for (_, arm) in arms {
+                count_declarations(arm, declarations);
+            }
+            if let Some(default) = default {
+                count_declarations(default, declarations);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collects the addresses of the declarations in `stmt`, and of the
+/// assignments to variables declared in an enclosing scope before them.
+fn collect_scoped_stores<'a>(
+    stmt: &'a Stmt,
+    in_scope: &mut DHashSet<&'a Symbol>,
+    scoped: &mut DHashSet<*const Stmt>,
+) {
+    match stmt {
+        Stmt::Declare(name, _, _) => {
+            in_scope.insert(name);
+            scoped.insert(stmt);
+        }
+        Stmt::Assign(Expr::Var(sym), _) => {
+            if in_scope.contains(sym) {
+                scoped.insert(stmt);
+            }
+        }
+        Stmt::Block(stmts) => {
+            let mut inner = in_scope.clone();
+            for stmt in stmts {
+                collect_scoped_stores(stmt, &mut inner, scoped);
+            }
+        }
+        Stmt::If(_, then_branch, else_branch) => {
+            collect_scoped_stores(then_branch, &mut in_scope.clone(), scoped);
+            if let Some(else_branch) = else_branch {
+                collect_scoped_stores(else_branch, &mut in_scope.clone(), scoped);
+            }
+        }
+        Stmt::While(_, body, _) => collect_scoped_stores(body, &mut in_scope.clone(), scoped),
+        Stmt::Switch(_, arms, default) => {
+            for (_, arm) in arms {
+                collect_scoped_stores(arm, &mut in_scope.clone(), scoped);
+            }
+            if let Some(default) = default {
+                collect_scoped_stores(default, &mut in_scope.clone(), scoped);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Removes the statements at the addresses in `dead`, which must not have
+/// moved since they were collected.
+fn remove(stmt: &mut Stmt, dead: &[*const Stmt]) {
+    let is_dead = |stmt: &Stmt| dead.iter().any(|&ptr| std::ptr::eq(ptr, stmt));
+    let stmt_is_dead = is_dead(stmt);
+    match stmt {
+        Stmt::Block(stmts) => {
+            // Check every address before `retain` moves anything.
+            let keep: Vec<bool> = stmts
+                .iter()
+                .map(|stmt| !(matches!(stmt, Stmt::Assign(..)) && is_dead(stmt)))
+                .collect();
+            for stmt in stmts.iter_mut() {
+                remove(stmt, dead);
+            }
+            let mut keep = keep.into_iter();
+            stmts.retain(|_| keep.next().unwrap_or(true));
+        }
+        Stmt::Declare(_, _, init) => {
+            if stmt_is_dead {
+                *init = None;
+            }
+        }
+        Stmt::If(_, then_branch, else_branch) => {
+            remove(then_branch, dead);
+            if let Some(else_branch) = else_branch {
+                remove(else_branch, dead);
+            }
+        }
+        Stmt::While(_, body, _) => remove(body, dead),
+        Stmt::Switch(_, arms, default) => {
+            for (_, arm) in arms {
+                remove(arm, dead);
+            }
+            if let Some(default) = default {
+                remove(default, dead);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinOp;
+    use crate::Constant;
+    use crate::InlineHint;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(sym(name))
+    }
+
+    fn int(value: i64) -> Expr {
+        Expr::Const(Constant::Int(value))
+    }
+
+    /// `x > 0`
+    fn positive() -> Expr {
+        Expr::BinOp(BinOp::Gt, Box::new(var("x")), Box::new(int(0)))
+    }
+
+    fn function(body: Vec<Stmt>) -> Function {
+        Function {
+            name: sym("f"),
+            params: vec![],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(body),
+        }
+    }
+
+    #[test]
+    fn test_overwritten_store() {
+        // let x: int = 1; x = 2; return x;
+        let mut f = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(1))),
+            Stmt::Assign(var("x"), int(2)),
+            Stmt::Return(Some(var("x"))),
+        ]);
+        dead_store_elimination(&mut f);
+
+        assert_eq!(
+            f.body,
+            Stmt::Block(vec![
+                Stmt::Declare(sym("x"), Type::Int, None),
+                Stmt::Assign(var("x"), int(2)),
+                Stmt::Return(Some(var("x"))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unread_assignment() {
+        // let x: int = 1; if x > 0 { x = 2; } return 0;
+        let mut f = function(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(1))),
+            Stmt::If(
+                positive(),
+                Box::new(Stmt::Block(vec![Stmt::Assign(var("x"), int(2))])),
+                None,
+            ),
+            Stmt::Return(Some(int(0))),
+        ]);
+        dead_store_elimination(&mut f);
+
+        assert_eq!(
+            f.body,
+            Stmt::Block(vec![
+                Stmt::Declare(sym("x"), Type::Int, Some(int(1))),
+                Stmt::If(positive(), Box::new(Stmt::Block(vec![])), None),
+                Stmt::Return(Some(int(0))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_store_with_call_is_kept() {
+        // let x: int = 1; x = g(); x = 2; return x;
+        let body = vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(1))),
+            Stmt::Assign(var("x"), Expr::Call(sym("g"), vec![])),
+            Stmt::Assign(var("x"), int(2)),
+            Stmt::Return(Some(var("x"))),
+        ];
+        let mut f = function(body.clone());
+        dead_store_elimination(&mut f);
+
+        let mut expected = body;
+        expected[0] = Stmt::Declare(sym("x"), Type::Int, None);
+        assert_eq!(f.body, Stmt::Block(expected));
+    }
+
+    #[test]
+    fn test_global_store_before_local_is_kept() {
+        // g = 5; f(); let g: int = 1; return g;
+        let body = vec![
+            Stmt::Assign(var("g"), int(5)),
+            Stmt::Expr(Expr::Call(sym("f"), vec![])),
+            Stmt::Declare(sym("g"), Type::Int, Some(int(1))),
+            Stmt::Return(Some(var("g"))),
+        ];
+        let mut f = function(body.clone());
+        dead_store_elimination(&mut f);
+
+        assert_eq!(f.body, Stmt::Block(body));
+    }
+
+    #[test]
+    fn test_shadowed_variable_is_kept() {
+        // let x: int = 1; { let x: int = 2; g(x); } return x;
+        let body = vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(1))),
+            Stmt::Block(vec![
+                Stmt::Declare(sym("x"), Type::Int, Some(int(2))),
+                Stmt::Expr(Expr::Call(sym("g"), vec![var("x")])),
+            ]),
+            Stmt::Return(Some(var("x"))),
+        ];
+        let mut f = function(body.clone());
+        dead_store_elimination(&mut f);
+
+        assert_eq!(f.body, Stmt::Block(body));
+    }
+}
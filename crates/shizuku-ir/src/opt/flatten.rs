@@ -0,0 +1,122 @@
+//! Removal of redundant scope levels.
+
+use crate::Stmt;
+use crate::Symbol;
+
+/// Merges every block that is the sole statement of another block into its
+/// parent, e.g. `{ { a; } }` becomes `{ a; }`.
+///
+/// The merge is skipped when the inner block declares a name that is also
+/// declared in an enclosing block, so shadowing keeps its meaning even if a
+/// later pass moves statements around the merged scope.
+pub fn flatten_blocks(stmt: &mut Stmt) {
+    flatten(stmt, &mut Vec::new());
+}
+
+fn flatten(stmt: &mut Stmt, enclosing: &mut Vec<Symbol>) {
+    match stmt {
+        Stmt::Block(stmts) => {
+            while let [Stmt::Block(inner)] = stmts.as_mut_slice() {
+                if declares_any(inner, enclosing) {
+                    break;
+                }
+                *stmts = std::mem::take(inner);
+            }
+
+            let depth = enclosing.len();
+            enclosing.extend(declared(stmts).cloned());
+            for stmt in stmts.iter_mut() {
+                flatten(stmt, enclosing);
+            }
+            enclosing.truncate(depth);
+        }
+        Stmt::If(_, then_branch, else_branch) => {
+            flatten(then_branch, enclosing);
+            if let Some(else_branch) = else_branch {
+                flatten(else_branch, enclosing);
+            }
+        }
+        Stmt::While(_, body, _) => flatten(body, enclosing),
+        Stmt::Switch(_, arms, default) => {
+            for (_, arm) in arms {
+                flatten(arm, enclosing);
+            }
+            if let Some(default) = default {
+                flatten(default, enclosing);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn declared(stmts: &[Stmt]) -> impl Iterator<Item = &Symbol> {
+    stmts.iter().filter_map(|stmt| match stmt {
+        Stmt::Declare(name, _, _) => Some(name),
+        _ => None,
+    })
+}
+
+fn declares_any(stmts: &[Stmt], names: &[Symbol]) -> bool {
+    declared(stmts).any(|name| names.contains(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Constant;
+    use crate::Expr;
+    use crate::Type;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn declare(name: &str, value: i64) -> Stmt {
+        Stmt::Declare(
+            sym(name),
+            Type::Int,
+            Some(Expr::Const(Constant::Int(value))),
+        )
+    }
+
+    #[test]
+    fn test_flatten_nested_empty_block() {
+        let mut stmt = Stmt::Block(vec![Stmt::Block(vec![Stmt::Block(vec![])])]);
+        flatten_blocks(&mut stmt);
+
+        assert_eq!(stmt, Stmt::Block(vec![]));
+    }
+
+    #[test]
+    fn test_flatten_inside_branch() {
+        // while a { { let x = 1; } }
+        let mut stmt = Stmt::Block(vec![Stmt::While(
+            Expr::Var(sym("a")),
+            Box::new(Stmt::Block(vec![Stmt::Block(vec![declare("x", 1)])])),
+            None,
+        )]);
+        flatten_blocks(&mut stmt);
+
+        assert_eq!(
+            stmt,
+            Stmt::Block(vec![Stmt::While(
+                Expr::Var(sym("a")),
+                Box::new(Stmt::Block(vec![declare("x", 1)])),
+                None,
+            )])
+        );
+    }
+
+    #[test]
+    fn test_shadowing_block_is_kept() {
+        // let x = 1; { { let x = 2; } }
+        let mut stmt = Stmt::Block(vec![
+            declare("x", 1),
+            Stmt::Block(vec![Stmt::Block(vec![declare("x", 2)])]),
+        ]);
+        let expected = stmt.clone();
+        flatten_blocks(&mut stmt);
+
+        assert_eq!(stmt, expected);
+    }
+}
@@ -0,0 +1,189 @@
+//! Constant folding of operators and conditional expressions.
+
+use crate::Constant;
+use crate::Expr;
+use crate::Function;
+use crate::Stmt;
+
+/// Replaces every operation on constants with its result, and every
+/// conditional expression with a constant condition with the chosen branch.
+///
+/// Operations are evaluated by `Constant::binop` and `Constant::unop`. One it rejects, such as a
+/// division by zero that would trap at run time, is left alone.
+pub fn fold_constants(function: &mut Function) {
+    fold_stmt(&mut function.body);
+}
+
+fn fold_stmt(stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Declare(_, _, init) => {
+            if let Some(init) = init {
+                fold_expr(init);
+            }
+        }
+        Stmt::Assign(target, value) => {
+            fold_expr(target);
+            fold_expr(value);
+        }
+        Stmt::Expr(expr) => fold_expr(expr),
+        Stmt::Return(value) => {
+            if let Some(value) = value {
+                fold_expr(value);
+            }
+        }
+        Stmt::Block(stmts) => stmts.iter_mut().for_each(fold_stmt),
+        Stmt::If(cond, then_branch, else_branch) => {
+            fold_expr(cond);
+            fold_stmt(then_branch);
+            if let Some(else_branch) = else_branch {
+                fold_stmt(else_branch);
+            }
+        }
+        Stmt::While(cond, body, _) => {
+            fold_expr(cond);
+            fold_stmt(body);
+        }
+        Stmt::Switch(scrutinee, arms, default) => {
+            fold_expr(scrutinee);
+            arms.iter_mut().for_each(|(_, arm)| fold_stmt(arm));
+            if let Some(default) = default {
+                fold_stmt(default);
+            }
+        }
+        Stmt::Break(_) | Stmt::Continue(_) => {}
+    }
+}
+
+pub(crate) fn fold_expr(expr: &mut Expr) {
+    match expr {
+        Expr::Var(_) | Expr::Const(_) => {}
+        Expr::BinOp(op, lhs, rhs) => {
+            fold_expr(lhs);
+            fold_expr(rhs);
+            if let (Expr::Const(lhs), Expr::Const(rhs)) = (&**lhs, &**rhs)
+                && let Ok(value) = lhs.binop(*op, rhs)
+            {
+                *expr = Expr::Const(value);
+            }
+        }
+        Expr::UnOp(op, operand) => {
+            fold_expr(operand);
+            if let Expr::Const(operand) = &**operand
+                && let Ok(value) = operand.unop(*op)
+            {
+                *expr = Expr::Const(value);
+            }
+        }
+        Expr::Call(_, args) => args.iter_mut().for_each(fold_expr),
+        Expr::CallIndirect(callee, args) => {
+            fold_expr(callee);
+            args.iter_mut().for_each(fold_expr);
+        }
+        Expr::ArrayAccess(array, index) => {
+            fold_expr(array);
+            fold_expr(index);
+        }
+        Expr::FieldAccess(object, _) | Expr::Cast(object, _) => fold_expr(object),
+        Expr::If(cond, then_expr, else_expr) => {
+            fold_expr(cond);
+            fold_expr(then_expr);
+            fold_expr(else_expr);
+            if let Expr::Const(Constant::Bool(cond)) = **cond {
+                let chosen = if cond { then_expr } else { else_expr };
+                *expr = std::mem::replace(&mut **chosen, Expr::Const(Constant::Bool(false)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinOp;
+    use crate::InlineHint;
+    use crate::Symbol;
+    use crate::Type;
+    use crate::UnOp;
+
+    fn int(value: i64) -> Box<Expr> {
+        Box::new(Expr::Const(Constant::Int(value)))
+    }
+
+    fn string(value: &str) -> Box<Expr> {
+        Box::new(Expr::Const(Constant::String(value.to_string())))
+    }
+
+    fn folded(expr: Expr) -> Expr {
+        let mut function = Function {
+            name: Symbol("f".to_string()),
+            params: vec![],
+            return_type: Type::Void,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![Stmt::Expr(expr)]),
+        };
+        fold_constants(&mut function);
+        let Stmt::Block(mut body) = function.body else {
+            unreachable!()
+        };
+        let Some(Stmt::Expr(expr)) = body.pop() else {
+            unreachable!()
+        };
+        expr
+    }
+
+    #[test]
+    fn test_fold_string_concatenation() {
+        let expr = Expr::BinOp(BinOp::Add, string("ab"), string("cd"));
+
+        assert_eq!(folded(expr), *string("abcd"));
+    }
+
+    #[test]
+    fn test_fold_string_equality() {
+        let eq = Expr::BinOp(BinOp::Eq, string("a"), string("a"));
+        let neq = Expr::BinOp(BinOp::Neq, string("a"), string("a"));
+
+        assert_eq!(folded(eq), Expr::Const(Constant::Bool(true)));
+        assert_eq!(folded(neq), Expr::Const(Constant::Bool(false)));
+    }
+
+    #[test]
+    fn test_fold_nested_arithmetic() {
+        // (1 + 2) * 4
+        let expr = Expr::BinOp(
+            BinOp::Mul,
+            Box::new(Expr::BinOp(BinOp::Add, int(1), int(2))),
+            int(4),
+        );
+
+        assert_eq!(folded(expr), *int(12));
+    }
+
+    #[test]
+    fn test_division_by_zero_is_not_folded() {
+        let expr = Expr::BinOp(BinOp::Div, int(1), int(0));
+
+        assert_eq!(folded(expr.clone()), expr);
+    }
+
+    #[test]
+    fn test_fold_not() {
+        let expr = Expr::UnOp(UnOp::Not, Box::new(Expr::BinOp(BinOp::Lt, int(1), int(2))));
+
+        assert_eq!(folded(expr), Expr::Const(Constant::Bool(false)));
+    }
+
+    #[test]
+    fn test_fold_conditional() {
+        // 1 < 2 ? x : 0
+        let expr = Expr::If(
+            Box::new(Expr::BinOp(BinOp::Lt, int(1), int(2))),
+            Box::new(Expr::Var(Symbol("x".to_string()))),
+            int(0),
+        );
+
+        assert_eq!(folded(expr), Expr::Var(Symbol("x".to_string())));
+    }
+}
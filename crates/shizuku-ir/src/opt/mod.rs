@@ -0,0 +1,14 @@
+//! Optimization passes over the IR.
+
+mod cse;
+mod dse;
+mod flatten;
+mod fold;
+mod propagate;
+
+pub use cse::cse;
+pub use dse::dead_store_elimination;
+pub use flatten::flatten_blocks;
+pub use fold::fold_constants;
+pub(crate) use fold::fold_expr;
+pub use propagate::constant_propagation;
@@ -0,0 +1,271 @@
+//! Constant propagation of `let`-bound constants.
+
+use crate::Constant;
+use crate::Expr;
+use crate::Function;
+use crate::Stmt;
+use crate::Symbol;
+use crate::Type;
+use crate::opt::fold_constants;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashMap;
+use shizuku_common::dmap::DHashSet;
+
+/// Replaces reads of a variable declared with a constant initializer by that
+/// constant, then folds the function with `fold_constants`.
+///
+/// A binding ends at the next store to the variable, or at a nested statement
+/// that may store to it, and is not seen outside the block declaring it. The
+/// IR has no address-of operator, so a variable cannot change behind the
+/// pass's back. A declaration is only propagated when its type is that of
+/// the constant, or `Type::Infer`, so types seen by `check_program` stay the
+/// same.
+pub fn constant_propagation(function: &mut Function) {
+    propagate(&mut function.body, &mut dmap::new());
+    fold_constants(function);
+}
+
+fn propagate(stmt: &mut Stmt, known: &mut DHashMap<Symbol, Constant>) {
+    match stmt {
+        Stmt::Declare(sym, ty, init) => {
+            if let Some(init) = init {
+                substitute(init, known);
+            }
+            known.remove(sym);
+            if let Some(Expr::Const(value)) = init
+                && (*ty == Type::Infer || *ty == value.ty())
+            {
+                known.insert(sym.clone(), value.clone());
+            }
+        }
+        Stmt::Assign(target, value) => {
+            substitute(value, known);
+            substitute_indices(target, known);
+            if let Some(sym) = root(target) {
+                known.remove(sym);
+            }
+        }
+        Stmt::Expr(expr) => substitute(expr, known),
+        Stmt::Return(value) => {
+            if let Some(value) = value {
+                substitute(value, known);
+            }
+        }
+        Stmt::Block(stmts) => {
+            let mut inner = known.clone();
+            for stmt in stmts.iter_mut() {
+                propagate(stmt, &mut inner);
+            }
+        }
+        Stmt::If(cond, then_branch, else_branch) => {
+            substitute(cond, known);
+            propagate(then_branch, &mut known.clone());
+            if let Some(else_branch) = else_branch {
+                propagate(else_branch, &mut known.clone());
+            }
+        }
+        Stmt::While(cond, body, _) => {
+            // The condition and body run again after the body's stores.
+            forget_stored(body, known);
+            substitute(cond, known);
+            propagate(body, &mut known.clone());
+        }
+        Stmt::Switch(scrutinee, arms, default) => {
+            substitute(scrutinee, known);
+            for (_, arm) in arms {
+                propagate(arm, &mut known.clone());
+            }
+            if let Some(default) = default {
+                propagate(default, &mut known.clone());
+            }
+        }
+        Stmt::Break(_) | Stmt::Continue(_) => {}
+    }
+    if matches!(
+        stmt,
+        Stmt::Block(_) | Stmt::If(..) | Stmt::While(..) | Stmt::Switch(..)
+    ) {
+        forget_stored(stmt, known);
+    }
+}
+
+/// Removes from `known` every variable `stmt` may store to.
+fn forget_stored(stmt: &Stmt, known: &mut DHashMap<Symbol, Constant>) {
+    let mut stored = dmap::new_set();
+    collect_stored(stmt, &mut stored);
+    known.retain(|sym, _| !stored.contains(sym));
+}
+
+fn collect_stored(stmt: &Stmt, stored: &mut DHashSet<Symbol>) {
+    match stmt {
+        Stmt::Assign(target, _) => {
+            if let Some(sym) = root(target) {
+                stored.insert(sym.clone());
+            }
+        }
+        Stmt::Block(stmts) => stmts.iter().for_each(|stmt| collect_stored(stmt, stored)),
+        Stmt::If(_, then_branch, else_branch) => {
+            collect_stored(then_branch, stored);
+            if let Some(else_branch) = else_branch {
+                collect_stored(else_branch, stored);
+            }
+        }
+        Stmt::While(_, body, _) => collect_stored(body, stored),
+        Stmt::Switch(_, arms, default) => {
+            arms.iter().for_each(|(_, arm)| collect_stored(arm, stored));
+            if let Some(default) = default {
+                collect_stored(default, stored);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn root(target: &Expr) -> Option<&Symbol> {
+    match target {
+        Expr::Var(sym) => Some(sym),
+        Expr::ArrayAccess(base, _) | Expr::FieldAccess(base, _) => root(base),
+        _ => None,
+    }
+}
+
+/// Substitutes into the indices of an assignment target, leaving the
+/// variable stored to alone.
+fn substitute_indices(target: &mut Expr, known: &DHashMap<Symbol, Constant>) {
+    match target {
+        Expr::ArrayAccess(base, index) => {
+            substitute_indices(base, known);
+            substitute(index, known);
+        }
+        Expr::FieldAccess(base, _) => substitute_indices(base, known),
+        _ => {}
+    }
+}
+
+fn substitute(expr: &mut Expr, known: &DHashMap<Symbol, Constant>) {
+    match expr {
+        Expr::Var(sym) => {
+            if let Some(value) = known.get(sym) {
+                *expr = Expr::Const(value.clone());
+            }
+        }
+        Expr::Const(_) => {}
+        Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+            substitute(lhs, known);
+            substitute(rhs, known);
+        }
+        Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+            substitute(operand, known)
+        }
+        Expr::Call(_, args) => args.iter_mut().for_each(|arg| substitute(arg, known)),
+        Expr::CallIndirect(callee, args) => {
+            substitute(callee, known);
+            args.iter_mut().for_each(|arg| substitute(arg, known));
+        }
+        Expr::If(cond, then_value, else_value) => {
+            substitute(cond, known);
+            substitute(then_value, known);
+            substitute(else_value, known);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinOp;
+    use crate::InlineHint;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(sym(name))
+    }
+
+    fn int(value: i64) -> Expr {
+        Expr::Const(Constant::Int(value))
+    }
+
+    fn add(lhs: Expr, rhs: Expr) -> Expr {
+        Expr::BinOp(BinOp::Add, Box::new(lhs), Box::new(rhs))
+    }
+
+    fn function(body: Vec<Stmt>) -> Function {
+        Function {
+            name: sym("f"),
+            params: vec![(sym("c"), Type::Bool)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(body),
+        }
+    }
+
+    #[test]
+    fn test_propagate_and_fold() {
+        // let x = 2; return x + 3;
+        let mut f = function(vec![
+            Stmt::Declare(sym("x"), Type::Infer, Some(int(2))),
+            Stmt::Return(Some(add(var("x"), int(3)))),
+        ]);
+        constant_propagation(&mut f);
+
+        assert_eq!(
+            f.body,
+            Stmt::Block(vec![
+                Stmt::Declare(sym("x"), Type::Infer, Some(int(2))),
+                Stmt::Return(Some(int(5))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_reassignment_ends_binding() {
+        // let x: int = 2; x = g(); return x + 3;
+        let body = vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(2))),
+            Stmt::Assign(var("x"), Expr::Call(sym("g"), vec![])),
+            Stmt::Return(Some(add(var("x"), int(3)))),
+        ];
+        let mut f = function(body.clone());
+        constant_propagation(&mut f);
+
+        assert_eq!(f.body, Stmt::Block(body));
+    }
+
+    #[test]
+    fn test_store_in_loop_ends_binding() {
+        // let x: int = 0; while c { g(x); x = x + 1; }
+        let body = vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(int(0))),
+            Stmt::While(
+                var("c"),
+                Box::new(Stmt::Block(vec![
+                    Stmt::Expr(Expr::Call(sym("g"), vec![var("x")])),
+                    Stmt::Assign(var("x"), add(var("x"), int(1))),
+                ])),
+                None,
+            ),
+        ];
+        let mut f = function(body.clone());
+        constant_propagation(&mut f);
+
+        assert_eq!(f.body, Stmt::Block(body));
+    }
+
+    #[test]
+    fn test_mismatched_type_is_not_propagated() {
+        // let x: i32 = 2; return x;
+        let body = vec![
+            Stmt::Declare(sym("x"), Type::Int32, Some(int(2))),
+            Stmt::Return(Some(var("x"))),
+        ];
+        let mut f = function(body.clone());
+        constant_propagation(&mut f);
+
+        assert_eq!(f.body, Stmt::Block(body));
+    }
+}
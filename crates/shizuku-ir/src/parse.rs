@@ -0,0 +1,932 @@
+//! Reading back the textual IR printed by `Program`'s `Display`.
+//!
+//! The format is the printed one and nothing more, so hand-written fixtures
+//! use the same syntax: globals as `let name: type = constant;`, functions as
+//! `fn name(param: type, ...) -> type { ... }`, and `extern fn ...;` for
+//! declarations. Whitespace is insignificant.
+//!
+//! Besides the statement keywords, the operators `and`, `or` and `as` and the
+//! float constants `inf` and `NaN`, as `Debug` prints them, are reserved and
+//! cannot be used as names.
+
+use crate::BinOp;
+use crate::Constant;
+use crate::Expr;
+use crate::Function;
+use crate::InlineHint;
+use crate::Mutability;
+use crate::Program;
+use crate::Stmt;
+use crate::Symbol;
+use crate::Type;
+use crate::UnOp;
+use shizuku_common::span::SrcSpan;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrParseErrorType {
+    /// A character that starts no token.
+    UnexpectedChar(char),
+    UnterminatedString,
+    /// A backslash escape `Debug` never prints.
+    InvalidEscape,
+    /// A number literal out of range or malformed.
+    InvalidNumber(String),
+    /// A token other than the one the grammar requires here.
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+    },
+    /// A global initializer or switch case that is not a constant.
+    ExpectedConstant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrParseError {
+    pub error: IrParseErrorType,
+    pub location: SrcSpan,
+}
+
+impl fmt::Display for IrParseErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IrParseErrorType::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            IrParseErrorType::UnterminatedString => write!(f, "unterminated string"),
+            IrParseErrorType::InvalidEscape => write!(f, "invalid escape in string"),
+            IrParseErrorType::InvalidNumber(text) => write!(f, "invalid number `{}`", text),
+            IrParseErrorType::UnexpectedToken { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            IrParseErrorType::ExpectedConstant => write!(f, "expected a constant"),
+        }
+    }
+}
+
+impl fmt::Display for IrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.error, self.location.start, self.location.end
+        )
+    }
+}
+
+impl std::error::Error for IrParseError {}
+
+/// Parses textual IR, the inverse of `Program`'s `Display`.
+///
+/// Printing a program and parsing the text gives back an equal program,
+/// except that the payload of a `NaN` is not kept.
+pub fn parse_ir(text: &str) -> Result<Program, IrParseError> {
+    let tokens = tokenize(text)?;
+    IrParser { tokens, pos: 0 }.parse_program()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    /// The literal as written, converted once the parser knows its sign.
+    Number(String),
+    Str(String),
+    /// `'name`, without the quote.
+    Label(String),
+    Punct(&'static str),
+    Eof,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Ident(name) => write!(f, "`{}`", name),
+            Tok::Number(text) => write!(f, "`{}`", text),
+            Tok::Str(value) => write!(f, "{:?}", value),
+            Tok::Label(name) => write!(f, "`'{}`", name),
+            Tok::Punct(punct) => write!(f, "`{}`", punct),
+            Tok::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// Punctuation, longest first so that `==` is not read as two `=`.
+const PUNCTUATION: &[&str] = &[
+    "...", "==", "=>", "!=", "<=", ">=", "->", "+", "-", "*", "/", "%", "<", ">", "=", "!", "(",
+    ")", "{", "}", "[", "]", ",", ";", ":", ".", "?", "#",
+];
+
+fn tokenize(text: &str) -> Result<Vec<(Tok, SrcSpan)>, IrParseError> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut pos = 0;
+    let span = |start: usize, end: usize| SrcSpan {
+        start: start as u32,
+        end: end as u32,
+    };
+    let error = |error, start, end| IrParseError {
+        error,
+        location: span(start, end),
+    };
+
+    while pos < text.len() {
+        let c = text[pos..].chars().next().unwrap();
+        let start = pos;
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+
+        let tok = if c.is_ascii_alphabetic() || c == '_' {
+            pos = ident_end(bytes, pos);
+            Tok::Ident(text[start..pos].to_string())
+        } else if c == '\'' {
+            pos = ident_end(bytes, pos + 1);
+            Tok::Label(text[start + 1..pos].to_string())
+        } else if c.is_ascii_digit() {
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'.') {
+                // An exponent sign belongs to the number, as in `1e-7`.
+                if matches!(bytes[pos], b'e' | b'E')
+                    && matches!(bytes.get(pos + 1), Some(b'+' | b'-'))
+                {
+                    pos += 1;
+                }
+                pos += 1;
+            }
+            Tok::Number(text[start..pos].to_string())
+        } else if c == '"' {
+            let (value, end) =
+                lex_string(text, pos).map_err(|(kind, end)| error(kind, start, end))?;
+            pos = end;
+            Tok::Str(value)
+        } else if let Some(punct) = PUNCTUATION.iter().find(|p| text[pos..].starts_with(**p)) {
+            pos += punct.len();
+            Tok::Punct(punct)
+        } else {
+            return Err(error(
+                IrParseErrorType::UnexpectedChar(c),
+                start,
+                start + c.len_utf8(),
+            ));
+        };
+        tokens.push((tok, span(start, pos)));
+    }
+
+    tokens.push((Tok::Eof, span(text.len(), text.len())));
+    Ok(tokens)
+}
+
+/// End of the identifier starting at `pos`.
+fn ident_end(bytes: &[u8], mut pos: usize) -> usize {
+    while bytes
+        .get(pos)
+        .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_')
+    {
+        pos += 1;
+    }
+    pos
+}
+
+/// Reads the string literal at `start` as `Debug` escapes it, returning its
+/// value and the offset past the closing quote.
+fn lex_string(text: &str, start: usize) -> Result<(String, usize), (IrParseErrorType, usize)> {
+    let mut value = String::new();
+    let mut chars = text[start + 1..]
+        .char_indices()
+        .map(|(i, c)| (start + 1 + i, c));
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, i + 1)),
+            '\\' => {
+                let invalid = (IrParseErrorType::InvalidEscape, i + 1);
+                let (_, escape) = chars.next().ok_or(invalid.clone())?;
+                value.push(match escape {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    '\\' | '"' | '\'' => escape,
+                    'u' => {
+                        let rest = &text[i + 2..];
+                        let close = rest.find('}').ok_or(invalid.clone())?;
+                        let code = rest
+                            .strip_prefix('{')
+                            .and_then(|_| u32::from_str_radix(&rest[1..close], 16).ok())
+                            .and_then(char::from_u32)
+                            .ok_or(invalid.clone())?;
+                        // Skip the `{...}` just read.
+                        for _ in 0..=close {
+                            chars.next();
+                        }
+                        code
+                    }
+                    _ => return Err(invalid),
+                });
+            }
+            c => value.push(c),
+        }
+    }
+    Err((IrParseErrorType::UnterminatedString, text.len()))
+}
+
+struct IrParser {
+    tokens: Vec<(Tok, SrcSpan)>,
+    pos: usize,
+}
+
+impl IrParser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Tok::Punct(p) if *p == punct)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Tok::Ident(name) if name == keyword)
+    }
+
+    /// Consumes the punctuation if it is next.
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = self.is_punct(punct);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn error(&self, error: IrParseErrorType) -> IrParseError {
+        IrParseError {
+            error,
+            location: self.tokens[self.pos].1,
+        }
+    }
+
+    fn unexpected(&self, expected: &'static str) -> IrParseError {
+        self.error(IrParseErrorType::UnexpectedToken {
+            expected,
+            found: self.peek().to_string(),
+        })
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<(), IrParseError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.unexpected(punct))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &'static str) -> Result<(), IrParseError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    fn ident(&mut self) -> Result<Symbol, IrParseError> {
+        match self.peek() {
+            Tok::Ident(name) => {
+                let name = Symbol(name.clone());
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    fn parse_program(&mut self) -> Result<Program, IrParseError> {
+        let mut program = Program::new();
+        while *self.peek() != Tok::Eof {
+            let mutability = if self.eat_keyword("let") {
+                Some(Mutability::Mutable)
+            } else if self.eat_keyword("const") {
+                Some(Mutability::Const)
+            } else {
+                None
+            };
+            if let Some(mutability) = mutability {
+                let name = self.ident()?;
+                self.expect(":")?;
+                let ty = self.parse_type()?;
+                let value = if self.eat("=") {
+                    Some(self.parse_constant()?)
+                } else {
+                    None
+                };
+                self.expect(";")?;
+                program.globals.push((name, ty, value, mutability));
+            } else {
+                program.functions.push(self.parse_function()?);
+            }
+        }
+        Ok(program)
+    }
+
+    fn parse_function(&mut self) -> Result<Function, IrParseError> {
+        let inline_hint = self.parse_inline_hint()?;
+        let is_external = self.eat_keyword("extern");
+        self.expect_keyword("fn")?;
+        let name = self.ident()?;
+        self.expect("(")?;
+        let mut params = vec![];
+        let mut is_variadic = false;
+        while !self.eat(")") {
+            if !params.is_empty() || is_variadic {
+                self.expect(",")?;
+            }
+            if self.eat("...") {
+                is_variadic = true;
+                continue;
+            }
+            let param = self.ident()?;
+            self.expect(":")?;
+            params.push((param, self.parse_type()?));
+        }
+        self.expect("->")?;
+        let return_type = self.parse_type()?;
+        let body = if is_external {
+            self.expect(";")?;
+            Stmt::Block(vec![])
+        } else {
+            self.parse_block()?
+        };
+        Ok(Function {
+            name,
+            params,
+            return_type,
+            is_external,
+            is_variadic,
+            inline_hint,
+            body,
+        })
+    }
+
+    /// Parses an optional `#[inline]` or `#[inline(never)]`.
+    fn parse_inline_hint(&mut self) -> Result<InlineHint, IrParseError> {
+        if !self.eat("#") {
+            return Ok(InlineHint::Default);
+        }
+        self.expect("[")?;
+        self.expect_keyword("inline")?;
+        let hint = if self.eat("(") {
+            self.expect_keyword("never")?;
+            self.expect(")")?;
+            InlineHint::Never
+        } else {
+            InlineHint::Always
+        };
+        self.expect("]")?;
+        Ok(hint)
+    }
+
+    fn parse_type(&mut self) -> Result<Type, IrParseError> {
+        if self.eat("[") {
+            let elem = self.parse_type()?;
+            self.expect(";")?;
+            let size = match self.peek() {
+                Tok::Number(text) => text
+                    .parse()
+                    .map_err(|_| self.error(IrParseErrorType::InvalidNumber(text.clone())))?,
+                _ => return Err(self.unexpected("array size")),
+            };
+            self.pos += 1;
+            self.expect("]")?;
+            return Ok(Type::Array(Box::new(elem), size));
+        }
+
+        let name = self.ident()?;
+        Ok(match &name.0[..] {
+            "int" => Type::Int,
+            "i8" => Type::Int8,
+            "i16" => Type::Int16,
+            "i32" => Type::Int32,
+            "float" => Type::Float,
+            "bool" => Type::Bool,
+            "string" => Type::String,
+            "void" => Type::Void,
+            "_" => Type::Infer,
+            "fn" => {
+                self.expect("(")?;
+                let mut args = vec![];
+                let mut variadic = false;
+                while !self.eat(")") {
+                    if !args.is_empty() || variadic {
+                        self.expect(",")?;
+                    }
+                    if self.eat("...") {
+                        variadic = true;
+                    } else {
+                        args.push(self.parse_type()?);
+                    }
+                }
+                self.expect("->")?;
+                Type::Function(args, Box::new(self.parse_type()?), variadic)
+            }
+            "struct" => {
+                self.expect("{")?;
+                let mut fields = vec![];
+                while !self.eat("}") {
+                    if !fields.is_empty() {
+                        self.expect(",")?;
+                    }
+                    let field = self.ident()?;
+                    self.expect(":")?;
+                    fields.push((field, self.parse_type()?));
+                }
+                Type::Struct(fields)
+            }
+            _ => Type::TypeVar(name),
+        })
+    }
+
+    fn parse_block(&mut self) -> Result<Stmt, IrParseError> {
+        self.expect("{")?;
+        let mut stmts = vec![];
+        while !self.eat("}") {
+            stmts.push(self.parse_stmt()?);
+        }
+        Ok(Stmt::Block(stmts))
+    }
+
+    fn parse_stmt(&mut self) -> Result<Stmt, IrParseError> {
+        if self.is_punct("{") {
+            return self.parse_block();
+        }
+        if let Tok::Label(label) = self.peek() {
+            let label = Symbol(label.clone());
+            self.pos += 1;
+            self.expect(":")?;
+            self.expect_keyword("while")?;
+            return self.parse_while(Some(label));
+        }
+        if self.eat_keyword("let") {
+            let name = self.ident()?;
+            self.expect(":")?;
+            let ty = self.parse_type()?;
+            let init = if self.eat("=") {
+                Some(self.parse_expr()?)
+            } else {
+                None
+            };
+            self.expect(";")?;
+            return Ok(Stmt::Declare(name, ty, init));
+        }
+        if self.eat_keyword("return") {
+            let value = if self.is_punct(";") {
+                None
+            } else {
+                Some(self.parse_expr()?)
+            };
+            self.expect(";")?;
+            return Ok(Stmt::Return(value));
+        }
+        if self.eat_keyword("if") {
+            let cond = self.parse_expr()?;
+            let then_branch = Box::new(self.parse_stmt()?);
+            let else_branch = if self.eat_keyword("else") {
+                Some(Box::new(self.parse_stmt()?))
+            } else {
+                None
+            };
+            return Ok(Stmt::If(cond, then_branch, else_branch));
+        }
+        if self.eat_keyword("while") {
+            return self.parse_while(None);
+        }
+        if self.eat_keyword("switch") {
+            let scrutinee = self.parse_expr()?;
+            self.expect("{")?;
+            let mut arms = vec![];
+            let mut default = None;
+            while !self.eat("}") {
+                if self.eat_keyword("default") {
+                    self.expect("=>")?;
+                    default = Some(Box::new(self.parse_stmt()?));
+                } else {
+                    let value = self.parse_constant()?;
+                    self.expect("=>")?;
+                    arms.push((value, self.parse_stmt()?));
+                }
+            }
+            return Ok(Stmt::Switch(scrutinee, arms, default));
+        }
+        if self.is_keyword("break") || self.is_keyword("continue") {
+            let is_break = self.is_keyword("break");
+            self.pos += 1;
+            let label = match self.peek() {
+                Tok::Label(label) => {
+                    let label = Symbol(label.clone());
+                    self.pos += 1;
+                    Some(label)
+                }
+                _ => None,
+            };
+            self.expect(";")?;
+            return Ok(if is_break {
+                Stmt::Break(label)
+            } else {
+                Stmt::Continue(label)
+            });
+        }
+
+        let expr = self.parse_expr()?;
+        if self.eat("=") {
+            let value = self.parse_expr()?;
+            self.expect(";")?;
+            return Ok(Stmt::Assign(expr, value));
+        }
+        self.expect(";")?;
+        Ok(Stmt::Expr(expr))
+    }
+
+    fn parse_while(&mut self, label: Option<Symbol>) -> Result<Stmt, IrParseError> {
+        let cond = self.parse_expr()?;
+        let body = self.parse_stmt()?;
+        Ok(Stmt::While(cond, Box::new(body), label))
+    }
+
+    fn parse_constant(&mut self) -> Result<Constant, IrParseError> {
+        let start = self.pos;
+        match self.parse_expr()? {
+            Expr::Const(constant) => Ok(constant),
+            _ => {
+                self.pos = start;
+                Err(self.error(IrParseErrorType::ExpectedConstant))
+            }
+        }
+    }
+
+    /// Parses an expression, the conditional `c ? a : b` binding loosest and
+    /// grouping to the right.
+    fn parse_expr(&mut self) -> Result<Expr, IrParseError> {
+        let cond = self.parse_binary(1)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let then_value = self.parse_expr()?;
+        self.expect(":")?;
+        let else_value = self.parse_expr()?;
+        Ok(Expr::If(
+            Box::new(cond),
+            Box::new(then_value),
+            Box::new(else_value),
+        ))
+    }
+
+    /// Parses operators binding at least as tight as `min_precedence`, by
+    /// the table `BinOp::precedence`.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr, IrParseError> {
+        let mut lhs = self.parse_cast()?;
+        while let Some(op) = self.peek_binop() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.parse_binary(op.precedence() + 1)?;
+            lhs = Expr::BinOp(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn peek_binop(&self) -> Option<BinOp> {
+        let op = match self.peek() {
+            Tok::Punct(punct) => *punct,
+            Tok::Ident(name) if name == "and" => "and",
+            Tok::Ident(name) if name == "or" => "or",
+            _ => return None,
+        };
+        [
+            BinOp::Add,
+            BinOp::Sub,
+            BinOp::Mul,
+            BinOp::Div,
+            BinOp::Mod,
+            BinOp::Eq,
+            BinOp::Neq,
+            BinOp::Lt,
+            BinOp::Gt,
+            BinOp::Leq,
+            BinOp::Geq,
+            BinOp::And,
+            BinOp::Or,
+        ]
+        .into_iter()
+        .find(|binop| binop.as_str() == op)
+    }
+
+    /// Parses an operand followed by any number of `as type`.
+    fn parse_cast(&mut self) -> Result<Expr, IrParseError> {
+        let mut expr = self.parse_unary()?;
+        while self.eat_keyword("as") {
+            expr = Expr::Cast(Box::new(expr), Box::new(self.parse_type()?));
+        }
+        Ok(expr)
+    }
+
+    /// Parses a prefix operator, which binds tighter than any binary one but
+    /// applies to the whole postfix expression after it: `!f(x)` negates the
+    /// call.
+    fn parse_unary(&mut self) -> Result<Expr, IrParseError> {
+        if self.eat("!") {
+            let operand = self.parse_unary()?;
+            return Ok(Expr::UnOp(UnOp::Not, Box::new(operand)));
+        }
+        self.parse_postfix()
+    }
+
+    /// Parses a primary expression and the calls, indexing and field accesses
+    /// after it. Only a bare name followed by arguments is a direct call;
+    /// `(f)(x)` calls through the variable `f`.
+    fn parse_postfix(&mut self) -> Result<Expr, IrParseError> {
+        let bare_name = matches!(self.peek(), Tok::Ident(_));
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat("(") {
+                let mut args = vec![];
+                while !self.eat(")") {
+                    if !args.is_empty() {
+                        self.expect(",")?;
+                    }
+                    args.push(self.parse_expr()?);
+                }
+                expr = match expr {
+                    Expr::Var(name) if bare_name => Expr::Call(name, args),
+                    callee => Expr::CallIndirect(Box::new(callee), args),
+                };
+            } else if self.eat("[") {
+                let index = self.parse_expr()?;
+                self.expect("]")?;
+                expr = Expr::ArrayAccess(Box::new(expr), Box::new(index));
+            } else if self.eat(".") {
+                expr = Expr::FieldAccess(Box::new(expr), self.ident()?);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, IrParseError> {
+        if self.eat("(") {
+            let expr = self.parse_expr()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        // The IR has no negation, a `-` here is the sign of a literal.
+        let negative = self.eat("-");
+        let constant = match self.peek() {
+            Tok::Number(text) => self.parse_number(text, negative)?,
+            Tok::Ident(name) if name == "inf" => Constant::Float(if negative {
+                -f64::INFINITY
+            } else {
+                f64::INFINITY
+            }),
+            Tok::Ident(name) if name == "NaN" => Constant::Float(f64::NAN),
+            Tok::Ident(name) if name == "true" && !negative => Constant::Bool(true),
+            Tok::Ident(name) if name == "false" && !negative => Constant::Bool(false),
+            Tok::Ident(name) if !negative => {
+                let name = Symbol(name.clone());
+                self.pos += 1;
+                return Ok(Expr::Var(name));
+            }
+            Tok::Str(value) if !negative => Constant::String(value.clone()),
+            _ => return Err(self.unexpected("expression")),
+        };
+        self.pos += 1;
+        Ok(Expr::Const(constant))
+    }
+
+    /// Converts the literal at the current token, an integer unless it has a
+    /// fraction or exponent.
+    fn parse_number(&self, text: &str, negative: bool) -> Result<Constant, IrParseError> {
+        let signed = if negative {
+            format!("-{}", text)
+        } else {
+            text.to_string()
+        };
+        let constant = if text.contains(['.', 'e', 'E']) {
+            signed.parse().ok().map(Constant::Float)
+        } else {
+            signed.parse().ok().map(Constant::Int)
+        };
+        constant.ok_or_else(|| self.error(IrParseErrorType::InvalidNumber(signed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn var(name: &str) -> Box<Expr> {
+        Box::new(Expr::Var(sym(name)))
+    }
+
+    fn int(value: i64) -> Box<Expr> {
+        Box::new(Expr::Const(Constant::Int(value)))
+    }
+
+    fn sample_program() -> Program {
+        let point = Type::Struct(vec![(sym("x"), Type::Int), (sym("y"), Type::Float)]);
+        let callback = Type::Function(vec![Type::Int], Box::new(Type::Bool), false);
+        let sum = Function {
+            name: sym("sum"),
+            params: vec![
+                (sym("xs"), Type::Array(Box::new(Type::Int32), 4)),
+                (sym("p"), point.clone()),
+                (sym("pred"), callback),
+            ],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Always,
+            body: Stmt::Block(vec![
+                Stmt::Declare(sym("total"), Type::Int, Some(*int(0))),
+                Stmt::Declare(sym("__tmp0"), Type::Infer, None),
+                Stmt::Assign(Expr::Var(sym("__tmp0")), *int(-3)),
+                // (i as float + 0.5) as int
+                Stmt::Assign(
+                    Expr::Var(sym("i")),
+                    Expr::Cast(
+                        Box::new(Expr::BinOp(
+                            BinOp::Add,
+                            Box::new(Expr::Cast(var("i"), Box::new(Type::Float))),
+                            Box::new(Expr::Const(Constant::Float(0.5))),
+                        )),
+                        Box::new(Type::Int),
+                    ),
+                ),
+                Stmt::Switch(
+                    Expr::BinOp(BinOp::Mod, var("i"), int(3)),
+                    vec![
+                        (Constant::Int(0), Stmt::Break(None)),
+                        (
+                            Constant::Int(-1),
+                            Stmt::Block(vec![Stmt::Expr(Expr::Call(sym("f"), vec![]))]),
+                        ),
+                    ],
+                    Some(Box::new(Stmt::Block(vec![]))),
+                ),
+                Stmt::Switch(*var("i"), vec![], None),
+                // !!done and !(a == b)
+                Stmt::Expr(Expr::BinOp(
+                    BinOp::And,
+                    Box::new(Expr::UnOp(
+                        UnOp::Not,
+                        Box::new(Expr::UnOp(UnOp::Not, var("done"))),
+                    )),
+                    Box::new(Expr::UnOp(
+                        UnOp::Not,
+                        Box::new(Expr::BinOp(BinOp::Eq, var("a"), var("b"))),
+                    )),
+                )),
+                Stmt::While(
+                    Expr::BinOp(BinOp::Lt, var("i"), int(4)),
+                    Box::new(Stmt::Block(vec![
+                        Stmt::If(
+                            Expr::CallIndirect(
+                                var("pred"),
+                                vec![Expr::ArrayAccess(var("xs"), var("i"))],
+                            ),
+                            Box::new(Stmt::Block(vec![Stmt::Continue(Some(sym("outer")))])),
+                            Some(Box::new(Stmt::Block(vec![Stmt::Break(None)]))),
+                        ),
+                        // (total - 1) * (i - (1 - 2))
+                        Stmt::Assign(
+                            Expr::Var(sym("total")),
+                            Expr::BinOp(
+                                BinOp::Mul,
+                                Box::new(Expr::BinOp(BinOp::Sub, var("total"), int(1))),
+                                Box::new(Expr::BinOp(
+                                    BinOp::Sub,
+                                    var("i"),
+                                    Box::new(Expr::BinOp(BinOp::Sub, int(1), int(2))),
+                                )),
+                            ),
+                        ),
+                    ])),
+                    Some(sym("outer")),
+                ),
+                Stmt::Expr(Expr::Call(
+                    sym("printf"),
+                    vec![
+                        Expr::Const(Constant::String("a \"b\"\n\t\\ é".to_string())),
+                        Expr::FieldAccess(var("p"), sym("y")),
+                    ],
+                )),
+                Stmt::Return(Some(Expr::If(
+                    Box::new(Expr::BinOp(
+                        BinOp::Or,
+                        Box::new(Expr::Const(Constant::Bool(true))),
+                        Box::new(Expr::BinOp(BinOp::And, var("a"), var("b"))),
+                    )),
+                    var("total"),
+                    Box::new(Expr::If(var("c"), int(i64::MIN), var("total"))),
+                ))),
+            ]),
+        };
+        let printf = Function {
+            name: sym("printf"),
+            params: vec![(sym("format"), Type::String)],
+            return_type: Type::Int32,
+            is_external: true,
+            is_variadic: true,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        };
+        let main = Function {
+            name: sym("main"),
+            params: vec![],
+            return_type: Type::Void,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Never,
+            body: Stmt::Block(vec![Stmt::Return(None)]),
+        };
+        Program {
+            functions: vec![printf, sum, main],
+            globals: vec![
+                (
+                    sym("scale"),
+                    Type::Float,
+                    Some(Constant::Float(-1.5e-7)),
+                    Mutability::Const,
+                ),
+                (
+                    sym("big"),
+                    Type::Float,
+                    Some(Constant::Float(f64::INFINITY)),
+                    Mutability::Mutable,
+                ),
+                (sym("origin"), point, None, Mutability::Mutable),
+            ],
+            default_int: Type::Int,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let program = sample_program();
+        let text = program.to_string();
+
+        assert_eq!(parse_ir(&text), Ok(program), "{}", text);
+    }
+
+    #[test]
+    fn test_parse_fixture() {
+        let text = "
+            fn id(x: T) -> T { return x; }
+            fn main() -> void { let f: fn(int) -> int = id; (f)(1); }
+        ";
+        let program = parse_ir(text).unwrap();
+
+        assert_eq!(
+            program.functions[0].params,
+            vec![(sym("x"), Type::TypeVar(sym("T")))]
+        );
+        assert_eq!(
+            program.functions[1].body,
+            Stmt::Block(vec![
+                Stmt::Declare(
+                    sym("f"),
+                    Type::Function(vec![Type::Int], Box::new(Type::Int), false),
+                    Some(Expr::Var(sym("id"))),
+                ),
+                Stmt::Expr(Expr::CallIndirect(var("f"), vec![*int(1)])),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_ir("fn f() -> int { return 1 }"),
+            Err(IrParseError {
+                error: IrParseErrorType::UnexpectedToken {
+                    expected: ";",
+                    found: "`}`".to_string(),
+                },
+                location: SrcSpan { start: 25, end: 26 },
+            })
+        );
+        assert_eq!(
+            parse_ir("let g: int = x;").unwrap_err().error,
+            IrParseErrorType::ExpectedConstant
+        );
+        assert_eq!(
+            parse_ir("let s: string = \"abc").unwrap_err().error,
+            IrParseErrorType::UnterminatedString
+        );
+    }
+}
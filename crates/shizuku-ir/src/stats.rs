@@ -0,0 +1,198 @@
+//! Size metrics of a program, for profiling and for checking what passes do.
+
+use crate::Expr;
+use crate::Program;
+use crate::Stmt;
+
+/// Counts over a whole program, see `Program::stats`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramStats {
+    /// Functions, external ones included.
+    pub functions: usize,
+    pub globals: usize,
+    /// Statements other than blocks, which only group the ones they hold.
+    pub statements: usize,
+    /// Expressions, counting every subexpression and assignment target.
+    pub expressions: usize,
+    /// Depth of the deepest expression tree, 1 for a variable or constant.
+    pub max_expr_depth: usize,
+    /// Depth of the most deeply nested block, 1 for a function body.
+    pub max_block_depth: usize,
+}
+
+impl Program {
+    /// Counts the functions, globals, statements and expressions of this
+    /// program.
+    pub fn stats(&self) -> ProgramStats {
+        let mut stats = ProgramStats {
+            functions: self.functions.len(),
+            globals: self.globals.len(),
+            ..ProgramStats::default()
+        };
+        for function in &self.functions {
+            stats.stmt(&function.body, 0);
+        }
+        stats
+    }
+}
+
+impl ProgramStats {
+    /// Counts `stmt`, nested in `depth` blocks.
+    fn stmt(&mut self, stmt: &Stmt, depth: usize) {
+        if let Stmt::Block(stmts) = stmt {
+            self.max_block_depth = self.max_block_depth.max(depth + 1);
+            for stmt in stmts {
+                self.stmt(stmt, depth + 1);
+            }
+            return;
+        }
+
+        self.statements += 1;
+        match stmt {
+            Stmt::Declare(_, _, init) => {
+                if let Some(init) = init {
+                    self.expr(init);
+                }
+            }
+            Stmt::Assign(target, value) => {
+                self.expr(target);
+                self.expr(value);
+            }
+            Stmt::Expr(expr) => {
+                self.expr(expr);
+            }
+            Stmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::If(cond, then_branch, else_branch) => {
+                self.expr(cond);
+                self.stmt(then_branch, depth);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch, depth);
+                }
+            }
+            Stmt::While(cond, body, _) => {
+                self.expr(cond);
+                self.stmt(body, depth);
+            }
+            Stmt::Switch(scrutinee, arms, default) => {
+                self.expr(scrutinee);
+                for (_, arm) in arms {
+                    self.stmt(arm, depth);
+                }
+                if let Some(default) = default {
+                    self.stmt(default, depth);
+                }
+            }
+            Stmt::Break(_) | Stmt::Continue(_) | Stmt::Block(_) => {}
+        }
+    }
+
+    /// Counts `expr` and its subexpressions, returning its depth.
+    fn expr(&mut self, expr: &Expr) -> usize {
+        self.expressions += 1;
+        let children = match expr {
+            Expr::Var(_) | Expr::Const(_) => 0,
+            Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+                self.expr(operand)
+            }
+            Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+                self.expr(lhs).max(self.expr(rhs))
+            }
+            Expr::Call(_, args) => self.exprs(args),
+            Expr::CallIndirect(callee, args) => self.expr(callee).max(self.exprs(args)),
+            Expr::If(cond, then_value, else_value) => self
+                .expr(cond)
+                .max(self.expr(then_value))
+                .max(self.expr(else_value)),
+        };
+        let depth = children + 1;
+        self.max_expr_depth = self.max_expr_depth.max(depth);
+        depth
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) -> usize {
+        exprs.iter().map(|expr| self.expr(expr)).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinOp;
+    use crate::Constant;
+    use crate::Function;
+    use crate::InlineHint;
+    use crate::Mutability;
+    use crate::Symbol;
+    use crate::Type;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(sym(name))
+    }
+
+    #[test]
+    fn test_stats() {
+        // fn f(a: int) -> int {
+        //     let b: int = a * (a + 1);
+        //     if b > 10 { return b; }
+        //     return 0;
+        // }
+        let body = Stmt::Block(vec![
+            Stmt::Declare(
+                sym("b"),
+                Type::Int,
+                Some(Expr::BinOp(
+                    BinOp::Mul,
+                    Box::new(var("a")),
+                    Box::new(Expr::BinOp(
+                        BinOp::Add,
+                        Box::new(var("a")),
+                        Box::new(Expr::Const(Constant::Int(1))),
+                    )),
+                )),
+            ),
+            Stmt::If(
+                Expr::BinOp(
+                    BinOp::Gt,
+                    Box::new(var("b")),
+                    Box::new(Expr::Const(Constant::Int(10))),
+                ),
+                Box::new(Stmt::Block(vec![Stmt::Return(Some(var("b")))])),
+                None,
+            ),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        let program = Program {
+            functions: vec![Function {
+                name: sym("f"),
+                params: vec![(sym("a"), Type::Int)],
+                return_type: Type::Int,
+                is_external: false,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
+                body,
+            }],
+            globals: vec![(sym("g"), Type::Int, None, Mutability::Mutable)],
+            default_int: Type::Int,
+        };
+
+        assert_eq!(
+            program.stats(),
+            ProgramStats {
+                functions: 1,
+                globals: 1,
+                statements: 4,
+                expressions: 10,
+                max_expr_depth: 3,
+                max_block_depth: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Names for compiler-introduced temporaries.
+
+use crate::Function;
+use crate::Stmt;
+use crate::Symbol;
+
+const PREFIX: &str = "__tmp";
+
+/// Hands out the temporaries of one function as `__tmp0`, `__tmp1`, ...
+///
+/// Counting restarts for every function, so the names a pass introduces
+/// depend only on the function it runs on and the same input always yields
+/// the same output.
+#[derive(Debug, Default, Clone)]
+pub struct TempAllocator {
+    next: usize,
+}
+
+impl TempAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An allocator for further temporaries in `function`, numbered after
+    /// the ones an earlier pass already declared there.
+    pub fn for_function(function: &Function) -> Self {
+        let mut temps = Self::new();
+        temps.skip_declared(&function.body);
+        temps
+    }
+
+    /// A fresh temporary name.
+    pub fn fresh(&mut self) -> Symbol {
+        let temp = Symbol(format!("{PREFIX}{}", self.next));
+        self.next += 1;
+        temp
+    }
+
+    fn skip_declared(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Declare(sym, _, _) => {
+                if let Some(index) = sym.0.strip_prefix(PREFIX)
+                    && let Ok(index) = index.parse::<usize>()
+                {
+                    self.next = self.next.max(index + 1);
+                }
+            }
+            Stmt::Block(stmts) => stmts.iter().for_each(|stmt| self.skip_declared(stmt)),
+            Stmt::If(_, then_branch, else_branch) => {
+                self.skip_declared(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.skip_declared(else_branch);
+                }
+            }
+            Stmt::While(_, body, _) => self.skip_declared(body),
+            Stmt::Switch(_, arms, default) => {
+                for (_, arm) in arms {
+                    self.skip_declared(arm);
+                }
+                if let Some(default) = default {
+                    self.skip_declared(default);
+                }
+            }
+            Stmt::Assign(..)
+            | Stmt::Expr(_)
+            | Stmt::Return(_)
+            | Stmt::Break(_)
+            | Stmt::Continue(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Constant;
+    use crate::Expr;
+    use crate::InlineHint;
+    use crate::Type;
+
+    #[test]
+    fn test_fresh() {
+        let mut temps = TempAllocator::new();
+        assert_eq!(temps.fresh(), Symbol("__tmp0".to_string()));
+        assert_eq!(temps.fresh(), Symbol("__tmp1".to_string()));
+    }
+
+    #[test]
+    fn test_for_function_skips_declared_temps() {
+        let declare = |name: &str| Stmt::Declare(Symbol(name.to_string()), Type::Int, None);
+        let function = Function {
+            name: Symbol("f".to_string()),
+            params: vec![],
+            return_type: Type::Void,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![
+                declare("__tmp3"),
+                Stmt::While(
+                    Expr::Const(Constant::Bool(true)),
+                    Box::new(Stmt::Block(vec![declare("__tmp5"), declare("__tmpx")])),
+                    None,
+                ),
+            ]),
+        };
+        let mut temps = TempAllocator::for_function(&function);
+        assert_eq!(temps.fresh(), Symbol("__tmp6".to_string()));
+    }
+}
@@ -0,0 +1,2179 @@
+//! Type checking for the IR.
+//!
+//! The checker walks every function body with a stack of lexical scopes and
+//! verifies that each expression is used at a type it supports.
+
+use crate::BinOp;
+use crate::BlockId;
+use crate::Cfg;
+use crate::Constant;
+use crate::Expr;
+use crate::Function;
+use crate::Intrinsic;
+use crate::Mutability;
+use crate::Program;
+use crate::Stmt;
+use crate::Symbol;
+use crate::Terminator;
+use crate::Type;
+use crate::UnOp;
+use crate::build_cfg;
+use crate::opt::fold_expr;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashMap;
+use shizuku_common::dmap::DHashSet;
+use shizuku_common::strsim::edit_distance;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError {
+    /// A variable or function name with no binding in scope, with the most
+    /// similar name that is in scope, if any is close enough.
+    UnresolvedSymbol {
+        name: Symbol,
+        suggestion: Option<Symbol>,
+    },
+    /// An expression whose type differs from the one required by its context.
+    Mismatch { expected: Type, found: Type },
+    /// A binary operator applied to operands it is not defined for.
+    InvalidOperands { op: BinOp, lhs: Type, rhs: Type },
+    /// A call whose callee is not of function type.
+    NotCallable(Type),
+    /// A call with the wrong number of arguments.
+    ArityMismatch { expected: usize, found: usize },
+    /// An index or field access on a value that has no such element.
+    InvalidAccess(Type),
+    /// A `let` with neither a type annotation nor an initializer.
+    CannotInfer(Symbol),
+    /// A non-`void` function that can reach the end of its body.
+    MissingReturn(Symbol),
+    /// The result of a `void` call used where a value is required.
+    VoidValueUsed,
+    /// A type variable unified with a type containing itself.
+    RecursiveType { var: Symbol, ty: Type },
+    /// A cast between types other than the numeric ones.
+    InvalidCast { from: Type, to: Type },
+    /// A switch with two arms for the same constant.
+    DuplicateCase(Constant),
+    /// A switch arm for a constant outside the range of the scrutinee's
+    /// integer type.
+    CaseOutOfRange { value: Constant, ty: Type },
+    /// An assignment to something other than a variable, field or element.
+    NotAnLValue(Expr),
+    /// An assignment to a `const` global or to part of one.
+    AssignToConst(Symbol),
+    /// A parameter, variable or global declared with a type that holds a
+    /// `void` value. `void` is only allowed as a return type.
+    VoidNotAllowedHere(Symbol),
+    /// A `return` with a value in the named `void` function.
+    ReturnValueInVoid(Symbol),
+    /// A `return` without a value in the named non-`void` function.
+    MissingReturnValue(Symbol),
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeError::UnresolvedSymbol { name, suggestion } => {
+                write!(f, "cannot find `{}` in this scope", name.0)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, "; did you mean `{}`?", suggestion.0)?;
+                }
+                Ok(())
+            }
+            TypeError::Mismatch { expected, found } => {
+                write!(
+                    f,
+                    "mismatched types: expected `{}`, found `{}`",
+                    expected, found
+                )
+            }
+            TypeError::InvalidOperands { op, lhs, rhs } => {
+                write!(f, "cannot apply `{:?}` to `{}` and `{}`", op, lhs, rhs)
+            }
+            TypeError::NotCallable(ty) => write!(f, "expected function, found `{}`", ty),
+            TypeError::ArityMismatch { expected, found } => write!(
+                f,
+                "this function takes {} arguments but {} were supplied",
+                expected, found
+            ),
+            TypeError::InvalidAccess(ty) => write!(f, "cannot index into a value of type `{}`", ty),
+            TypeError::CannotInfer(sym) => write!(f, "type annotations needed for `{}`", sym.0),
+            TypeError::MissingReturn(sym) => {
+                write!(f, "function `{}` does not return on every path", sym.0)
+            }
+            TypeError::VoidValueUsed => write!(f, "a `void` expression has no value to use"),
+            TypeError::RecursiveType { var, ty } => {
+                write!(f, "type variable `{}` occurs in `{}`", var.0, ty)
+            }
+            TypeError::InvalidCast { from, to } => {
+                write!(f, "cannot cast `{}` as `{}`", from, to)
+            }
+            TypeError::DuplicateCase(value) => {
+                write!(f, "switch has more than one arm for `{}`", value)
+            }
+            TypeError::CaseOutOfRange { value, ty } => {
+                write!(f, "switch arm `{}` is out of range for `{}`", value, ty)
+            }
+            TypeError::NotAnLValue(target) => write!(f, "cannot assign to `{}`", target),
+            TypeError::AssignToConst(sym) => {
+                write!(f, "cannot assign to `{}`, it is a `const` global", sym.0)
+            }
+            TypeError::VoidNotAllowedHere(sym) => {
+                write!(f, "`{}` cannot have type `void`", sym.0)
+            }
+            TypeError::ReturnValueInVoid(sym) => {
+                write!(f, "`void` function `{}` cannot return a value", sym.0)
+            }
+            TypeError::MissingReturnValue(sym) => {
+                write!(f, "`return` in function `{}` needs a value", sym.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// A suspicious construct that is still well typed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// A `let` redeclaring a name already declared in the same scope.
+    ///
+    /// The IR carries no source spans, so the declarations are located by
+    /// the function and their 0-based positions in the block they share.
+    Shadowing {
+        function: Symbol,
+        name: Symbol,
+        previous_index: usize,
+        new_index: usize,
+    },
+    /// A function that calls itself on every path before it can return, see
+    /// `detect_unconditional_self_recursion`.
+    UnconditionalRecursion { function: Symbol },
+    /// A `while` whose condition is always true and whose body can neither
+    /// `break` out of it nor return.
+    InfiniteLoop { function: Symbol },
+    /// A `while` whose condition is always false, so its body never runs.
+    UnreachableLoopBody { function: Symbol },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Shadowing {
+                function,
+                name,
+                previous_index,
+                new_index,
+            } => write!(
+                f,
+                "`{}` declared by statement {} is redeclared by statement {} of the same block in function `{}`",
+                name.0,
+                previous_index + 1,
+                new_index + 1,
+                function.0
+            ),
+            Warning::UnconditionalRecursion { function } => write!(
+                f,
+                "function `{}` calls itself on every path and never returns",
+                function.0
+            ),
+            Warning::InfiniteLoop { function } => write!(
+                f,
+                "loop in function `{}` never exits, its condition is always true",
+                function.0
+            ),
+            Warning::UnreachableLoopBody { function } => write!(
+                f,
+                "loop body in function `{}` never runs, its condition is always false",
+                function.0
+            ),
+        }
+    }
+}
+
+/// Options relaxing the type rules.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TypeCheckConfig {
+    /// Accept a binary operator with one integer and one `float` operand by
+    /// converting the integer to `float`. The conversion is inserted into the
+    /// program as an `Expr::Cast`, so codegen sees it.
+    pub implicit_int_to_float: bool,
+}
+
+/// Type checks every global initializer and function in `program`,
+/// returning the warnings found along the way.
+///
+/// An integer initializer may give a global of a narrower integer type, as
+/// a literal may a local. Declarations of type `Type::Infer` are rewritten in place to the type of
+/// their initializer. External functions have no body to check, calls to
+/// them are checked against their signature like any other.
+pub fn check_program(program: &mut Program) -> Result<Vec<Warning>, TypeError> {
+    check_program_with_config(program, &TypeCheckConfig::default())
+}
+
+/// Type checks `program` like `check_program`, under non-default rules.
+pub fn check_program_with_config(
+    program: &mut Program,
+    config: &TypeCheckConfig,
+) -> Result<Vec<Warning>, TypeError> {
+    let mut checker = TypeChecker::new(program, *config);
+    for (name, ty, init, _) in &program.globals {
+        check_not_void(name, ty)?;
+        if let Some(init) = init {
+            checker.expect_value(&mut Expr::Const(init.clone()), ty)?;
+        }
+    }
+    for function in &mut program.functions {
+        for (name, ty) in &function.params {
+            check_not_void(name, ty)?;
+        }
+        if function.is_external {
+            continue;
+        }
+        checker.check_function(function)?;
+        check_returns(function)?;
+        if detect_unconditional_self_recursion(function).is_some() {
+            checker.warnings.push(Warning::UnconditionalRecursion {
+                function: function.name.clone(),
+            });
+        }
+        checker.warnings.extend(loop_condition_warnings(function));
+    }
+    // Every integer literal now has its type, a cast if it is not `int`.
+    program.default_int = Type::Int;
+    Ok(checker.warnings)
+}
+
+/// Warns about each `while` whose condition folds to a constant.
+fn loop_condition_warnings(function: &Function) -> Vec<Warning> {
+    let mut conditions = vec![];
+    loop_conditions(&function.body, &mut conditions);
+    if conditions.is_empty() {
+        return vec![];
+    }
+
+    let cfg = build_cfg(function);
+    let mut warnings = vec![];
+    for (index, block) in cfg.blocks().iter().enumerate() {
+        let Terminator::Branch(cond, body, exit) = &block.terminator else {
+            continue;
+        };
+        if !conditions.iter().any(|&c| std::ptr::eq(c, *cond)) {
+            continue;
+        }
+        let mut folded = (*cond).clone();
+        fold_expr(&mut folded);
+        let function = function.name.clone();
+        match folded {
+            Expr::Const(Constant::Bool(true))
+                if !can_leave_loop(&cfg, BlockId(index), *body, *exit) =>
+            {
+                warnings.push(Warning::InfiniteLoop { function })
+            }
+            Expr::Const(Constant::Bool(false)) => {
+                warnings.push(Warning::UnreachableLoopBody { function })
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+/// Collects the condition of every `while` in `stmt`.
+fn loop_conditions<'a>(stmt: &'a Stmt, conditions: &mut Vec<&'a Expr>) {
+    match stmt {
+        Stmt::Block(stmts) => stmts
+            .iter()
+            .for_each(|stmt| loop_conditions(stmt, conditions)),
+        Stmt::If(_, then_branch, else_branch) => {
+            loop_conditions(then_branch, conditions);
+            if let Some(else_branch) = else_branch {
+                loop_conditions(else_branch, conditions);
+            }
+        }
+        Stmt::While(cond, body, _) => {
+            conditions.push(cond);
+            loop_conditions(body, conditions);
+        }
+        Stmt::Switch(_, arms, default) => {
+            arms.iter()
+                .for_each(|(_, arm)| loop_conditions(arm, conditions));
+            if let Some(default) = default {
+                loop_conditions(default, conditions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether control entering the loop body at `body` can reach its `exit`
+/// or a `return` other than through the loop `header`.
+fn can_leave_loop(cfg: &Cfg, header: BlockId, body: BlockId, exit: BlockId) -> bool {
+    let mut seen = vec![false; cfg.blocks().len()];
+    let mut stack = vec![body];
+    while let Some(block) = stack.pop() {
+        if block == header || std::mem::replace(&mut seen[block.0], true) {
+            continue;
+        }
+        if block == exit || matches!(cfg.block(block).terminator, Terminator::Return(_)) {
+            return true;
+        }
+        stack.extend(cfg.successors(block));
+    }
+    false
+}
+
+/// Checks that a non-`void` function returns on every control-flow path.
+///
+/// The check is syntactic: an `if` returns when both of its branches do, and
+/// a `while` never counts since its body may not run. A call to `panic` counts
+/// as returning because it never comes back.
+pub fn check_returns(function: &Function) -> Result<(), TypeError> {
+    if function.is_external || function.return_type == Type::Void || always_returns(&function.body)
+    {
+        Ok(())
+    } else {
+        Err(TypeError::MissingReturn(function.name.clone()))
+    }
+}
+
+/// Finds a call `function` makes to itself on every path through its body
+/// before any `return`, `break`, `continue` or `panic`, which makes every call
+/// of it recurse until the stack overflows.
+///
+/// The IR carries no source spans, so the offending call is returned instead.
+/// This is a best-effort lint: conditions are not evaluated, so recursion the
+/// function can never escape in practice goes unnoticed.
+pub fn detect_unconditional_self_recursion(function: &Function) -> Option<&Expr> {
+    if function.is_external {
+        return None;
+    }
+    SelfCalls(&function.name).stmt(&function.body)
+}
+
+/// Searches for a call to the named function that every execution reaches.
+struct SelfCalls<'a>(&'a Symbol);
+
+impl SelfCalls<'_> {
+    fn stmt<'e>(&self, stmt: &'e Stmt) -> Option<&'e Expr> {
+        match stmt {
+            Stmt::Declare(_, _, init) => init.as_ref().and_then(|init| self.expr(init)),
+            Stmt::Assign(target, value) => self.expr(value).or_else(|| self.expr(target)),
+            Stmt::Expr(expr) | Stmt::Return(Some(expr)) => self.expr(expr),
+            Stmt::Return(None) | Stmt::Break(_) | Stmt::Continue(_) => None,
+            Stmt::Block(stmts) => {
+                for stmt in stmts {
+                    if let Some(call) = self.stmt(stmt) {
+                        return Some(call);
+                    }
+                    if may_exit(stmt) {
+                        return None;
+                    }
+                }
+                None
+            }
+            Stmt::If(cond, then_branch, else_branch) => self.expr(cond).or_else(|| {
+                let else_branch = else_branch.as_ref()?;
+                self.stmt(then_branch).and(self.stmt(else_branch))
+            }),
+            // The body may not run at all.
+            Stmt::While(cond, _, _) => self.expr(cond),
+            Stmt::Switch(scrutinee, arms, default) => self.expr(scrutinee).or_else(|| {
+                let default = self.stmt(default.as_ref()?)?;
+                for (_, arm) in arms {
+                    self.stmt(arm)?;
+                }
+                Some(default)
+            }),
+        }
+    }
+
+    fn expr<'e>(&self, expr: &'e Expr) -> Option<&'e Expr> {
+        match expr {
+            Expr::Var(_) | Expr::Const(_) => None,
+            Expr::Call(sym, args) => args
+                .iter()
+                .find_map(|arg| self.expr(arg))
+                .or_else(|| (sym == self.0).then_some(expr)),
+            Expr::CallIndirect(callee, args) => self
+                .expr(callee)
+                .or_else(|| args.iter().find_map(|arg| self.expr(arg))),
+            // The right operand is short-circuited.
+            Expr::BinOp(BinOp::And | BinOp::Or, lhs, _) => self.expr(lhs),
+            Expr::BinOp(_, lhs, rhs) | Expr::ArrayAccess(lhs, rhs) => {
+                self.expr(lhs).or_else(|| self.expr(rhs))
+            }
+            Expr::UnOp(_, operand) | Expr::Cast(operand, _) | Expr::FieldAccess(operand, _) => {
+                self.expr(operand)
+            }
+            Expr::If(cond, then_value, else_value) => self
+                .expr(cond)
+                .or_else(|| self.expr(then_value).and(self.expr(else_value))),
+        }
+    }
+}
+
+/// Whether `stmt` may leave the enclosing block early.
+fn may_exit(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_) => true,
+        Stmt::Expr(Expr::Call(sym, _)) => Intrinsic::from_symbol(sym) == Some(Intrinsic::Panic),
+        Stmt::Block(stmts) => stmts.iter().any(may_exit),
+        Stmt::If(_, then_branch, else_branch) => {
+            may_exit(then_branch) || else_branch.as_deref().is_some_and(may_exit)
+        }
+        Stmt::While(_, body, _) => may_exit(body),
+        Stmt::Switch(_, arms, default) => {
+            arms.iter().any(|(_, arm)| may_exit(arm)) || default.as_deref().is_some_and(may_exit)
+        }
+        Stmt::Declare(..) | Stmt::Assign(..) | Stmt::Expr(_) => false,
+    }
+}
+
+/// Rejects `ty` as the type of the variable `name` if it holds a `void`
+/// value anywhere, other than as the return type of a function type.
+fn check_not_void(name: &Symbol, ty: &Type) -> Result<(), TypeError> {
+    if holds_void(ty) {
+        Err(TypeError::VoidNotAllowedHere(name.clone()))
+    } else {
+        Ok(())
+    }
+}
+
+fn holds_void(ty: &Type) -> bool {
+    match ty {
+        Type::Void => true,
+        Type::Function(params, _, _) => params.iter().any(holds_void),
+        Type::Array(elem, _) => holds_void(elem),
+        Type::Struct(fields) => fields.iter().any(|(_, ty)| holds_void(ty)),
+        _ => false,
+    }
+}
+
+fn always_returns(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(_) => true,
+        Stmt::Expr(Expr::Call(sym, _)) => Intrinsic::from_symbol(sym) == Some(Intrinsic::Panic),
+        Stmt::Block(stmts) => stmts.iter().any(always_returns),
+        Stmt::If(_, then_branch, Some(else_branch)) => {
+            always_returns(then_branch) && always_returns(else_branch)
+        }
+        Stmt::Switch(_, arms, Some(default)) => {
+            arms.iter().all(|(_, arm)| always_returns(arm)) && always_returns(default)
+        }
+        _ => false,
+    }
+}
+
+/// Largest edit distance at which an unresolved name gets a suggestion.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+struct TypeChecker {
+    /// Signatures of every function and the types of every global.
+    items: DHashMap<Symbol, Type>,
+    /// The globals declared `const`.
+    consts: DHashSet<Symbol>,
+    /// Innermost scope last.
+    scopes: Vec<DHashMap<Symbol, Type>>,
+    /// For each scope in `scopes`, the position of each local declared in
+    /// it within its block.
+    positions: Vec<DHashMap<Symbol, usize>>,
+    /// Position of the statement being checked within its block.
+    position: usize,
+    /// The function being checked.
+    function: Symbol,
+    return_type: Type,
+    warnings: Vec<Warning>,
+    /// The type of an integer literal that is not given one by its context.
+    default_int: Type,
+    config: TypeCheckConfig,
+}
+
+impl TypeChecker {
+    fn new(program: &Program, config: TypeCheckConfig) -> Self {
+        let mut items = dmap::new();
+        for intrinsic in Intrinsic::ALL {
+            items.insert(intrinsic.symbol(), intrinsic.signature());
+        }
+        let mut consts = dmap::new_set();
+        for (name, ty, _, mutability) in &program.globals {
+            items.insert(name.clone(), ty.clone());
+            if *mutability == Mutability::Const {
+                consts.insert(name.clone());
+            }
+        }
+        for function in &program.functions {
+            items.insert(function.name.clone(), function.signature());
+        }
+
+        Self {
+            items,
+            consts,
+            scopes: Vec::new(),
+            positions: Vec::new(),
+            position: 0,
+            function: Symbol(String::new()),
+            return_type: Type::Void,
+            warnings: Vec::new(),
+            default_int: program.default_int.clone(),
+            config,
+        }
+    }
+
+    fn lookup(&self, sym: &Symbol) -> Result<Type, TypeError> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(sym))
+            .or_else(|| self.items.get(sym))
+            .cloned()
+            .ok_or_else(|| TypeError::UnresolvedSymbol {
+                name: sym.clone(),
+                suggestion: self.similar_name(sym),
+            })
+    }
+
+    /// The visible name closest to `sym`, if within `MAX_SUGGESTION_DISTANCE`.
+    /// Ties go to the alphabetically first name.
+    fn similar_name(&self, sym: &Symbol) -> Option<Symbol> {
+        self.scopes
+            .iter()
+            .flat_map(|scope| scope.keys())
+            .chain(self.items.keys())
+            .map(|name| (edit_distance(&sym.0, &name.0), name))
+            .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+            .min_by(|(a, a_name), (b, b_name)| a.cmp(b).then_with(|| a_name.0.cmp(&b_name.0)))
+            .map(|(_, name)| name.clone())
+    }
+
+    fn declare(&mut self, sym: Symbol, ty: Type) {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("declaration outside of a scope");
+        let positions = self
+            .positions
+            .last_mut()
+            .expect("declaration outside of a scope");
+        if let Some(previous_index) = positions.insert(sym.clone(), self.position) {
+            self.warnings.push(Warning::Shadowing {
+                function: self.function.clone(),
+                name: sym.clone(),
+                previous_index,
+                new_index: self.position,
+            });
+        }
+        scope.insert(sym, ty);
+    }
+
+    /// Checks that `target` names storage that may be written: a variable
+    /// other than a `const` global, or a field or element of one.
+    fn check_assignable(&self, target: &Expr) -> Result<(), TypeError> {
+        match target {
+            Expr::Var(sym) => {
+                let is_local = self.scopes.iter().any(|scope| scope.contains_key(sym));
+                if !is_local && self.consts.contains(sym) {
+                    return Err(TypeError::AssignToConst(sym.clone()));
+                }
+                Ok(())
+            }
+            Expr::FieldAccess(base, _) | Expr::ArrayAccess(base, _) => self.check_assignable(base),
+            _ => Err(TypeError::NotAnLValue(target.clone())),
+        }
+    }
+
+    fn check_function(&mut self, function: &mut Function) -> Result<(), TypeError> {
+        let mut params = dmap::new();
+        for (name, ty) in &function.params {
+            params.insert(name.clone(), ty.clone());
+        }
+        self.scopes = vec![params];
+        self.positions = vec![dmap::new()];
+        self.position = 0;
+        self.function = function.name.clone();
+        self.return_type = function.return_type.clone();
+
+        self.check_stmt(&mut function.body)
+    }
+
+    fn check_stmt(&mut self, stmt: &mut Stmt) -> Result<(), TypeError> {
+        match stmt {
+            Stmt::Declare(sym, ty, init) => {
+                check_not_void(sym, ty)?;
+                if *ty == Type::Infer {
+                    let init = init
+                        .as_mut()
+                        .ok_or_else(|| TypeError::CannotInfer(sym.clone()))?;
+                    *ty = self.check_value(init)?;
+                } else if let Some(init) = init {
+                    self.expect_value(init, ty)?;
+                }
+                self.declare(sym.clone(), ty.clone());
+            }
+            Stmt::Assign(target, value) => {
+                self.check_assignable(target)?;
+                let target_ty = self.check_expr(target)?;
+                self.expect_value(value, &target_ty)?;
+            }
+            Stmt::Expr(expr) => {
+                self.check_expr(expr)?;
+            }
+            Stmt::Return(value) => {
+                let return_type = self.return_type.clone();
+                match value {
+                    Some(value) => {
+                        let found = if retype_int_literal(value, &return_type) {
+                            return_type.clone()
+                        } else {
+                            self.check_expr(value)?
+                        };
+                        // `return f();` of a `void` call has no value to return.
+                        if return_type == Type::Void && found != Type::Void {
+                            return Err(TypeError::ReturnValueInVoid(self.function.clone()));
+                        }
+                        expect(&return_type, &found)?;
+                    }
+                    None if return_type != Type::Void => {
+                        return Err(TypeError::MissingReturnValue(self.function.clone()));
+                    }
+                    None => {}
+                }
+            }
+            Stmt::Block(stmts) => {
+                self.scopes.push(dmap::new());
+                self.positions.push(dmap::new());
+                let outer = self.position;
+                let result = stmts.iter_mut().enumerate().try_for_each(|(i, stmt)| {
+                    self.position = i;
+                    self.check_stmt(stmt)
+                });
+                self.position = outer;
+                self.positions.pop();
+                self.scopes.pop();
+                result?;
+            }
+            Stmt::If(cond, then_branch, else_branch) => {
+                self.expect_value(cond, &Type::Bool)?;
+                self.check_stmt(then_branch)?;
+                if let Some(else_branch) = else_branch {
+                    self.check_stmt(else_branch)?;
+                }
+            }
+            Stmt::While(cond, body, _) => {
+                self.expect_value(cond, &Type::Bool)?;
+                self.check_stmt(body)?;
+            }
+            Stmt::Switch(scrutinee, arms, default) => {
+                let ty = self.check_value(scrutinee)?;
+                if !ty.is_integer() {
+                    return Err(TypeError::Mismatch {
+                        expected: Type::Int,
+                        found: ty,
+                    });
+                }
+                let mut seen: DHashSet<&Constant> = dmap::new_set();
+                for (value, arm) in arms {
+                    // Integer constants are `int`, so an arm is checked
+                    // against the range of a narrower scrutinee instead.
+                    let Constant::Int(int) = value else {
+                        return Err(TypeError::Mismatch {
+                            expected: ty,
+                            found: value.ty(),
+                        });
+                    };
+                    if !ty.fits_int(*int) {
+                        return Err(TypeError::CaseOutOfRange {
+                            value: value.clone(),
+                            ty,
+                        });
+                    }
+                    if !seen.insert(value) {
+                        return Err(TypeError::DuplicateCase(value.clone()));
+                    }
+                    self.check_stmt(arm)?;
+                }
+                if let Some(default) = default {
+                    self.check_stmt(default)?;
+                }
+            }
+            Stmt::Break(_) | Stmt::Continue(_) => {}
+        }
+        Ok(())
+    }
+
+    fn expect_expr(&mut self, expr: &mut Expr, expected: &Type) -> Result<(), TypeError> {
+        if retype_int_literal(expr, expected) {
+            return Ok(());
+        }
+        let found = self.check_expr(expr)?;
+        expect(expected, &found)
+    }
+
+    /// Like `expect_expr`, but a `void` expression is reported as
+    /// `VoidValueUsed` rather than a mismatch.
+    fn expect_value(&mut self, expr: &mut Expr, expected: &Type) -> Result<(), TypeError> {
+        let found = self.check_value_as(expr, expected)?;
+        expect(expected, &found)
+    }
+
+    /// Like `check_value`, but an integer literal whose value fits `ty` is
+    /// given that type.
+    fn check_value_as(&mut self, expr: &mut Expr, ty: &Type) -> Result<Type, TypeError> {
+        if retype_int_literal(expr, ty) {
+            return Ok(ty.clone());
+        }
+        self.check_value(expr)
+    }
+
+    /// Returns the type of `expr`, which must produce a value.
+    ///
+    /// Only a call can have type `void`, and its result may only be discarded
+    /// or returned from a `void` function.
+    fn check_value(&mut self, expr: &mut Expr) -> Result<Type, TypeError> {
+        match self.check_expr(expr)? {
+            Type::Void => Err(TypeError::VoidValueUsed),
+            ty => Ok(ty),
+        }
+    }
+
+    /// Returns the type of `expr`.
+    fn check_expr(&mut self, expr: &mut Expr) -> Result<Type, TypeError> {
+        match expr {
+            Expr::Var(sym) => self.lookup(sym),
+            Expr::Const(constant) => {
+                let ty = constant.ty();
+                // A literal no context has given a type takes the default,
+                // or stays `int` if its value does not fit it.
+                let default_int = self.default_int.clone();
+                if ty == Type::Int && retype_int_literal(expr, &default_int) {
+                    return Ok(default_int);
+                }
+                Ok(ty)
+            }
+            Expr::BinOp(op, lhs, rhs) => {
+                // An integer literal takes the type of the other operand.
+                let (mut lhs_ty, mut rhs_ty) = if is_int_literal(lhs) {
+                    let rhs_ty = self.check_value(rhs)?;
+                    (self.check_value_as(lhs, &rhs_ty)?, rhs_ty)
+                } else {
+                    let lhs_ty = self.check_value(lhs)?;
+                    let rhs_ty = self.check_value_as(rhs, &lhs_ty)?;
+                    (lhs_ty, rhs_ty)
+                };
+                if self.config.implicit_int_to_float {
+                    coerce_to_float(lhs, &mut lhs_ty, &rhs_ty);
+                    coerce_to_float(rhs, &mut rhs_ty, &lhs_ty);
+                }
+                check_binop(*op, lhs_ty, rhs_ty)
+            }
+            Expr::Call(sym, args) => {
+                let callee = self.lookup(sym)?;
+                self.check_call(callee, args)
+            }
+            Expr::CallIndirect(callee, args) => {
+                let callee = self.check_expr(callee)?;
+                self.check_call(callee, args)
+            }
+            Expr::ArrayAccess(array, index) => {
+                let array = self.check_expr(array)?;
+                self.expect_expr(index, &Type::Int)?;
+                match array {
+                    Type::Array(elem, _) => Ok(*elem),
+                    ty => Err(TypeError::InvalidAccess(ty)),
+                }
+            }
+            Expr::FieldAccess(object, field) => match self.check_expr(object)? {
+                Type::Struct(fields) => match fields.iter().find(|(name, _)| name == field) {
+                    Some((_, ty)) => Ok(ty.clone()),
+                    None => Err(TypeError::InvalidAccess(Type::Struct(fields))),
+                },
+                ty => Err(TypeError::InvalidAccess(ty)),
+            },
+            Expr::UnOp(UnOp::Not, operand) => {
+                self.expect_value(operand, &Type::Bool)?;
+                Ok(Type::Bool)
+            }
+            Expr::Cast(value, to) => {
+                let from = self.check_value(value)?;
+                if !from.is_numeric() || !to.is_numeric() {
+                    return Err(TypeError::InvalidCast {
+                        from,
+                        to: (**to).clone(),
+                    });
+                }
+                Ok((**to).clone())
+            }
+            Expr::If(cond, then_expr, else_expr) => {
+                self.expect_value(cond, &Type::Bool)?;
+                let then_ty = self.check_expr(then_expr)?;
+                self.expect_expr(else_expr, &then_ty)?;
+                Ok(then_ty)
+            }
+        }
+    }
+
+    fn check_call(&mut self, callee: Type, args: &mut [Expr]) -> Result<Type, TypeError> {
+        let Type::Function(params, ret, variadic) = callee else {
+            return Err(TypeError::NotCallable(callee));
+        };
+        // A variadic callee takes any number of arguments after its parameters.
+        if params.len() > args.len() || (params.len() < args.len() && !variadic) {
+            return Err(TypeError::ArityMismatch {
+                expected: params.len(),
+                found: args.len(),
+            });
+        }
+        for (arg, param) in args.iter_mut().zip(&params) {
+            self.expect_value(arg, param)?;
+        }
+        for arg in &mut args[params.len()..] {
+            self.check_value(arg)?;
+        }
+        Ok(*ret)
+    }
+}
+
+fn expect(expected: &Type, found: &Type) -> Result<(), TypeError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(TypeError::Mismatch {
+            expected: expected.clone(),
+            found: found.clone(),
+        })
+    }
+}
+
+/// Unifies `a` with `b`, recording the types bound to type variables in
+/// `subst`.
+///
+/// Bindings already in `subst` are honored, so unifying several pairs with
+/// the same map instantiates a generic signature consistently. On error,
+/// `subst` may hold bindings made before the conflict was found.
+pub fn unify(a: &Type, b: &Type, subst: &mut DHashMap<Symbol, Type>) -> Result<(), TypeError> {
+    let a = resolve(a, subst);
+    let b = resolve(b, subst);
+    match (&a, &b) {
+        (Type::TypeVar(x), Type::TypeVar(y)) if x == y => Ok(()),
+        (Type::TypeVar(var), ty) | (ty, Type::TypeVar(var)) => {
+            if occurs(var, ty, subst) {
+                return Err(TypeError::RecursiveType {
+                    var: var.clone(),
+                    ty: ty.clone(),
+                });
+            }
+            subst.insert(var.clone(), ty.clone());
+            Ok(())
+        }
+        (
+            Type::Function(a_params, a_ret, a_variadic),
+            Type::Function(b_params, b_ret, b_variadic),
+        ) if a_params.len() == b_params.len() && a_variadic == b_variadic => {
+            for (a, b) in a_params.iter().zip(b_params) {
+                unify(a, b, subst)?;
+            }
+            unify(a_ret, b_ret, subst)
+        }
+        (Type::Array(a_elem, a_size), Type::Array(b_elem, b_size)) if a_size == b_size => {
+            unify(a_elem, b_elem, subst)
+        }
+        // Field order is part of the type, it decides the layout.
+        (Type::Struct(a_fields), Type::Struct(b_fields))
+            if a_fields.len() == b_fields.len()
+                && a_fields
+                    .iter()
+                    .zip(b_fields)
+                    .all(|((a_name, _), (b_name, _))| a_name == b_name) =>
+        {
+            for ((_, a), (_, b)) in a_fields.iter().zip(b_fields) {
+                unify(a, b, subst)?;
+            }
+            Ok(())
+        }
+        _ => expect(&a, &b),
+    }
+}
+
+/// Follows the bindings of a type variable until a non-variable or an unbound
+/// variable is reached.
+fn resolve(ty: &Type, subst: &DHashMap<Symbol, Type>) -> Type {
+    let mut ty = ty;
+    while let Type::TypeVar(var) = ty {
+        match subst.get(var) {
+            Some(bound) => ty = bound,
+            None => break,
+        }
+    }
+    ty.clone()
+}
+
+fn occurs(var: &Symbol, ty: &Type, subst: &DHashMap<Symbol, Type>) -> bool {
+    match resolve(ty, subst) {
+        Type::TypeVar(other) => other == *var,
+        Type::Function(params, ret, _) => {
+            params.iter().any(|param| occurs(var, param, subst)) || occurs(var, &ret, subst)
+        }
+        Type::Array(elem, _) => occurs(var, &elem, subst),
+        Type::Struct(fields) => fields.iter().any(|(_, field)| occurs(var, field, subst)),
+        _ => false,
+    }
+}
+
+fn is_int_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Const(Constant::Int(_)))
+}
+
+/// Gives the integer literal `expr` type `ty` if its value fits it, and
+/// returns whether it did.
+///
+/// A literal given a type other than `int` is cast to it. An explicit cast
+/// is not a literal and keeps its type.
+fn retype_int_literal(expr: &mut Expr, ty: &Type) -> bool {
+    let Expr::Const(Constant::Int(value)) = *expr else {
+        return false;
+    };
+    if !ty.fits_int(value) {
+        return false;
+    }
+    if *ty != Type::Int {
+        let constant = std::mem::replace(expr, Expr::Const(Constant::Int(0)));
+        *expr = Expr::Cast(Box::new(constant), Box::new(ty.clone()));
+    }
+    true
+}
+
+/// Wraps `operand` in a cast to `float` if it is an integer and `other`, the
+/// type of the other operand, is `float`.
+fn coerce_to_float(operand: &mut Box<Expr>, ty: &mut Type, other: &Type) {
+    if ty.is_integer() && other.is_float() {
+        let value = std::mem::replace(&mut **operand, Expr::Const(Constant::Int(0)));
+        **operand = Expr::Cast(Box::new(value), Box::new(Type::Float));
+        *ty = Type::Float;
+    }
+}
+
+fn check_binop(op: BinOp, lhs: Type, rhs: Type) -> Result<Type, TypeError> {
+    let valid = lhs == rhs
+        && match op {
+            // `+` on strings concatenates them.
+            BinOp::Add => lhs.is_numeric() || lhs == Type::String,
+            BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod => lhs.is_numeric(),
+            BinOp::Lt | BinOp::Gt | BinOp::Leq | BinOp::Geq => lhs.is_numeric(),
+            BinOp::Eq | BinOp::Neq => lhs != Type::Void,
+            BinOp::And | BinOp::Or => lhs == Type::Bool,
+        };
+    if !valid {
+        return Err(TypeError::InvalidOperands { op, lhs, rhs });
+    }
+
+    match op {
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod => Ok(lhs),
+        _ => Ok(Type::Bool),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InlineHint;
+
+    fn sym(name: &str) -> Symbol {
+        Symbol(name.to_string())
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(sym(name))
+    }
+
+    fn int_to_int() -> Type {
+        Type::Function(vec![Type::Int], Box::new(Type::Int), false)
+    }
+
+    /// `fn double(x: int) -> int { return x + x; }`
+    fn double() -> Function {
+        Function {
+            name: sym("double"),
+            params: vec![(sym("x"), Type::Int)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![Stmt::Return(Some(Expr::BinOp(
+                BinOp::Add,
+                Box::new(var("x")),
+                Box::new(var("x")),
+            )))]),
+        }
+    }
+
+    fn program(body: Vec<Stmt>) -> Program {
+        Program {
+            functions: vec![
+                double(),
+                Function {
+                    name: sym("main"),
+                    params: vec![],
+                    return_type: Type::Int,
+                    is_external: false,
+                    is_variadic: false,
+                    inline_hint: InlineHint::Default,
+                    body: Stmt::Block(body),
+                },
+            ],
+            globals: vec![],
+            default_int: Type::Int,
+        }
+    }
+
+    #[test]
+    fn test_int_literal_takes_expected_type() {
+        // let x: i8 = 1; let y: i8 = x + 2; return 3;
+        let int = |value| Expr::Const(Constant::Int(value));
+        let mut program = program(vec![
+            Stmt::Declare(sym("x"), Type::Int8, Some(int(1))),
+            Stmt::Declare(
+                sym("y"),
+                Type::Int8,
+                Some(Expr::BinOp(
+                    BinOp::Add,
+                    Box::new(var("x")),
+                    Box::new(int(2)),
+                )),
+            ),
+            Stmt::Return(Some(int(3))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+        let Stmt::Block(body) = &program.functions[1].body else {
+            unreachable!()
+        };
+        assert_eq!(
+            body[0],
+            Stmt::Declare(
+                sym("x"),
+                Type::Int8,
+                Some(Expr::Cast(Box::new(int(1)), Box::new(Type::Int8)))
+            )
+        );
+        assert_eq!(body[2], Stmt::Return(Some(int(3))));
+    }
+
+    #[test]
+    fn test_explicit_cast_keeps_its_type() {
+        // return 3 as i32;
+        let mut program = program(vec![Stmt::Return(Some(Expr::Cast(
+            Box::new(Expr::Const(Constant::Int(3))),
+            Box::new(Type::Int32),
+        )))]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Int,
+                found: Type::Int32,
+            })
+        );
+    }
+
+    #[test]
+    fn test_default_int() {
+        // let x = 1; let y = x + 2; return 3;
+        let int = |value| Expr::Const(Constant::Int(value));
+        let mut program = program(vec![
+            Stmt::Declare(sym("x"), Type::Infer, Some(int(1))),
+            Stmt::Declare(
+                sym("y"),
+                Type::Infer,
+                Some(Expr::BinOp(
+                    BinOp::Add,
+                    Box::new(int(2)),
+                    Box::new(var("x")),
+                )),
+            ),
+            Stmt::Return(Some(int(3))),
+        ]);
+        program.default_int = Type::Int32;
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+        let Stmt::Block(body) = &program.functions[1].body else {
+            unreachable!()
+        };
+        let int32 = |value| Expr::Cast(Box::new(int(value)), Box::new(Type::Int32));
+        assert_eq!(
+            body[..],
+            [
+                Stmt::Declare(sym("x"), Type::Int32, Some(int32(1))),
+                Stmt::Declare(
+                    sym("y"),
+                    Type::Int32,
+                    Some(Expr::BinOp(
+                        BinOp::Add,
+                        Box::new(int32(2)),
+                        Box::new(var("x")),
+                    )),
+                ),
+                Stmt::Return(Some(int(3))),
+            ]
+        );
+        assert_eq!(program.default_int, Type::Int);
+    }
+
+    #[test]
+    fn test_int_literal_out_of_range() {
+        // let x: i8 = 300;
+        let mut program = program(vec![Stmt::Declare(
+            sym("x"),
+            Type::Int8,
+            Some(Expr::Const(Constant::Int(300))),
+        )]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Int8,
+                found: Type::Int,
+            })
+        );
+    }
+
+    #[test]
+    fn test_call_through_function_variable() {
+        // let f: fn(int) -> int = double;
+        // return f(21);
+        let mut program = program(vec![
+            Stmt::Declare(sym("f"), int_to_int(), Some(var("double"))),
+            Stmt::Return(Some(Expr::CallIndirect(
+                Box::new(var("f")),
+                vec![Expr::Const(Constant::Int(21))],
+            ))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_call_indirect_checks_arguments() {
+        let mut program = program(vec![
+            Stmt::Declare(sym("f"), int_to_int(), Some(var("double"))),
+            Stmt::Return(Some(Expr::CallIndirect(
+                Box::new(var("f")),
+                vec![Expr::Const(Constant::Bool(true))],
+            ))),
+        ]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Int,
+                found: Type::Bool,
+            })
+        );
+    }
+
+    #[test]
+    fn test_call_indirect_checks_arity() {
+        let mut program = program(vec![Stmt::Return(Some(Expr::CallIndirect(
+            Box::new(var("double")),
+            vec![],
+        )))]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::ArityMismatch {
+                expected: 1,
+                found: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_call_indirect_on_non_function() {
+        let mut program = program(vec![
+            Stmt::Declare(sym("n"), Type::Int, Some(Expr::Const(Constant::Int(1)))),
+            Stmt::Return(Some(Expr::CallIndirect(Box::new(var("n")), vec![]))),
+        ]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::NotCallable(Type::Int))
+        );
+    }
+
+    #[test]
+    fn test_unresolved_symbol() {
+        let mut program = program(vec![Stmt::Return(Some(var("missing")))]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::UnresolvedSymbol {
+                name: sym("missing"),
+                suggestion: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unresolved_symbol_suggestion() {
+        let mut program = program(vec![
+            Stmt::Declare(
+                sym("length"),
+                Type::Int,
+                Some(Expr::Const(Constant::Int(1))),
+            ),
+            Stmt::Return(Some(var("lenght"))),
+        ]);
+
+        let err = check_program(&mut program).unwrap_err();
+        assert_eq!(
+            err,
+            TypeError::UnresolvedSymbol {
+                name: sym("lenght"),
+                suggestion: Some(sym("length")),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "cannot find `lenght` in this scope; did you mean `length`?"
+        );
+    }
+
+    #[test]
+    fn test_infer_let_type() {
+        // let x = 1;
+        // return x;
+        let mut program = program(vec![
+            Stmt::Declare(sym("x"), Type::Infer, Some(Expr::Const(Constant::Int(1)))),
+            Stmt::Return(Some(var("x"))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+        let Stmt::Block(body) = &program.functions[1].body else {
+            unreachable!()
+        };
+        assert_eq!(
+            body[0],
+            Stmt::Declare(sym("x"), Type::Int, Some(Expr::Const(Constant::Int(1))))
+        );
+    }
+
+    #[test]
+    fn test_infer_let_without_initializer() {
+        // let x;
+        let mut program = program(vec![
+            Stmt::Declare(sym("x"), Type::Infer, None),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::CannotInfer(sym("x")))
+        );
+    }
+
+    fn if_expr(then_value: Constant, else_value: Constant) -> Expr {
+        Expr::If(
+            Box::new(var("c")),
+            Box::new(Expr::Const(then_value)),
+            Box::new(Expr::Const(else_value)),
+        )
+    }
+
+    #[test]
+    fn test_infer_let_from_if_expression() {
+        // let c = true;
+        // let x = if c { 1 } else { 2 };
+        // return x;
+        let value = if_expr(Constant::Int(1), Constant::Int(2));
+        let mut program = program(vec![
+            Stmt::Declare(
+                sym("c"),
+                Type::Infer,
+                Some(Expr::Const(Constant::Bool(true))),
+            ),
+            Stmt::Declare(sym("x"), Type::Infer, Some(value.clone())),
+            Stmt::Return(Some(var("x"))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+        let Stmt::Block(body) = &program.functions[1].body else {
+            unreachable!()
+        };
+        assert_eq!(body[1], Stmt::Declare(sym("x"), Type::Int, Some(value)));
+    }
+
+    #[test]
+    fn test_if_expression_branch_mismatch() {
+        // let c = true;
+        // let x = if c { 1 } else { "one" };
+        let mut program = program(vec![
+            Stmt::Declare(
+                sym("c"),
+                Type::Infer,
+                Some(Expr::Const(Constant::Bool(true))),
+            ),
+            Stmt::Declare(
+                sym("x"),
+                Type::Infer,
+                Some(if_expr(
+                    Constant::Int(1),
+                    Constant::String("one".to_string()),
+                )),
+            ),
+        ]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Int,
+                found: Type::String,
+            })
+        );
+    }
+
+    #[test]
+    fn test_logical_not() {
+        // let done = false;
+        // let x = !done;
+        // return 0;
+        let not = Expr::UnOp(UnOp::Not, Box::new(var("done")));
+        let mut program = program(vec![
+            Stmt::Declare(
+                sym("done"),
+                Type::Infer,
+                Some(Expr::Const(Constant::Bool(false))),
+            ),
+            Stmt::Declare(sym("x"), Type::Infer, Some(not.clone())),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+        let Stmt::Block(body) = &program.functions[1].body else {
+            unreachable!()
+        };
+        assert_eq!(body[1], Stmt::Declare(sym("x"), Type::Bool, Some(not)));
+    }
+
+    #[test]
+    fn test_logical_not_requires_bool() {
+        // return !1;
+        let mut program = program(vec![Stmt::Return(Some(Expr::UnOp(
+            UnOp::Not,
+            Box::new(Expr::Const(Constant::Int(1))),
+        )))]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Bool,
+                found: Type::Int,
+            })
+        );
+    }
+
+    /// `let x = 1 + 2.0;`
+    fn mixed_addition() -> Program {
+        let mut program = program(vec![
+            Stmt::Declare(
+                sym("x"),
+                Type::Infer,
+                Some(Expr::BinOp(
+                    BinOp::Add,
+                    Box::new(Expr::Const(Constant::Int(1))),
+                    Box::new(Expr::Const(Constant::Float(2.0))),
+                )),
+            ),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        program.functions.remove(0);
+        program
+    }
+
+    #[test]
+    fn test_implicit_int_to_float() {
+        let mut program = mixed_addition();
+        let config = TypeCheckConfig {
+            implicit_int_to_float: true,
+        };
+
+        assert_eq!(check_program_with_config(&mut program, &config), Ok(vec![]));
+        let Stmt::Block(body) = &program.functions[0].body else {
+            unreachable!()
+        };
+        assert_eq!(
+            body[0],
+            Stmt::Declare(
+                sym("x"),
+                Type::Float,
+                Some(Expr::BinOp(
+                    BinOp::Add,
+                    Box::new(Expr::Cast(
+                        Box::new(Expr::Const(Constant::Int(1))),
+                        Box::new(Type::Float)
+                    )),
+                    Box::new(Expr::Const(Constant::Float(2.0))),
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn test_mixed_arithmetic_without_coercion() {
+        let mut program = mixed_addition();
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::InvalidOperands {
+                op: BinOp::Add,
+                lhs: Type::Int,
+                rhs: Type::Float,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cast_of_non_numeric() {
+        // return "1" as int;
+        let mut program = program(vec![Stmt::Return(Some(Expr::Cast(
+            Box::new(Expr::Const(Constant::String("1".to_string()))),
+            Box::new(Type::Int),
+        )))]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::InvalidCast {
+                from: Type::String,
+                to: Type::Int,
+            })
+        );
+    }
+
+    /// `switch x { <cases> => return 1, default => return 0 }` in a function
+    /// taking `x: int`.
+    fn switch_on(cases: Vec<Constant>) -> Program {
+        switch_on_type(Type::Int, cases)
+    }
+
+    /// `main(x: <ty>)` switching on `x` with an arm for each of `cases`.
+    fn switch_on_type(ty: Type, cases: Vec<Constant>) -> Program {
+        let ret = |value| Stmt::Return(Some(Expr::Const(Constant::Int(value))));
+        let mut program = program(vec![Stmt::Switch(
+            var("x"),
+            cases.into_iter().map(|case| (case, ret(1))).collect(),
+            Some(Box::new(ret(0))),
+        )]);
+        program.functions[1].params = vec![(sym("x"), ty)];
+        program
+    }
+
+    #[test]
+    fn test_switch() {
+        let mut program = switch_on(vec![Constant::Int(1), Constant::Int(2)]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_switch_on_i32() {
+        let mut program = switch_on_type(Type::Int32, vec![Constant::Int(1), Constant::Int(-2)]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_switch_case_out_of_range() {
+        let mut program = switch_on_type(Type::Int8, vec![Constant::Int(128)]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::CaseOutOfRange {
+                value: Constant::Int(128),
+                ty: Type::Int8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_switch_duplicate_case() {
+        let mut program = switch_on(vec![Constant::Int(1), Constant::Int(1)]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::DuplicateCase(Constant::Int(1)))
+        );
+    }
+
+    #[test]
+    fn test_switch_case_type() {
+        let mut program = switch_on(vec![Constant::Bool(true)]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Int,
+                found: Type::Bool,
+            })
+        );
+    }
+
+    /// `main` with `body`, with `limit: int = 10` as a `const` global.
+    fn with_const_global(body: Vec<Stmt>) -> Program {
+        let mut program = program(body);
+        program.globals.push((
+            sym("limit"),
+            Type::Int,
+            Some(Constant::Int(10)),
+            Mutability::Const,
+        ));
+        program
+    }
+
+    #[test]
+    fn test_global_initializer() {
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.globals.push((
+            sym("X"),
+            Type::Int32,
+            Some(Constant::Int(5)),
+            Mutability::Const,
+        ));
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+
+        program.globals.push((
+            sym("F"),
+            Type::Float,
+            Some(Constant::Int(1)),
+            Mutability::Const,
+        ));
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Float,
+                found: Type::Int,
+            })
+        );
+    }
+
+    #[test]
+    fn test_global_initializer_out_of_range() {
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.globals.push((
+            sym("X"),
+            Type::Int8,
+            Some(Constant::Int(300)),
+            Mutability::Const,
+        ));
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::Mismatch {
+                expected: Type::Int8,
+                found: Type::Int,
+            })
+        );
+    }
+
+    #[test]
+    fn test_assign_to_variable() {
+        // let x: int = 1;
+        // x = limit;
+        // return x;
+        let mut program = with_const_global(vec![
+            Stmt::Declare(sym("x"), Type::Int, Some(Expr::Const(Constant::Int(1)))),
+            Stmt::Assign(var("x"), var("limit")),
+            Stmt::Return(Some(var("x"))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_assign_to_literal() {
+        let target = Expr::Const(Constant::Int(1));
+        let mut program = program(vec![
+            Stmt::Assign(target.clone(), Expr::Const(Constant::Int(2))),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::NotAnLValue(target))
+        );
+    }
+
+    #[test]
+    fn test_assign_to_const_global() {
+        let mut program = with_const_global(vec![
+            Stmt::Assign(var("limit"), Expr::Const(Constant::Int(20))),
+            Stmt::Return(Some(var("limit"))),
+        ]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::AssignToConst(sym("limit")))
+        );
+    }
+
+    #[test]
+    fn test_local_shadowing_const_global_is_assignable() {
+        let mut program = with_const_global(vec![
+            Stmt::Declare(sym("limit"), Type::Int, None),
+            Stmt::Assign(var("limit"), Expr::Const(Constant::Int(20))),
+            Stmt::Return(Some(var("limit"))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_redeclaration_in_same_scope_warns() {
+        // let x = 1;
+        // let y = 2;
+        // let x = 3;
+        // return x;
+        let mut program = program(vec![
+            Stmt::Declare(sym("x"), Type::Infer, Some(Expr::Const(Constant::Int(1)))),
+            Stmt::Declare(sym("y"), Type::Infer, Some(Expr::Const(Constant::Int(2)))),
+            Stmt::Declare(sym("x"), Type::Infer, Some(Expr::Const(Constant::Int(3)))),
+            Stmt::Return(Some(var("x"))),
+        ]);
+
+        let warnings = check_program(&mut program).unwrap();
+        assert_eq!(
+            warnings,
+            vec![Warning::Shadowing {
+                function: sym("main"),
+                name: sym("x"),
+                previous_index: 0,
+                new_index: 2,
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "`x` declared by statement 1 is redeclared by statement 3 of the same block in function `main`"
+        );
+    }
+
+    #[test]
+    fn test_shadowing_in_inner_scope_does_not_warn() {
+        // let x = 1;
+        // { let x = 2; }
+        // return x;
+        let mut program = program(vec![
+            Stmt::Declare(sym("x"), Type::Infer, Some(Expr::Const(Constant::Int(1)))),
+            Stmt::Block(vec![Stmt::Declare(
+                sym("x"),
+                Type::Infer,
+                Some(Expr::Const(Constant::Int(2))),
+            )]),
+            Stmt::Return(Some(var("x"))),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    /// `fn fact(n: int) -> int { <body> }`
+    fn fact(body: Vec<Stmt>) -> Program {
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.functions.push(Function {
+            name: sym("fact"),
+            params: vec![(sym("n"), Type::Int)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(body),
+        });
+        program
+    }
+
+    /// `n * fact(n - 1)`
+    fn fact_step() -> Expr {
+        Expr::BinOp(
+            BinOp::Mul,
+            Box::new(var("n")),
+            Box::new(Expr::Call(
+                sym("fact"),
+                vec![Expr::BinOp(
+                    BinOp::Sub,
+                    Box::new(var("n")),
+                    Box::new(Expr::Const(Constant::Int(1))),
+                )],
+            )),
+        )
+    }
+
+    #[test]
+    fn test_unconditional_recursion() {
+        // return n * fact(n - 1);
+        let mut program = fact(vec![Stmt::Return(Some(fact_step()))]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Ok(vec![Warning::UnconditionalRecursion {
+                function: sym("fact")
+            }])
+        );
+    }
+
+    #[test]
+    fn test_guarded_recursion_does_not_warn() {
+        // if n < 2 { return 1; }
+        // return n * fact(n - 1);
+        let mut program = fact(vec![
+            Stmt::If(
+                Expr::BinOp(
+                    BinOp::Lt,
+                    Box::new(var("n")),
+                    Box::new(Expr::Const(Constant::Int(2))),
+                ),
+                Box::new(Stmt::Block(vec![Stmt::Return(Some(Expr::Const(
+                    Constant::Int(1),
+                )))])),
+                None,
+            ),
+            Stmt::Return(Some(fact_step())),
+        ]);
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+        assert_eq!(
+            detect_unconditional_self_recursion(&program.functions[2]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unify_type_var() {
+        let mut subst = dmap::new();
+
+        assert_eq!(
+            unify(&Type::TypeVar(sym("T")), &Type::Int, &mut subst),
+            Ok(())
+        );
+        assert_eq!(subst.get(&sym("T")), Some(&Type::Int));
+    }
+
+    #[test]
+    fn test_unify_conflicting_bindings() {
+        // fn(T, T) -> T against fn(int, bool) -> int
+        let generic = Type::Function(
+            vec![Type::TypeVar(sym("T")), Type::TypeVar(sym("T"))],
+            Box::new(Type::TypeVar(sym("T"))),
+            false,
+        );
+        let concrete = Type::Function(vec![Type::Int, Type::Bool], Box::new(Type::Int), false);
+
+        assert_eq!(
+            unify(&generic, &concrete, &mut dmap::new()),
+            Err(TypeError::Mismatch {
+                expected: Type::Int,
+                found: Type::Bool,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unify_recursive_type() {
+        let t = Type::TypeVar(sym("T"));
+        let array = Type::Array(Box::new(t.clone()), 2);
+
+        assert_eq!(
+            unify(&t, &array, &mut dmap::new()),
+            Err(TypeError::RecursiveType {
+                var: sym("T"),
+                ty: array,
+            })
+        );
+    }
+
+    /// `fn print(x: int) {}`
+    fn print() -> Function {
+        Function {
+            name: sym("print"),
+            params: vec![(sym("x"), Type::Int)],
+            return_type: Type::Void,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        }
+    }
+
+    /// `fn f() -> <return_type> { <stmt> }`
+    fn returning(return_type: Type, stmt: Stmt) -> Program {
+        Program {
+            functions: vec![Function {
+                name: sym("f"),
+                params: vec![],
+                return_type,
+                is_external: false,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
+                body: Stmt::Block(vec![stmt]),
+            }],
+            globals: vec![],
+            default_int: Type::Int,
+        }
+    }
+
+    fn while_loop(cond: bool, body: Vec<Stmt>) -> Stmt {
+        Stmt::While(
+            Expr::Const(Constant::Bool(cond)),
+            Box::new(Stmt::Block(body)),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_infinite_loop() {
+        // while true {}
+        let mut program = returning(Type::Void, while_loop(true, vec![]));
+
+        assert_eq!(
+            check_program(&mut program),
+            Ok(vec![Warning::InfiniteLoop { function: sym("f") }])
+        );
+    }
+
+    #[test]
+    fn test_loop_with_break_does_not_warn() {
+        // while true { break; }
+        let mut program = returning(Type::Void, while_loop(true, vec![Stmt::Break(None)]));
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_loop_with_return_does_not_warn() {
+        // while true { return; }
+        let mut program = returning(Type::Void, while_loop(true, vec![Stmt::Return(None)]));
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_unreachable_loop_body() {
+        // while false { f(); }
+        let call = Stmt::Expr(Expr::Call(sym("f"), vec![]));
+        let mut program = returning(Type::Void, while_loop(false, vec![call]));
+
+        assert_eq!(
+            check_program(&mut program),
+            Ok(vec![Warning::UnreachableLoopBody { function: sym("f") }])
+        );
+    }
+
+    #[test]
+    fn test_return_value() {
+        let value = || Stmt::Return(Some(Expr::Const(Constant::Int(1))));
+
+        assert_eq!(
+            check_program(&mut returning(Type::Int, value())),
+            Ok(vec![])
+        );
+        assert_eq!(
+            check_program(&mut returning(Type::Void, value())),
+            Err(TypeError::ReturnValueInVoid(sym("f")))
+        );
+    }
+
+    #[test]
+    fn test_return_without_value() {
+        assert_eq!(
+            check_program(&mut returning(Type::Void, Stmt::Return(None))),
+            Ok(vec![])
+        );
+        assert_eq!(
+            check_program(&mut returning(Type::Int, Stmt::Return(None))),
+            Err(TypeError::MissingReturnValue(sym("f")))
+        );
+    }
+
+    #[test]
+    fn test_return_void_call_from_void_function() {
+        // fn f() { return print(1); }
+        let mut program = returning(
+            Type::Void,
+            Stmt::Return(Some(Expr::Call(
+                sym("print"),
+                vec![Expr::Const(Constant::Int(1))],
+            ))),
+        );
+        program.functions.push(print());
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_call_to_external() {
+        // extern fn abs(x: int) -> int;
+        // return abs(-1);
+        let mut program = program(vec![Stmt::Return(Some(Expr::Call(
+            sym("abs"),
+            vec![Expr::Const(Constant::Int(-1))],
+        )))]);
+        program.functions.push(Function {
+            name: sym("abs"),
+            params: vec![(sym("x"), Type::Int)],
+            return_type: Type::Int,
+            is_external: true,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        });
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    fn printf() -> Function {
+        Function {
+            name: sym("printf"),
+            params: vec![(sym("fmt"), Type::String)],
+            return_type: Type::Int32,
+            is_external: true,
+            is_variadic: true,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![]),
+        }
+    }
+
+    #[test]
+    fn test_call_to_variadic() {
+        // printf("%d %f", 1, 2.5);
+        // return 0;
+        let mut program = program(vec![
+            Stmt::Expr(Expr::Call(
+                sym("printf"),
+                vec![
+                    Expr::Const(Constant::String("%d %f".to_string())),
+                    Expr::Const(Constant::Int(1)),
+                    Expr::Const(Constant::Float(2.5)),
+                ],
+            )),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        program.functions.push(printf());
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_variadic_call_without_fixed_args() {
+        // printf();
+        let mut program = program(vec![Stmt::Expr(Expr::Call(sym("printf"), vec![]))]);
+        program.functions.push(printf());
+
+        assert!(matches!(
+            check_program(&mut program),
+            Err(TypeError::ArityMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_void_call_as_statement() {
+        // print(1);
+        // return 0;
+        let mut program = program(vec![
+            Stmt::Expr(Expr::Call(
+                sym("print"),
+                vec![Expr::Const(Constant::Int(1))],
+            )),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        program.functions.push(print());
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_void_call_as_value() {
+        // let x = print(1);
+        let mut program = program(vec![Stmt::Declare(
+            sym("x"),
+            Type::Infer,
+            Some(Expr::Call(
+                sym("print"),
+                vec![Expr::Const(Constant::Int(1))],
+            )),
+        )]);
+        program.functions.push(print());
+
+        assert_eq!(check_program(&mut program), Err(TypeError::VoidValueUsed));
+    }
+
+    #[test]
+    fn test_void_call_as_operand() {
+        // return print(1) + 1;
+        let mut program = program(vec![Stmt::Return(Some(Expr::BinOp(
+            BinOp::Add,
+            Box::new(Expr::Call(
+                sym("print"),
+                vec![Expr::Const(Constant::Int(1))],
+            )),
+            Box::new(Expr::Const(Constant::Int(1))),
+        )))]);
+        program.functions.push(print());
+
+        assert_eq!(check_program(&mut program), Err(TypeError::VoidValueUsed));
+    }
+
+    #[test]
+    fn test_void_param() {
+        let mut program = program(vec![Stmt::Return(Some(Expr::Const(Constant::Int(0))))]);
+        program.functions[0].params = vec![(sym("x"), Type::Void)];
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::VoidNotAllowedHere(sym("x")))
+        );
+    }
+
+    #[test]
+    fn test_void_variable() {
+        // let f: fn(void) -> int;
+        let fn_type = Type::Function(vec![Type::Void], Box::new(Type::Int), false);
+        let mut program = program(vec![
+            Stmt::Declare(sym("f"), fn_type, None),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+
+        assert_eq!(
+            check_program(&mut program),
+            Err(TypeError::VoidNotAllowedHere(sym("f")))
+        );
+    }
+
+    #[test]
+    fn test_void_return() {
+        // let p: fn(int) -> void = print;
+        // p(1);
+        let print_type = Type::Function(vec![Type::Int], Box::new(Type::Void), false);
+        let mut program = program(vec![
+            Stmt::Declare(sym("p"), print_type, Some(var("print"))),
+            Stmt::Expr(Expr::CallIndirect(
+                Box::new(var("p")),
+                vec![Expr::Const(Constant::Int(1))],
+            )),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        program.functions.push(print());
+
+        assert_eq!(check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_string_operators() {
+        let string = |value: &str| Box::new(Expr::Const(Constant::String(value.to_string())));
+        let check = |op| {
+            let mut program = program(vec![
+                Stmt::Declare(
+                    sym("s"),
+                    Type::Infer,
+                    Some(Expr::BinOp(op, string("a"), string("b"))),
+                ),
+                Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+            ]);
+            check_program(&mut program).map(|_| {
+                let Stmt::Block(body) = &program.functions[1].body else {
+                    unreachable!()
+                };
+                let Stmt::Declare(_, ty, _) = &body[0] else {
+                    unreachable!()
+                };
+                ty.clone()
+            })
+        };
+
+        assert_eq!(check(BinOp::Add), Ok(Type::String));
+        assert_eq!(check(BinOp::Eq), Ok(Type::Bool));
+        assert_eq!(check(BinOp::Neq), Ok(Type::Bool));
+        assert_eq!(
+            check(BinOp::Lt),
+            Err(TypeError::InvalidOperands {
+                op: BinOp::Lt,
+                lhs: Type::String,
+                rhs: Type::String,
+            })
+        );
+        assert!(check(BinOp::Sub).is_err());
+    }
+
+    #[test]
+    fn test_assert_takes_bool() {
+        let assert = |arg| Stmt::Expr(Expr::Call(Intrinsic::Assert.symbol(), vec![arg]));
+
+        let mut ok = program(vec![
+            assert(Expr::Const(Constant::Bool(true))),
+            Stmt::Return(Some(Expr::Const(Constant::Int(0)))),
+        ]);
+        assert_eq!(check_program(&mut ok), Ok(vec![]));
+
+        let mut err = program(vec![assert(Expr::Const(Constant::Int(1)))]);
+        assert_eq!(
+            check_program(&mut err),
+            Err(TypeError::Mismatch {
+                expected: Type::Bool,
+                found: Type::Int,
+            })
+        );
+    }
+
+    /// `fn sign(x: int) -> int { if x < 0 { return -1; } <else> }`
+    fn sign(else_branch: Option<Stmt>) -> Function {
+        let ret = |value| Stmt::Return(Some(Expr::Const(Constant::Int(value))));
+        Function {
+            name: sym("sign"),
+            params: vec![(sym("x"), Type::Int)],
+            return_type: Type::Int,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body: Stmt::Block(vec![Stmt::If(
+                Expr::BinOp(
+                    BinOp::Lt,
+                    Box::new(var("x")),
+                    Box::new(Expr::Const(Constant::Int(0))),
+                ),
+                Box::new(Stmt::Block(vec![ret(-1)])),
+                else_branch.map(Box::new),
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_returns_in_both_branches() {
+        let function = sign(Some(Stmt::Block(vec![Stmt::Return(Some(Expr::Const(
+            Constant::Int(1),
+        )))])));
+
+        assert_eq!(check_returns(&function), Ok(()));
+    }
+
+    #[test]
+    fn test_returns_in_one_branch() {
+        let function = sign(Some(Stmt::Block(vec![])));
+
+        assert_eq!(
+            check_returns(&function),
+            Err(TypeError::MissingReturn(sym("sign")))
+        );
+        assert_eq!(
+            check_returns(&sign(None)),
+            Err(TypeError::MissingReturn(sym("sign")))
+        );
+    }
+
+    #[test]
+    fn test_while_does_not_return() {
+        let mut function = sign(None);
+        function.body = Stmt::Block(vec![Stmt::While(
+            Expr::Const(Constant::Bool(true)),
+            Box::new(Stmt::Return(Some(var("x")))),
+            None,
+        )]);
+
+        assert_eq!(
+            check_returns(&function),
+            Err(TypeError::MissingReturn(sym("sign")))
+        );
+    }
+}
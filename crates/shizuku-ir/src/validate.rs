@@ -0,0 +1,144 @@
+//! Structural invariants of a `Program` that its types cannot express.
+
+use crate::Program;
+use crate::Stmt;
+use crate::Symbol;
+use shizuku_common::dmap;
+use shizuku_common::dmap::DHashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructuralError {
+    /// Two functions with the same name.
+    DuplicateFunction(Symbol),
+    /// Two globals with the same name.
+    DuplicateGlobal(Symbol),
+    /// A function whose body is not a `Stmt::Block`.
+    BodyNotBlock(Symbol),
+}
+
+impl fmt::Display for StructuralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructuralError::DuplicateFunction(sym) => {
+                write!(f, "function `{}` is defined multiple times", sym.0)
+            }
+            StructuralError::DuplicateGlobal(sym) => {
+                write!(f, "global `{}` is defined multiple times", sym.0)
+            }
+            StructuralError::BodyNotBlock(sym) => {
+                write!(f, "body of function `{}` is not a block", sym.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StructuralError {}
+
+impl Program {
+    /// Checks the structural invariants codegen relies on, returning every
+    /// violation found.
+    ///
+    /// This is much cheaper than `check_program` and meant to catch malformed
+    /// IR from a buggy producer. Struct field names are not checked for
+    /// duplicates, a field access always resolves to the first one.
+    pub fn validate(&self) -> Result<(), Vec<StructuralError>> {
+        let mut errors = Vec::new();
+
+        let mut functions: DHashSet<&Symbol> = dmap::new_set();
+        for function in &self.functions {
+            if !functions.insert(&function.name) {
+                errors.push(StructuralError::DuplicateFunction(function.name.clone()));
+            }
+            if !matches!(function.body, Stmt::Block(_)) {
+                errors.push(StructuralError::BodyNotBlock(function.name.clone()));
+            }
+        }
+
+        let mut globals: DHashSet<&Symbol> = dmap::new_set();
+        for (name, ..) in &self.globals {
+            if !globals.insert(name) {
+                errors.push(StructuralError::DuplicateGlobal(name.clone()));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Function;
+    use crate::InlineHint;
+    use crate::Mutability;
+    use crate::Type;
+
+    fn function(name: &str, body: Stmt) -> Function {
+        Function {
+            name: Symbol(name.to_string()),
+            params: vec![],
+            return_type: Type::Void,
+            is_external: false,
+            is_variadic: false,
+            inline_hint: InlineHint::Default,
+            body,
+        }
+    }
+
+    #[test]
+    fn test_valid_program() {
+        let program = Program {
+            functions: vec![
+                function("f", Stmt::Block(vec![])),
+                function("g", Stmt::Block(vec![])),
+            ],
+            globals: vec![(
+                Symbol("x".to_string()),
+                Type::Int,
+                None,
+                Mutability::Mutable,
+            )],
+            default_int: Type::Int,
+        };
+
+        assert_eq!(program.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_duplicate_function() {
+        let program = Program {
+            functions: vec![
+                function("f", Stmt::Block(vec![])),
+                function("f", Stmt::Block(vec![])),
+            ],
+            globals: vec![],
+            default_int: Type::Int,
+        };
+
+        assert_eq!(
+            program.validate(),
+            Err(vec![StructuralError::DuplicateFunction(Symbol(
+                "f".to_string()
+            ))])
+        );
+    }
+
+    #[test]
+    fn test_body_not_block() {
+        let program = Program {
+            functions: vec![function("f", Stmt::Return(None))],
+            globals: vec![],
+            default_int: Type::Int,
+        };
+
+        assert_eq!(
+            program.validate(),
+            Err(vec![StructuralError::BodyNotBlock(Symbol("f".to_string()))])
+        );
+    }
+}
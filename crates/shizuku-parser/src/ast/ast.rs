@@ -1,21 +1,32 @@
+use crate::token::Base;
 use crate::token::Token;
-use ecow::EcoString;
+use crate::token::TokenStr;
 
 /// Represents a node in the Abstract Syntax Tree (AST).
 #[derive(Debug, PartialEq)]
 pub enum ASTNode {
     Function {
-        name: EcoString,
+        name: TokenStr,
+        type_params: Vec<TokenStr>,
         params: Vec<Parameter>,
         return_type: Option<Type>,
+        /// Declared with `extern` and defined elsewhere, `body` is then empty.
+        is_external: bool,
+        /// The parameter list ends in `...`, accepting any further arguments.
+        is_variadic: bool,
+        /// Declared with `pub`.
+        is_public: bool,
+        /// Decorators written before the function, in source order.
+        decorators: Vec<Decorator>,
         body: Vec<ASTNode>,
     },
     Variable {
-        name: EcoString,
+        name: TokenStr,
+        var_type: Option<Type>,
         value: Option<Box<ASTNode>>,
     },
     GlobalVariable {
-        name: EcoString,
+        name: TokenStr,
         var_type: Type,
         value: Option<Box<ASTNode>>,
     },
@@ -23,7 +34,8 @@ pub enum ASTNode {
         value: Option<Box<ASTNode>>,
     },
     Struct {
-        name: EcoString,
+        name: TokenStr,
+        type_params: Vec<TokenStr>,
         fields: Vec<StructField>,
     },
     BinaryOp {
@@ -40,7 +52,7 @@ pub enum ASTNode {
         value: Box<ASTNode>,
     },
     FunctionCall {
-        name: EcoString,
+        name: TokenStr,
         arguments: Vec<ASTNode>,
     },
     If {
@@ -49,6 +61,8 @@ pub enum ASTNode {
         else_branch: Option<Vec<ASTNode>>,
     },
     While {
+        /// The loop's label without the quote, e.g. `outer` for `'outer:`.
+        label: Option<TokenStr>,
         condition: Box<ASTNode>,
         body: Vec<ASTNode>,
     },
@@ -62,12 +76,32 @@ pub enum ASTNode {
         body: Vec<ASTNode>,
         condition: Box<ASTNode>,
     },
-    Break,
-    Continue,
+    /// `break` out of the innermost loop, or the one named by `label`.
+    Break {
+        label: Option<TokenStr>,
+    },
+    /// `continue` the innermost loop, or the one named by `label`.
+    Continue {
+        label: Option<TokenStr>,
+    },
     ExpressionStatement(Box<ASTNode>),
+    /// The final expression of a block written without a trailing `;`.
+    Tail(Box<ASTNode>),
+    /// A statement or item with the comments around it, only produced by
+    /// `Parser::preserve_comments`.
+    Commented {
+        leading_comments: Vec<TokenStr>,
+        node: Box<ASTNode>,
+        trailing_comments: Vec<TokenStr>,
+    },
+    /// A literal value as written in the source.
+    Literal(Literal),
+    /// An expression wrapped in parentheses. Kept for printing the source back
+    /// faithfully, it has no meaning of its own and is dropped by lowering.
+    Paren(Box<ASTNode>),
     FieldAccess {
         object: Box<ASTNode>,
-        field: EcoString,
+        field: TokenStr,
     },
     PointerDereference {
         pointer: Box<ASTNode>,
@@ -77,24 +111,69 @@ pub enum ASTNode {
         then_branch: Box<ASTNode>,
         else_branch: Box<ASTNode>,
     },
+    /// `expr?`, returning early from the enclosing function when `expr` holds
+    /// no value. Parsed ahead of the optional types it will work on.
+    Try {
+        expr: Box<ASTNode>,
+    },
+}
+
+impl ASTNode {
+    /// The node itself, or the one an `ASTNode::Commented` wraps.
+    pub fn without_comments(&self) -> &ASTNode {
+        match self {
+            ASTNode::Commented { node, .. } => node.without_comments(),
+            node => node,
+        }
+    }
+}
+
+/// Represents a literal, keeping the spelling from the source.
+#[derive(Debug, PartialEq)]
+pub enum Literal {
+    Int { base: Base, value: TokenStr },
+    Float { value: TokenStr },
+    Char { value: char },
+    String { value: TokenStr },
 }
 
 /// Represents a function parameter.
 #[derive(Debug, PartialEq)]
 pub struct Parameter {
-    pub name: EcoString,
+    pub name: TokenStr,
     pub param_type: Type,
 }
 
+/// Represents a decorator on a function (e.g., `@memoize` or `@cache(64)`).
+#[derive(Debug, PartialEq)]
+pub struct Decorator {
+    pub name: TokenStr,
+    /// The arguments in parentheses, empty when there are none.
+    pub args: Vec<ASTNode>,
+}
+
 /// Represents a type in the language.
 #[derive(Debug, PartialEq)]
-pub struct Type {
-    pub name: EcoString,
+pub enum Type {
+    /// A type referred to by name (e.g., `i32` or `Point`).
+    Named { name: TokenStr },
+    /// A function type (e.g., `fn(int) -> bool`).
+    Function {
+        params: Vec<Type>,
+        return_type: Box<Type>,
+    },
+    /// A fixed-size array type (e.g., `[i32; 4]`).
+    Array {
+        element: Box<Type>,
+        size: Box<ASTNode>,
+    },
+    /// A pointer type (e.g., `*Point`).
+    Pointer { pointee: Box<Type> },
 }
 
 /// Represents a field in a struct declaration.
 #[derive(Debug, PartialEq)]
 pub struct StructField {
-    pub name: EcoString,
+    pub name: TokenStr,
     pub field_type: Type,
 }
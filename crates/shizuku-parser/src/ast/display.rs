@@ -0,0 +1,455 @@
+use super::ASTNode;
+use super::Literal;
+use super::Type;
+use crate::token::Token;
+use crate::token::TokenStr;
+use std::fmt;
+
+const INDENT: &str = "    ";
+
+/// Prints the node back as source code.
+///
+/// Statements are printed in statement position, with a trailing `;` where the
+/// grammar wants one. Every binary operation is parenthesized, so the printed
+/// source shows the shape of the tree rather than relying on precedence.
+/// Parentheses from the source are printed too, unless they would double
+/// those of a binary operation.
+impl fmt::Display for ASTNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_statement(self) {
+            fmt_stmt(self, 0, f)
+        } else {
+            fmt_expr(self, f)
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Named { name } => write!(f, "{}", name),
+            Type::Function {
+                params,
+                return_type,
+            } => {
+                write!(f, "fn(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, ") -> {}", return_type)
+            }
+            Type::Array { element, size } => {
+                write!(f, "[{}; ", element)?;
+                fmt_expr(size, f)?;
+                write!(f, "]")
+            }
+            Type::Pointer { pointee } => write!(f, "*{}", pointee),
+        }
+    }
+}
+
+fn is_statement(node: &ASTNode) -> bool {
+    matches!(
+        node,
+        ASTNode::Function { .. }
+            | ASTNode::Variable { .. }
+            | ASTNode::GlobalVariable { .. }
+            | ASTNode::Return { .. }
+            | ASTNode::Struct { .. }
+            | ASTNode::Assignment { .. }
+            | ASTNode::If { .. }
+            | ASTNode::While { .. }
+            | ASTNode::For { .. }
+            | ASTNode::DoWhile { .. }
+            | ASTNode::Break { .. }
+            | ASTNode::Continue { .. }
+            | ASTNode::ExpressionStatement(_)
+            | ASTNode::Tail(_)
+            | ASTNode::Commented { .. }
+    )
+}
+
+fn indent(depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for _ in 0..depth {
+        f.write_str(INDENT)?;
+    }
+    Ok(())
+}
+
+/// Prints `{`, the statements one per line, and the closing `}` at `depth`.
+fn fmt_block(body: &[ASTNode], depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{{")?;
+    for stmt in body {
+        fmt_stmt(stmt, depth + 1, f)?;
+        writeln!(f)?;
+    }
+    indent(depth, f)?;
+    write!(f, "}}")
+}
+
+fn fmt_stmt(node: &ASTNode, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let ASTNode::Commented {
+        leading_comments,
+        node,
+        trailing_comments,
+    } = node
+    {
+        for comment in leading_comments {
+            indent(depth, f)?;
+            writeln!(f, "//{}", comment)?;
+        }
+        fmt_stmt(node, depth, f)?;
+        for comment in trailing_comments {
+            write!(f, " //{}", comment)?;
+        }
+        return Ok(());
+    }
+    indent(depth, f)?;
+    match node {
+        ASTNode::Function {
+            name,
+            type_params,
+            params,
+            return_type,
+            is_external,
+            is_variadic,
+            is_public,
+            decorators,
+            body,
+        } => {
+            for decorator in decorators {
+                write!(f, "@{}", decorator.name)?;
+                if !decorator.args.is_empty() {
+                    write!(f, "(")?;
+                    for (i, arg) in decorator.args.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        fmt_expr(arg, f)?;
+                    }
+                    write!(f, ")")?;
+                }
+                writeln!(f)?;
+                indent(depth, f)?;
+            }
+            if *is_public {
+                write!(f, "pub ")?;
+            }
+            if *is_external {
+                write!(f, "extern ")?;
+            }
+            write!(f, "fn {}", name)?;
+            fmt_type_params(type_params, f)?;
+            write!(f, "(")?;
+            for (i, param) in params.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}: {}", param.name, param.param_type)?;
+            }
+            if *is_variadic {
+                if !params.is_empty() {
+                    write!(f, ", ")?;
+                }
+                write!(f, "...")?;
+            }
+            write!(f, ")")?;
+            if let Some(return_type) = return_type {
+                write!(f, " -> {}", return_type)?;
+            }
+            if *is_external {
+                return write!(f, ";");
+            }
+            write!(f, " ")?;
+            fmt_block(body, depth, f)
+        }
+        ASTNode::Variable {
+            name,
+            var_type,
+            value,
+        } => {
+            write!(f, "let {}", name)?;
+            if let Some(var_type) = var_type {
+                write!(f, ": {}", var_type)?;
+            }
+            if let Some(value) = value {
+                write!(f, " = ")?;
+                fmt_expr(value, f)?;
+            }
+            write!(f, ";")
+        }
+        ASTNode::GlobalVariable {
+            name,
+            var_type,
+            value,
+        } => {
+            write!(f, "let {}: {}", name, var_type)?;
+            if let Some(value) = value {
+                write!(f, " = ")?;
+                fmt_expr(value, f)?;
+            }
+            write!(f, ";")
+        }
+        ASTNode::Return { value } => {
+            write!(f, "return")?;
+            if let Some(value) = value {
+                write!(f, " ")?;
+                fmt_expr(value, f)?;
+            }
+            write!(f, ";")
+        }
+        ASTNode::Struct {
+            name,
+            type_params,
+            fields,
+        } => {
+            write!(f, "struct {}", name)?;
+            fmt_type_params(type_params, f)?;
+            writeln!(f, " {{")?;
+            for field in fields {
+                indent(depth + 1, f)?;
+                writeln!(f, "{}: {},", field.name, field.field_type)?;
+            }
+            indent(depth, f)?;
+            write!(f, "}}")
+        }
+        ASTNode::Assignment { target, value } => {
+            fmt_expr(target, f)?;
+            write!(f, " = ")?;
+            fmt_expr(value, f)?;
+            write!(f, ";")
+        }
+        ASTNode::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            write!(f, "if ")?;
+            fmt_expr(condition, f)?;
+            write!(f, " ")?;
+            fmt_block(then_branch, depth, f)?;
+            if let Some(else_branch) = else_branch {
+                write!(f, " else ")?;
+                fmt_block(else_branch, depth, f)?;
+            }
+            Ok(())
+        }
+        ASTNode::While {
+            label,
+            condition,
+            body,
+        } => {
+            if let Some(label) = label {
+                write!(f, "'{}: ", label)?;
+            }
+            write!(f, "while ")?;
+            fmt_expr(condition, f)?;
+            write!(f, " ")?;
+            fmt_block(body, depth, f)
+        }
+        ASTNode::For {
+            init,
+            condition,
+            increment,
+            body,
+        } => {
+            write!(f, "for (")?;
+            for (i, part) in [init, condition, increment].into_iter().enumerate() {
+                if i > 0 {
+                    write!(f, "; ")?;
+                }
+                if let Some(part) = part {
+                    fmt_expr(part, f)?;
+                }
+            }
+            write!(f, ") ")?;
+            fmt_block(body, depth, f)
+        }
+        ASTNode::DoWhile { body, condition } => {
+            write!(f, "do ")?;
+            fmt_block(body, depth, f)?;
+            write!(f, " while ")?;
+            fmt_expr(condition, f)?;
+            write!(f, ";")
+        }
+        ASTNode::Break { label } => {
+            write!(f, "break")?;
+            fmt_label(label, f)?;
+            write!(f, ";")
+        }
+        ASTNode::Continue { label } => {
+            write!(f, "continue")?;
+            fmt_label(label, f)?;
+            write!(f, ";")
+        }
+        ASTNode::ExpressionStatement(expr) => {
+            fmt_expr(expr, f)?;
+            write!(f, ";")
+        }
+        ASTNode::Tail(expr) => fmt_expr(expr, f),
+        expr => {
+            fmt_expr(expr, f)?;
+            write!(f, ";")
+        }
+    }
+}
+
+/// Prints a block on one line, for an if-expression (e.g., `{ a }`).
+fn fmt_inline_block(body: &[ASTNode], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{{")?;
+    for stmt in body {
+        write!(f, " ")?;
+        fmt_stmt(stmt, 0, f)?;
+    }
+    write!(f, " }}")
+}
+
+fn fmt_label(label: &Option<TokenStr>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match label {
+        Some(label) => write!(f, " '{}", label),
+        None => Ok(()),
+    }
+}
+
+fn fmt_type_params(type_params: &[TokenStr], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if type_params.is_empty() {
+        return Ok(());
+    }
+    write!(f, "<{}>", type_params.join(", "))
+}
+
+/// Writes `value` as a string literal, escaping the characters the lexer
+/// decodes escapes into.
+fn fmt_string(value: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            '\\' => write!(f, "\\\\")?,
+            '"' => write!(f, "\\\"")?,
+            '\0' => write!(f, "\\0")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn fmt_expr(node: &ASTNode, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match node {
+        ASTNode::Variable { name, .. } => write!(f, "{}", name),
+        ASTNode::Literal(literal) => match literal {
+            Literal::Int { value, .. } | Literal::Float { value } => write!(f, "{}", value),
+            Literal::Char { value } => write!(f, "'{}'", value),
+            Literal::String { value } => fmt_string(value, f),
+        },
+        ASTNode::BinaryOp {
+            left,
+            operator,
+            right,
+        } => {
+            write!(f, "(")?;
+            fmt_expr(left, f)?;
+            write!(f, " {} ", operator_str(operator))?;
+            fmt_expr(right, f)?;
+            write!(f, ")")
+        }
+        // Binary operations and ternaries already print their own parentheses.
+        ASTNode::Paren(inner) => match **inner {
+            ASTNode::BinaryOp { .. } | ASTNode::Ternary { .. } => fmt_expr(inner, f),
+            _ => {
+                write!(f, "(")?;
+                fmt_expr(inner, f)?;
+                write!(f, ")")
+            }
+        },
+        ASTNode::UnaryOp { operator, operand } => {
+            write!(f, "{}", operator_str(operator))?;
+            fmt_expr(operand, f)
+        }
+        ASTNode::FunctionCall { name, arguments } => {
+            write!(f, "{}(", name)?;
+            for (i, arg) in arguments.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_expr(arg, f)?;
+            }
+            write!(f, ")")
+        }
+        ASTNode::FieldAccess { object, field } => {
+            fmt_expr(object, f)?;
+            write!(f, ".{}", field)
+        }
+        ASTNode::PointerDereference { pointer } => {
+            write!(f, "*")?;
+            fmt_expr(pointer, f)
+        }
+        ASTNode::Try { expr } => {
+            fmt_expr(expr, f)?;
+            write!(f, "?")
+        }
+        ASTNode::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            write!(f, "(")?;
+            fmt_expr(condition, f)?;
+            write!(f, " ? ")?;
+            fmt_expr(then_branch, f)?;
+            write!(f, " : ")?;
+            fmt_expr(else_branch, f)?;
+            write!(f, ")")
+        }
+        ASTNode::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            write!(f, "if ")?;
+            fmt_expr(condition, f)?;
+            write!(f, " ")?;
+            fmt_inline_block(then_branch, f)?;
+            if let Some(else_branch) = else_branch {
+                write!(f, " else ")?;
+                match &else_branch[..] {
+                    [chained @ ASTNode::If { .. }] => fmt_expr(chained, f)?,
+                    _ => fmt_inline_block(else_branch, f)?,
+                }
+            }
+            Ok(())
+        }
+        // Statements only appear here in malformed trees, print them inline.
+        stmt => fmt_stmt(stmt, 0, f),
+    }
+}
+
+fn operator_str(operator: &Token) -> &'static str {
+    match operator {
+        Token::Plus => "+",
+        Token::Minus => "-",
+        Token::Asterisk => "*",
+        Token::Slash => "/",
+        Token::Percent => "%",
+        Token::Equal2 => "==",
+        Token::ExclamationEqual => "!=",
+        Token::LArrow => "<",
+        Token::RArrow => ">",
+        Token::LArrowEqual => "<=",
+        Token::RArrowEqual => ">=",
+        Token::And => "and",
+        Token::Or => "or",
+        Token::Exclamation => "!",
+        Token::Amper => "&",
+        Token::Pipe => "|",
+        Token::LArrow2 => "<<",
+        Token::RArrow2 => ">>",
+        Token::PipeRArrow => "|>",
+        _ => "<?>",
+    }
+}
@@ -1,2 +1,3 @@
 mod ast;
+mod display;
 pub use ast::*;
@@ -0,0 +1,77 @@
+//! Rendering of a message against the source line it points at.
+
+use crate::SrcSpan;
+use std::fmt::Write;
+
+const BLUE: &str = "\x1b[34m";
+const RED: &str = "\x1b[31m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// How `render_diagnostic` should style its output.
+///
+/// Nothing is detected automatically, the caller decides whether its output
+/// is a terminal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticStyle {
+    /// Colorize the output with ANSI escapes.
+    pub color: bool,
+}
+
+impl DiagnosticStyle {
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{code}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Renders `message` followed by the line `span` starts on, with carets under
+/// the spanned part of that line.
+///
+/// A span crossing a line break is underlined up to the end of its first line.
+///
+/// ```text
+/// error: expected ')', found '{'
+///   |
+/// 1 | fn main( {
+///   |          ^
+/// ```
+pub fn render_diagnostic(
+    source: &str,
+    span: SrcSpan,
+    message: &str,
+    style: DiagnosticStyle,
+) -> String {
+    let start = (span.start as usize).min(source.len());
+    let end = (span.end as usize).clamp(start, source.len());
+
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |i| start + i);
+    let line = &source[line_start..line_end];
+    let line_number = source[..line_start].matches('\n').count() + 1;
+
+    let column = source[line_start..start].chars().count();
+    let width = source[start..end.min(line_end)].chars().count().max(1);
+
+    let number = line_number.to_string();
+    let padding = " ".repeat(number.len());
+    let gutter = style.paint(BLUE, &format!("{padding} |"));
+
+    let mut out = String::new();
+    writeln!(out, "{}", style.paint(BOLD, &format!("error: {message}"))).unwrap();
+    writeln!(out, "{gutter}").unwrap();
+    writeln!(out, "{} {line}", style.paint(BLUE, &format!("{number} |"))).unwrap();
+    write!(
+        out,
+        "{gutter} {}{}",
+        " ".repeat(column),
+        style.paint(RED, &"^".repeat(width))
+    )
+    .unwrap();
+    out
+}
@@ -0,0 +1,53 @@
+use super::Lexer;
+use crate::token::Token;
+use serde_json::Value;
+use serde_json::json;
+
+/// Lexes `source` and returns its tokens as a JSON array, for tools that
+/// cannot link against the lexer.
+///
+/// Each token is an object `{ "start", "end", "kind", "text" }`, where `kind`
+/// is the name of the `Token` variant and `text` the source it spans. The
+/// array ends with the `EOF` token, or with an object `{ "error", "start",
+/// "end" }` if lexing fails, since the lexer cannot go on after an error.
+pub fn tokenize_to_json(source: &str) -> String {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next() {
+            Ok((start, token, end)) => {
+                let eof = token == Token::EOF;
+                tokens.push(json!({
+                    "start": start,
+                    "end": end,
+                    "kind": kind(&token),
+                    "text": source.get(start as usize..end as usize).unwrap_or(""),
+                }));
+                if eof {
+                    break;
+                }
+            }
+            Err(error) => {
+                tokens.push(json!({
+                    "error": error.error.to_string(),
+                    "start": error.location.start,
+                    "end": error.location.end,
+                }));
+                break;
+            }
+        }
+    }
+    Value::Array(tokens).to_string()
+}
+
+/// The name of the variant of `token`, e.g. `Ident` for `Token::Ident { .. }`.
+fn kind(token: &Token) -> String {
+    let debug = format!("{:?}", token);
+    debug
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
@@ -1,15 +1,32 @@
+#[cfg(feature = "serde")]
+mod json;
 mod number;
+mod peekable;
+mod recovery;
+mod relex;
+mod stream;
 mod utils;
 
-use crate::span::SrcSpan;
+use crate::SrcSpan;
 use crate::token::Base;
 use crate::token::Token;
-use ecow::EcoString;
+use crate::token::TokenStr;
+use crate::token::TokenStrBuf;
+use crate::token::finish_token_str;
 use number::State;
+use number::is_accepting;
 use number::state_transition;
 use utils::is_id_continue;
 use utils::is_id_start;
 use utils::is_whitespace;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+pub use json::tokenize_to_json;
+pub use peekable::PeekableLexer;
+pub use recovery::tokenize_all;
+pub use relex::relex_from;
+pub use stream::TokenStream;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum LiteralType {
@@ -22,33 +39,142 @@ pub enum LiteralType {
     ExpFloat,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum LexicalErrorType {
     UnexpectedStringEnd, // Unterminated string literal
     UnrecognizedToken { tok: char },
-    IllegalLiteral { tok: char },
+    IllegalLiteral { tok: char, partial: TokenStr }, // `partial` is the literal up to and including `tok`
     UnexpectedCharEnd, // Unterminated char literal
     EmptyCharLiteral,
+    NonAsciiIdentifier { tok: char }, // Rejected by `LexerConfig::allow_unicode_idents`
+    IdentifierTooLong { max: usize }, // Longer than `LexerConfig::max_ident_len`
+    TooManyErrors,                    // More than `LexerConfig::max_errors`, see `tokenize_all`
+    InvalidNumberSuffix { suffix_start: u32 }, // Letters right after a complete literal, as in `10abc`
+    InvalidEscape { ch: char },                // The char after `\` in a string literal
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct LexicalError {
     pub error: LexicalErrorType,
     pub location: SrcSpan,
 }
 
+impl LexicalErrorType {
+    /// A stable code identifying this kind of error, in the `E0xxx` range.
+    ///
+    /// Codes are never reused or renumbered, so they can be linked to
+    /// documentation and matched by tools.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexicalErrorType::UnexpectedStringEnd => "E0001",
+            LexicalErrorType::UnrecognizedToken { .. } => "E0002",
+            LexicalErrorType::IllegalLiteral { .. } => "E0003",
+            LexicalErrorType::UnexpectedCharEnd => "E0004",
+            LexicalErrorType::EmptyCharLiteral => "E0005",
+            LexicalErrorType::NonAsciiIdentifier { .. } => "E0006",
+            LexicalErrorType::IdentifierTooLong { .. } => "E0007",
+            LexicalErrorType::TooManyErrors => "E0008",
+            LexicalErrorType::InvalidNumberSuffix { .. } => "E0009",
+            LexicalErrorType::InvalidEscape { .. } => "E0010",
+        }
+    }
+}
+
+impl fmt::Display for LexicalErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexicalErrorType::UnexpectedStringEnd => write!(f, "unterminated string literal"),
+            LexicalErrorType::UnrecognizedToken { tok } => {
+                write!(f, "unrecognized character {:?}", tok)
+            }
+            LexicalErrorType::IllegalLiteral { tok, partial } => {
+                write!(f, "invalid character {:?} in numeric literal '{}'", tok, partial)
+            }
+            LexicalErrorType::UnexpectedCharEnd => write!(f, "unterminated char literal"),
+            LexicalErrorType::EmptyCharLiteral => write!(f, "empty char literal"),
+            LexicalErrorType::NonAsciiIdentifier { tok } => {
+                write!(f, "non-ASCII character {:?} in identifier", tok)
+            }
+            LexicalErrorType::IdentifierTooLong { max } => {
+                write!(f, "identifier longer than {} characters", max)
+            }
+            LexicalErrorType::TooManyErrors => write!(f, "too many errors, giving up"),
+            LexicalErrorType::InvalidNumberSuffix { suffix_start } => {
+                write!(f, "invalid suffix on numeric literal at {}", suffix_start)
+            }
+            LexicalErrorType::InvalidEscape { ch } => {
+                write!(f, "unknown escape sequence '\\{}'", ch)
+            }
+        }
+    }
+}
+
+impl fmt::Display for LexicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.error, self.location.start, self.location.end
+        )
+    }
+}
+
 pub type LOC = u32;
 pub type Spanned = (LOC, Token, LOC);
 pub type LexResult = Result<Spanned, LexicalError>;
 
+/// Panics unless every span in `tokens` ends no earlier than it starts and
+/// no later than the next one starts, as in the output of a lexer.
+pub fn assert_spans_monotonic(tokens: &[Spanned]) {
+    for (start, token, end) in tokens {
+        assert!(start <= end, "{token:?} spans {start}..{end}");
+    }
+    for pair in tokens.windows(2) {
+        let (_, prev, prev_end) = &pair[0];
+        let (next_start, next, _) = &pair[1];
+        assert!(
+            prev_end <= next_start,
+            "{prev:?} ending at {prev_end} overlaps {next:?} starting at {next_start}"
+        );
+    }
+}
+
+/// Options controlling which source the lexer accepts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LexerConfig {
+    /// Accept Unicode XID identifiers rather than only ASCII ones.
+    pub allow_unicode_idents: bool,
+    /// Reject identifiers longer than this many characters.
+    pub max_ident_len: Option<usize>,
+    /// Emit `Token::Whitespace` for whitespace between tokens, so a formatter
+    /// can rebuild the source from the tokens alone.
+    pub emit_trivia: bool,
+    /// Errors `tokenize_all` collects before giving up on the source.
+    pub max_errors: usize,
+}
+
+impl Default for LexerConfig {
+    fn default() -> Self {
+        Self {
+            allow_unicode_idents: true,
+            max_ident_len: None,
+            emit_trivia: false,
+            max_errors: 100,
+        }
+    }
+}
+
 /// A lexer for the Shizuku language.
 pub struct Lexer<I>
 where
     I: Iterator<Item = (LOC, char)>,
 {
     stream: I,
+    config: LexerConfig,
 
     pub pending: Vec<Spanned>,
+    /// An error `peek_token` ran into, for `next` to return.
+    peeked_error: Option<LexicalError>,
 
     pub chr0: Option<char>,
     pub chr1: Option<char>,
@@ -63,9 +189,16 @@ where
 {
     /// Creates a new lexer from the given stream
     pub fn new(stream: I) -> Self {
+        Self::with_config(stream, LexerConfig::default())
+    }
+
+    /// Creates a new lexer from the given stream with non-default options
+    pub fn with_config(stream: I, config: LexerConfig) -> Self {
         let mut lexer = Self {
             stream,
+            config,
             pending: Vec::new(),
+            peeked_error: None,
             location: 0,
             // current char
             chr0: None,
@@ -76,10 +209,24 @@ where
         };
         let _ = lexer.consume();
         let _ = lexer.consume();
+        // On empty input both advances above step past EOF, yet it is at 0.
+        if lexer.chr0.is_none() {
+            lexer.loc0 = 0;
+            lexer.loc1 = 1;
+        }
         lexer.location = 0;
+        lexer.skip_shebang();
         lexer
     }
 
+    /// Skips a `#!` line at the very start of the source, so scripts can be
+    /// run directly. The trailing newline is left to be lexed as usual.
+    fn skip_shebang(&mut self) {
+        if self.loc0 == 0 && self.chr0 == Some('#') && self.chr1 == Some('!') {
+            self.skip_while(|c| c != '\n');
+        }
+    }
+
     fn skip_while(&mut self, mut predicate: impl FnMut(char) -> bool) {
         while self.chr0.is_some_and(&mut predicate) {
             self.consume();
@@ -116,10 +263,20 @@ where
     }
 
     fn emit(&mut self, spanned: Spanned) {
+        #[cfg(debug_assertions)]
+        {
+            let (start, ref token, end) = spanned;
+            assert!(start <= end, "{token:?} ends at {end} before its start {start}");
+            let pos = self.get_pos();
+            assert!(end <= pos, "{token:?} ends at {end} past the lexer at {pos}");
+        }
         self.pending.push(spanned);
     }
 
     pub fn next(&mut self) -> LexResult {
+        if let Some(error) = self.peeked_error.take() {
+            return Err(error);
+        }
         while self.pending.is_empty() {
             self.advance_token()?;
         }
@@ -127,6 +284,24 @@ where
         Ok(self.pending.remove(0))
     }
 
+    /// Returns what the next call to `next` returns, without consuming it.
+    ///
+    /// The token is lexed into `pending` if that is empty, where `next` takes
+    /// it from.
+    pub fn peek_token(&mut self) -> LexResult {
+        if let Some(error) = &self.peeked_error {
+            return Err(error.clone());
+        }
+        while self.pending.is_empty() {
+            if let Err(error) = self.advance_token() {
+                self.peeked_error = Some(error.clone());
+                return Err(error);
+            }
+        }
+
+        Ok(self.pending[0].clone())
+    }
+
     /// Consumes `char_count` characters and emits `expected_token`
     fn consume_expect_token(&mut self, expected_token: Token, char_count: u32) {
         let start_pos = self.get_pos();
@@ -171,6 +346,9 @@ where
             '%' => {
                 self.consume_expect_token(Token::Percent, 1);
             }
+            '*' => {
+                self.consume_expect_token(Token::Asterisk, 1);
+            }
             ',' => {
                 self.consume_expect_token(Token::Comma, 1);
             }
@@ -263,10 +441,20 @@ where
                 }
             }
             '.' if !(self.next_chr_is(|c| c.is_ascii_digit())) => {
-                // handle `..` and `.`
+                // handle `...`, `..` and `.`
                 match self.chr1 {
                     Some('.') => {
-                        self.consume_expect_token(Token::Dot2, 2);
+                        let start = self.get_pos();
+                        self.consume();
+                        self.consume();
+                        let token = if self.chr0 == Some('.') {
+                            self.consume();
+                            Token::Dot3
+                        } else {
+                            Token::Dot2
+                        };
+                        let end = self.get_pos();
+                        self.emit((start, token, end));
                     }
                     _ => {
                         self.consume_expect_token(Token::Dot, 1);
@@ -290,11 +478,11 @@ where
                 self.emit(string_lit);
             }
             '\'' => {
-                let char_lit = self.consume_char_literal()?;
-                self.emit(char_lit);
+                let char_or_label = self.consume_char_literal_or_label()?;
+                self.emit(char_or_label);
             }
             c if is_id_start(c) => {
-                let id_or_keyword = self.consume_ident_or_keyword();
+                let id_or_keyword = self.consume_ident_or_keyword()?;
                 self.emit(id_or_keyword);
             }
             // handle integer or float
@@ -325,6 +513,8 @@ where
                     self.consume();
                     let end = self.get_pos();
                     self.emit((start, Token::NewLine, end));
+                } else if self.config.emit_trivia {
+                    self.consume_whitespace();
                 } else {
                     self.consume();
                 }
@@ -346,6 +536,18 @@ impl<I> Lexer<I>
 where
     I: Iterator<Item = (LOC, char)>,
 {
+    /// Consumes a run of whitespace up to the next newline and emits it.
+    fn consume_whitespace(&mut self) {
+        let start = self.get_pos();
+        let mut content = TokenStrBuf::new();
+        while let Some(c) = self.chr0.filter(|&c| is_whitespace(c) && c != '\n') {
+            content.push(c);
+            self.consume();
+        }
+        let end = self.get_pos();
+        self.emit((start, Token::Whitespace { content: finish_token_str(content) }, end));
+    }
+
     fn consume_comment_or_doc(&mut self) -> Spanned {
         enum Kind {
             Comment,
@@ -369,7 +571,7 @@ where
             }
         };
 
-        let mut content = EcoString::new();
+        let mut content = TokenStrBuf::new();
 
         let start_pos = self.get_pos();
         while self.chr0 != Some('\n') {
@@ -383,8 +585,12 @@ where
         let end_pos = self.get_pos();
 
         let token = match kind {
-            Kind::Comment => Token::Comment { content },
-            Kind::Doc => Token::CommentDoc { content },
+            Kind::Comment => Token::Comment {
+                content: finish_token_str(content),
+            },
+            Kind::Doc => Token::CommentDoc {
+                content: finish_token_str(content),
+            },
         };
 
         (start_pos, token, end_pos)
@@ -395,18 +601,43 @@ where
             .unwrap_or(false)
     }
 
-    fn consume_ident_or_keyword(&mut self) -> Spanned {
+    fn consume_ident_or_keyword(&mut self) -> Result<Spanned, LexicalError> {
         debug_assert!(self.chr0.is_some());
         debug_assert!(is_id_start(self.chr0.unwrap()));
 
-        let mut name = EcoString::new();
+        let mut name = TokenStrBuf::new();
+        let mut len = 0;
 
         let start = self.get_pos();
-        name.push(self.chr0.unwrap());
-        self.consume();
         while let Some(chr) = self.chr0 {
-            if is_id_continue(chr) {
+            if name.is_empty() || is_id_continue(chr) {
+                if let Some(max) = self.config.max_ident_len
+                    && len == max
+                {
+                    // Skip the rest of the run so the span covers all of it.
+                    while self.chr0.is_some_and(is_id_continue) {
+                        self.consume();
+                    }
+                    return Err(LexicalError {
+                        error: LexicalErrorType::IdentifierTooLong { max },
+                        location: SrcSpan {
+                            start,
+                            end: self.get_pos(),
+                        },
+                    });
+                }
+                if !chr.is_ascii() && !self.config.allow_unicode_idents {
+                    let location = self.get_pos();
+                    return Err(LexicalError {
+                        error: LexicalErrorType::NonAsciiIdentifier { tok: chr },
+                        location: SrcSpan {
+                            start: location,
+                            end: location,
+                        },
+                    });
+                }
                 name.push(chr);
+                len += 1;
                 self.consume();
             } else {
                 break;
@@ -415,17 +646,31 @@ where
         let end = self.get_pos();
 
         if let Some(token) = Token::try_from_keywords(&name) {
-            (start, token, end)
+            Ok((start, token, end))
         } else {
-            (start, Token::Ident { name }, end)
+            let name = finish_token_str(name);
+            Ok((start, Token::Ident { name }, end))
         }
     }
-    fn consume_char_literal(&mut self) -> Result<Spanned, LexicalError> {
+    /// Lexes a char literal, or a label such as `'outer`.
+    ///
+    /// A quote followed by an identifier is a label unless the identifier is
+    /// a single char closed by another quote, as in `'a'`.
+    fn consume_char_literal_or_label(&mut self) -> Result<Spanned, LexicalError> {
         debug_assert!(self.chr0 == Some('\''));
 
         let start = self.get_pos();
         self.consume();
 
+        if self.chr0.is_some_and(is_id_start) && self.next_chr_is(|c| c != '\'') {
+            let mut name = TokenStrBuf::new();
+            while self.chr0.is_some_and(is_id_continue) {
+                name.push(self.consume().unwrap());
+            }
+            let name = finish_token_str(name);
+            return Ok((start, Token::Label { name }, self.get_pos()));
+        }
+
         let chr = match self.chr0 {
             Some('\'') => {
                 self.consume();
@@ -474,12 +719,41 @@ where
         let start = self.get_pos();
         self.consume(); // Consume opening quote
 
-        let mut value = EcoString::new();
+        let mut value = TokenStrBuf::new();
+        // Reported once the closing quote is consumed, so lexing resumes
+        // after the string.
+        let mut invalid_escape = None;
 
         while let Some(c) = self.chr0 {
             if c == '"' {
                 break;
             }
+            if c == '\\' {
+                let escape_start = self.get_pos();
+                self.consume();
+                let Some(ch) = self.chr0 else {
+                    break;
+                };
+                self.consume();
+                value.push(match ch {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    '\\' | '"' => ch,
+                    _ => {
+                        invalid_escape.get_or_insert(LexicalError {
+                            error: LexicalErrorType::InvalidEscape { ch },
+                            location: SrcSpan {
+                                start: escape_start,
+                                end: self.get_pos(),
+                            },
+                        });
+                        continue;
+                    }
+                });
+                continue;
+            }
             value.push(c);
             self.consume();
         }
@@ -496,7 +770,11 @@ where
 
         self.consume(); // Consume closing quote
         let end = self.get_pos();
+        if let Some(error) = invalid_escape {
+            return Err(error);
+        }
 
+        let value = finish_token_str(value);
         Ok((start, Token::String { value }, end))
     }
 
@@ -505,7 +783,7 @@ where
         debug_assert!(self.chr0.is_some());
 
         let mut state = State::Start;
-        let mut value = EcoString::new();
+        let mut value = TokenStrBuf::new();
         let start = self.get_pos();
 
         let mut new_state;
@@ -514,7 +792,6 @@ where
         loop {
             let chr = self.chr0;
             new_state = state_transition(state, chr);
-            println!("chr: {chr:?} {state:?} -> {new_state:?}");
 
             debug_assert!(
                 chr.is_some()
@@ -532,17 +809,41 @@ where
                     return Err(LexicalError {
                         error: LexicalErrorType::IllegalLiteral {
                             tok: prev_chr.unwrap(),
+                            partial: finish_token_str(value),
                         },
                         location: SrcSpan { start, end },
                     });
                 }
 
+                // Letters after a complete literal are a suffix, such as a
+                // typo in `10abc`; report and skip all of it.
+                if is_accepting(state) && chr != Some('_') && chr.is_some_and(is_id_start) {
+                    let suffix_start = self.get_pos();
+                    while self.chr0.is_some_and(is_id_continue) {
+                        self.consume();
+                    }
+                    let end = self.get_pos();
+
+                    return Err(LexicalError {
+                        error: LexicalErrorType::InvalidNumberSuffix { suffix_start },
+                        location: SrcSpan { start, end },
+                    });
+                }
+
                 value.push(chr.unwrap());
                 self.consume();
                 let end = self.get_pos();
+                // Skip the rest of the bad literal, so lexing resumes after it
+                // rather than on its remaining digits.
+                while self.chr0.is_some_and(is_id_continue) {
+                    self.consume();
+                }
 
                 return Err(LexicalError {
-                    error: LexicalErrorType::IllegalLiteral { tok: chr.unwrap() },
+                    error: LexicalErrorType::IllegalLiteral {
+                        tok: chr.unwrap(),
+                        partial: finish_token_str(value),
+                    },
                     location: SrcSpan { start, end },
                 });
             }
@@ -556,6 +857,7 @@ where
 
         debug_assert!(new_state == State::End);
         let end = self.get_pos();
+        let value = finish_token_str(value);
 
         match state {
             State::Bin => {
@@ -738,7 +1040,11 @@ mod token_tests {
     test_single_token!(test_greathan_equal, ">=", Token::RArrowEqual);
     test_single_token!(test_dot, ".", Token::Dot);
     test_single_token!(test_dotdot, "..", Token::Dot2);
+    test_single_token!(test_dotdotdot, "...", Token::Dot3);
     test_single_token!(test_slash, "/", Token::Slash);
+    test_single_token!(test_label, "'outer", Token::Label {
+        name: "outer".into()
+    });
 
     #[test]
     fn test_ident() {
@@ -833,6 +1139,18 @@ mod token_tests {
         (0, Token::String { value: "".into() }, r#""""#.len() as u32)
     );
 
+    test_string_literal!(
+        test_string_escapes,
+        r#""a\n\t\r\\\"\0b""#,
+        (
+            0,
+            Token::String {
+                value: "a\n\t\r\\\"\0b".into()
+            },
+            r#""a\n\t\r\\\"\0b""#.len() as u32
+        )
+    );
+
     test_string_literal!(
         test_char_literal,
         "'a'",
@@ -857,6 +1175,11 @@ mod token_tests {
         }
     );
 
+    test_invalid_string_literal!(test_invalid_string_escape, r#""a\qb""#, LexicalError {
+        error: LexicalErrorType::InvalidEscape { ch: 'q' },
+        location: SrcSpan { start: 2, end: 4 }
+    });
+
     test_invalid_string_literal!(test_unterminated_char_literal, "'a", LexicalError {
         error: LexicalErrorType::UnexpectedCharEnd,
         location: SrcSpan { start: 0, end: 2 }
@@ -22,7 +22,7 @@ use crate::token::Token;
 ///     "b" | "B" -> BIN
 ///     "." -> DOT
 ///     "e" | "E" -> EXP  // e.g. `0e1`
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     "0"-> ZERO  // e.g. `02` is invalid but `00` is valid
 ///     .. -> ERROR
 ///
@@ -30,21 +30,21 @@ use crate::token::Token;
 ///     "0".."9"-> INT
 ///     "." -> DOT
 ///     "e" | "E" -> EXP
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     "_"  -> INT_UNDERSCORE
 ///     .. -> ERROR
 ///
 /// DOT:
 ///     "0".."9" -> FRAC
 ///     "e" | "E" -> EXP  // e.g. `.2e1`
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     .. -> ERROR
 ///
 /// FRAC:
 ///     "0".."9" -> FRAC
 ///     "e" | "E" -> EXP
 ///     "_"  -> FRAC_UNDERSCORE
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     .. -> ERROR
 ///
 /// EXP:
@@ -59,25 +59,25 @@ use crate::token::Token;
 /// EXP_INT:
 ///     "0".."9" -> EXP_INT
 ///     "_"  -> EXP_INT_UNDERSCORE
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     .. -> ERROR
 ///
 /// HEX:
 ///     "0".."9" | "a".."f" | "A".."F" -> HEX
 ///     "_"  -> HEX_UNDERSCORE
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     .. -> ERROR
 ///
 /// OCT:
 ///     "0".."7" -> OCT
 ///     "_"  -> OCT_UNDERSCORE
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     .. -> ERROR
 ///
 /// BIN:
 ///     "0" | "1" -> BIN
 ///     "_"  -> BIN_UNDERSCORE
-///     WHITESPACE | EOF -> END
+///     WHITESPACE | DELIMITER | EOF -> END
 ///     .. -> ERROR
 ///
 /// INT_UNDERSCORE:
@@ -133,20 +133,31 @@ pub(super) enum State {
     Error,
 }
 
+fn is_delimiter(chr: char) -> bool {
+    !(chr.is_alphanumeric() || chr == '_' || chr == '.')
+}
+
+/// True if the characters read so far form a complete literal.
+pub(super) fn is_accepting(state: State) -> bool {
+    matches!(
+        state,
+        State::Zero
+            | State::Int
+            | State::Dot
+            | State::Frac
+            | State::ExpInt
+            | State::Hex
+            | State::Oct
+            | State::Bin
+    )
+}
+
 pub(super) fn state_transition(state: State, chr: Option<char>) -> State {
+    let accepting = is_accepting(state);
+
     // handle EOF
     if chr.is_none() || is_whitespace(chr.unwrap()) {
-        if matches!(
-            state,
-            State::Zero
-                | State::Int
-                | State::Dot
-                | State::Frac
-                | State::ExpInt
-                | State::Hex
-                | State::Oct
-                | State::Bin
-        ) {
+        if accepting {
             return State::End;
         } else {
             return State::Error;
@@ -155,6 +166,12 @@ pub(super) fn state_transition(state: State, chr: Option<char>) -> State {
 
     let chr = chr.unwrap();
 
+    // A delimiter such as `;` or `)` ends the number, but letters, `_` and `.`
+    // are still part of it, so `1a` stays an error.
+    if accepting && is_delimiter(chr) {
+        return State::End;
+    }
+
     match state {
         State::Start => {
             if chr == '+' || chr == '-' {
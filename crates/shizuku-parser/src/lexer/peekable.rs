@@ -0,0 +1,56 @@
+use super::LOC;
+use super::LexResult;
+use super::Lexer;
+use crate::token::Token;
+
+/// Adapts a `Lexer` to allow looking at the next item without consuming it.
+///
+/// Unlike `std::iter::Peekable` over a `TokenStream`, items keep their
+/// `Result`, so a lexical error is peeked and returned in its place among the
+/// tokens. Nothing is returned after `Token::EOF` or an error.
+pub struct PeekableLexer<I>
+where
+    I: Iterator<Item = (LOC, char)>,
+{
+    lexer: Lexer<I>,
+    /// The item pulled by `peek`, `Some(None)` once the lexer is exhausted.
+    peeked: Option<Option<LexResult>>,
+    done: bool,
+}
+
+impl<I> PeekableLexer<I>
+where
+    I: Iterator<Item = (LOC, char)>,
+{
+    pub fn new(lexer: Lexer<I>) -> Self {
+        Self {
+            lexer,
+            peeked: None,
+            done: false,
+        }
+    }
+
+    /// The item the next call to `next_token` returns.
+    pub fn peek(&mut self) -> Option<&LexResult> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.pull());
+        }
+        self.peeked.as_ref().and_then(Option::as_ref)
+    }
+
+    pub fn next_token(&mut self) -> Option<LexResult> {
+        match self.peeked.take() {
+            Some(item) => item,
+            None => self.pull(),
+        }
+    }
+
+    fn pull(&mut self) -> Option<LexResult> {
+        if self.done {
+            return None;
+        }
+        let item = self.lexer.next();
+        self.done = matches!(item, Ok((_, Token::EOF, _)) | Err(_));
+        Some(item)
+    }
+}
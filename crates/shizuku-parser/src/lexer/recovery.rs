@@ -0,0 +1,48 @@
+use super::Lexer;
+use super::LexerConfig;
+use super::LexicalError;
+use super::LexicalErrorType;
+use super::Spanned;
+use crate::token::Token;
+
+/// Lexes all of `source`, continuing past lexical errors, and returns the
+/// tokens up to and including `Token::EOF` along with every error.
+///
+/// A character that fails to lex is skipped. Once more than
+/// `LexerConfig::max_errors` errors are found, lexing stops: the error list
+/// then ends in a `LexicalErrorType::TooManyErrors` at the first error not
+/// collected, and the tokens end without `Token::EOF`.
+pub fn tokenize_all(source: &str, config: LexerConfig) -> (Vec<Spanned>, Vec<LexicalError>) {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::with_config(chars, config);
+    let mut tokens = vec![];
+    let mut errors = vec![];
+    loop {
+        let pos = lexer.get_pos();
+        match lexer.next() {
+            Ok(token) => {
+                let eof = token.1 == Token::EOF;
+                tokens.push(token);
+                if eof {
+                    break;
+                }
+            }
+            Err(error) if errors.len() == config.max_errors => {
+                errors.push(LexicalError {
+                    error: LexicalErrorType::TooManyErrors,
+                    location: error.location,
+                });
+                break;
+            }
+            Err(error) => {
+                // Some errors leave the lexer on the offending character.
+                let stuck = lexer.get_pos() == pos || lexer.get_pos() == error.location.start;
+                if stuck {
+                    lexer.consume();
+                }
+                errors.push(error);
+            }
+        }
+    }
+    (tokens, errors)
+}
@@ -0,0 +1,38 @@
+use super::Lexer;
+use super::Spanned;
+use crate::token::Token;
+
+/// Re-lexes `source` after an edit starting at `start_offset`, reusing the
+/// tokens of `prior` (the tokens of the source before the edit) that precede
+/// it.
+///
+/// A token ending exactly at the edit is re-lexed too, since the edit may
+/// extend it (`ab` followed by an inserted `c`). Lexing resumes at the end of
+/// the last reused token, which is where a full lex would resume as well, so
+/// the result is identical to lexing the whole source. If the tail fails to
+/// lex, the tokens up to the error are returned.
+pub fn relex_from(source: &str, start_offset: u32, prior: &[Spanned]) -> Vec<Spanned> {
+    let keep = prior
+        .iter()
+        .take_while(|(_, tok, end)| *end < start_offset && *tok != Token::EOF)
+        .count();
+    let mut tokens = prior[..keep].to_vec();
+    let resume = tokens.last().map_or(0, |(_, _, end)| *end);
+
+    let tail = &source[resume as usize..];
+    if tail.is_empty() {
+        tokens.push((resume, Token::EOF, resume));
+        return tokens;
+    }
+
+    let chars = tail.char_indices().map(|(i, c)| (resume + i as u32, c));
+    let mut lexer = Lexer::new(chars);
+    while let Ok(token) = lexer.next() {
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+    tokens
+}
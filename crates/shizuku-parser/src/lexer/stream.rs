@@ -0,0 +1,60 @@
+use super::LOC;
+use super::Lexer;
+use super::LexicalError;
+use super::Spanned;
+use crate::token::Token;
+
+/// Adapts a `Lexer` into an iterator of its tokens.
+///
+/// The stream ends after yielding `Token::EOF`. A lexical error ends it as
+/// well, the error is then available from `error`.
+pub struct TokenStream<I>
+where
+    I: Iterator<Item = (LOC, char)>,
+{
+    lexer: Lexer<I>,
+    done: bool,
+    error: Option<LexicalError>,
+}
+
+impl<I> TokenStream<I>
+where
+    I: Iterator<Item = (LOC, char)>,
+{
+    pub fn new(lexer: Lexer<I>) -> Self {
+        Self {
+            lexer,
+            done: false,
+            error: None,
+        }
+    }
+
+    /// The lexical error that ended the stream, if any.
+    pub fn error(&self) -> Option<&LexicalError> {
+        self.error.as_ref()
+    }
+}
+
+impl<I> Iterator for TokenStream<I>
+where
+    I: Iterator<Item = (LOC, char)>,
+{
+    type Item = Spanned;
+
+    fn next(&mut self) -> Option<Spanned> {
+        if self.done {
+            return None;
+        }
+        match self.lexer.next() {
+            Ok(token) => {
+                self.done = token.1 == Token::EOF;
+                Some(token)
+            }
+            Err(error) => {
+                self.done = true;
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
@@ -1,16 +1,29 @@
 #![feature(is_ascii_octdigit)]
 #![allow(unused)]
 pub mod ast;
+mod diagnostic;
 pub mod lexer;
 pub mod parser;
-mod span;
 mod token;
 
 pub use ast::ASTNode;
+pub use diagnostic::DiagnosticStyle;
+pub use diagnostic::render_diagnostic;
 pub use lexer::Lexer;
+pub use lexer::LexerConfig;
 pub use lexer::LexicalError;
 pub use lexer::LexicalErrorType;
+pub use lexer::PeekableLexer;
+pub use lexer::TokenStream;
+pub use lexer::relex_from;
+pub use lexer::tokenize_all;
+#[cfg(feature = "serde")]
+pub use lexer::tokenize_to_json;
+pub use parser::ParseError;
+pub use parser::ParseErrorType;
 pub use parser::Parser;
-pub use span::SrcSpan;
+pub use shizuku_common::span::LineIndex;
+pub use shizuku_common::span::SrcSpan;
 pub use token::Base as NumberBase;
 pub use token::Token;
+pub use token::TokenStr;
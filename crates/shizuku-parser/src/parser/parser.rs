@@ -1,7 +1,128 @@
+use crate::LineIndex;
+use crate::SrcSpan;
 use crate::ast::ASTNode;
+use crate::ast::Decorator;
+use crate::ast::Literal;
 use crate::ast::Parameter;
+use crate::ast::StructField;
 use crate::ast::Type;
+use crate::lexer::LOC;
+use crate::lexer::Lexer;
+use crate::lexer::LexicalError;
+use crate::lexer::TokenStream;
 use crate::token::Token;
+use crate::token::TokenStr;
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorType {
+    ExpectedToken { expected: Token, found: Token },
+    UnexpectedEof,
+    ExpectedFunctionName,
+    ExpectedVariableName,
+    ExpectedParameterType,
+    ExpectedTypeAnnotation,
+    ExpectedStructName,
+    ExpectedFieldName,
+    ExpectedTypeParameter,
+    InvalidParameter { tok: Token },
+    InvalidStatement { tok: Token },
+    InvalidExpression { tok: Token },
+    ExpectedLoopAfterLabel,
+    ExpectedDecoratorName,
+    ExpectedFunctionAfterDecorator,
+    ExpectedFunctionAfterPub,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub error: ParseErrorType,
+    pub location: SrcSpan,
+}
+
+impl ParseErrorType {
+    /// A stable code identifying this kind of error, in the `E1xxx` range.
+    ///
+    /// Codes are never reused or renumbered, so they can be linked to
+    /// documentation and matched by tools.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseErrorType::ExpectedToken { .. } => "E1001",
+            ParseErrorType::UnexpectedEof => "E1002",
+            ParseErrorType::ExpectedFunctionName => "E1003",
+            ParseErrorType::ExpectedVariableName => "E1004",
+            ParseErrorType::ExpectedParameterType => "E1005",
+            ParseErrorType::ExpectedTypeAnnotation => "E1006",
+            ParseErrorType::ExpectedStructName => "E1007",
+            ParseErrorType::ExpectedFieldName => "E1008",
+            ParseErrorType::ExpectedTypeParameter => "E1009",
+            ParseErrorType::InvalidParameter { .. } => "E1010",
+            ParseErrorType::InvalidStatement { .. } => "E1011",
+            ParseErrorType::InvalidExpression { .. } => "E1012",
+            ParseErrorType::ExpectedLoopAfterLabel => "E1013",
+            ParseErrorType::ExpectedDecoratorName => "E1014",
+            ParseErrorType::ExpectedFunctionAfterDecorator => "E1015",
+            ParseErrorType::ExpectedFunctionAfterPub => "E1016",
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorType::ExpectedToken { expected, found } => {
+                write!(f, "expected '{}', found '{}'", expected, found)
+            }
+            ParseErrorType::UnexpectedEof => write!(f, "unexpected end of input"),
+            ParseErrorType::ExpectedFunctionName => write!(f, "expected function name"),
+            ParseErrorType::ExpectedVariableName => write!(f, "expected variable name"),
+            ParseErrorType::ExpectedParameterType => write!(f, "expected a type for parameter"),
+            ParseErrorType::ExpectedTypeAnnotation => write!(f, "expected a type annotation"),
+            ParseErrorType::ExpectedStructName => write!(f, "expected struct name"),
+            ParseErrorType::ExpectedFieldName => write!(f, "expected field name"),
+            ParseErrorType::ExpectedTypeParameter => write!(f, "expected type parameter name"),
+            ParseErrorType::InvalidParameter { tok } => {
+                write!(f, "unexpected token in parameter list: {:?}", tok)
+            }
+            ParseErrorType::InvalidStatement { tok } => {
+                write!(f, "unexpected token in statement: {:?}", tok)
+            }
+            ParseErrorType::InvalidExpression { tok } => {
+                write!(f, "unexpected token in expression: {:?}", tok)
+            }
+            ParseErrorType::ExpectedLoopAfterLabel => write!(f, "expected a loop after label"),
+            ParseErrorType::ExpectedDecoratorName => write!(f, "expected decorator name"),
+            ParseErrorType::ExpectedFunctionAfterDecorator => {
+                write!(f, "expected a function after decorator")
+            }
+            ParseErrorType::ExpectedFunctionAfterPub => {
+                write!(f, "expected a function after 'pub'")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.error, self.location.start, self.location.end
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// The message with the location as `line:column` in `lines`, e.g.
+    /// `expected ')', found '{' at 1:10`.
+    pub fn message(&self, lines: &LineIndex) -> String {
+        let (line, column) = lines.line_col(self.location.start);
+        format!("{} at {}:{}", self.error, line, column)
+    }
+}
 
 /// Represents a simple parser that processes a sequence of tokens.
 pub struct Parser<I>
@@ -10,6 +131,30 @@ where
 {
     token_stream: I,
     current_token: Option<(u32, Token, u32)>,
+    /// Whether a newline was skipped right before `current_token`.
+    newline_before: bool,
+    /// Comments skipped right before `current_token`, see `Skipped`.
+    comments: Vec<(TokenStr, bool)>,
+    /// Tokens after `current_token` already pulled from the stream by
+    /// `peek_nth`, each with what was skipped before it.
+    lookahead: VecDeque<((u32, Token, u32), Skipped)>,
+    /// End offset of the last token taken from the stream, used to locate EOF errors.
+    last_end: u32,
+    /// Whether a missing `;` before a newline is recovered from rather than fatal.
+    recover: bool,
+    /// Errors recovered from so far, see `with_recovery`.
+    errors: Vec<ParseError>,
+    /// Whether comments are kept as trivia on statements, see `preserve_comments`.
+    preserve_comments: bool,
+}
+
+/// The newlines and comments skipped between two tokens.
+#[derive(Debug, Default)]
+struct Skipped {
+    newline: bool,
+    /// Comments, only collected with `preserve_comments`, each with whether a
+    /// newline preceded it.
+    comments: Vec<(TokenStr, bool)>,
 }
 
 impl<I> Parser<I>
@@ -19,19 +164,87 @@ where
     /// Create a new parser with a given token iterator.
     pub fn new(mut tokens: I) -> Self {
         let current_token = tokens.next();
+        let last_end = current_token.as_ref().map_or(0, |(_, _, end)| *end);
         Self {
             token_stream: tokens,
             current_token,
+            newline_before: false,
+            comments: Vec::new(),
+            lookahead: VecDeque::new(),
+            last_end,
+            recover: false,
+            errors: Vec::new(),
+            preserve_comments: false,
         }
     }
 
+    /// Recover from a `;` missing before a newline: the error is recorded in
+    /// `errors` and parsing continues as if the `;` were there.
+    pub fn with_recovery(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    /// The errors recovered from so far, in source order.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Keep comments rather than skipping them, for a formatter to print back.
+    ///
+    /// Comments on their own lines before a statement or item become its
+    /// `leading_comments`, and one after it on the same line its
+    /// `trailing_comments`, in an `ASTNode::Commented` wrapping it. Comments
+    /// anywhere else, such as inside an expression, are still dropped.
+    pub fn preserve_comments(mut self, preserve: bool) -> Self {
+        self.preserve_comments = preserve;
+        self
+    }
+
     /// Advances the parser to the next token.
     fn advance(&mut self) {
-        self.current_token = self.token_stream.next();
+        let (token, skipped) = match self.lookahead.pop_front() {
+            Some((token, skipped)) => (Some(token), skipped),
+            None => self.next_from_stream(),
+        };
+        self.current_token = token;
+        self.newline_before = skipped.newline;
+        self.comments = skipped.comments;
+        if let Some((_, _, end)) = self.current_token {
+            self.last_end = end;
+        }
+    }
+
+    /// Takes the next token from the stream, skipping newlines and comments,
+    /// and returns it with what was skipped.
+    fn next_from_stream(&mut self) -> (Option<(u32, Token, u32)>, Skipped) {
         // TODO: thinks it should be here?
-        while let Some((_, Token::NewLine, _)) = self.current_token {
-            self.current_token = self.token_stream.next();
+        let mut skipped = Skipped::default();
+        for token in self.token_stream.by_ref() {
+            match token.1 {
+                Token::NewLine => skipped.newline = true,
+                Token::Whitespace { .. } => {}
+                Token::Comment { content } => {
+                    if self.preserve_comments {
+                        skipped.comments.push((content, skipped.newline));
+                    }
+                }
+                _ => return (Some(token), skipped),
+            }
         }
+        (None, skipped)
+    }
+
+    /// Builds an error located at the current token, or at the end of input.
+    fn error(&self, error: ParseErrorType) -> ParseError {
+        let location = match self.current_token {
+            Some((start, _, end)) => SrcSpan { start, end },
+            None => SrcSpan {
+                start: self.last_end,
+                end: self.last_end,
+            },
+        };
+        ParseError { error, location }
     }
 
     /// Peeks at the current token without advancing.
@@ -39,72 +252,197 @@ where
         self.current_token.as_ref()
     }
 
+    /// Returns true once the input is exhausted.
+    ///
+    /// A stream without a final `Token::EOF` simply runs out of tokens, so a
+    /// missing current token counts as the end as well.
+    fn is_at_end(&self) -> bool {
+        matches!(self.current_token, None | Some((_, Token::EOF, _)))
+    }
+
+    /// Peeks at the token `n` positions after the current one without
+    /// advancing, so `peek_nth(0)` is the current token.
+    ///
+    /// Tokens are pulled from the stream only as far as needed, and newlines
+    /// are skipped just like in `advance`.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&(u32, Token, u32)> {
+        if n == 0 {
+            return self.current_token.as_ref();
+        }
+        while self.lookahead.len() < n {
+            let (token, skipped) = self.next_from_stream();
+            self.lookahead.push_back((token?, skipped));
+        }
+        self.lookahead.get(n - 1).map(|(token, _)| token)
+    }
+
     /// Consumes the current token if it matches the given kind, otherwise returns an error.
-    fn consume(&mut self, expected: &Token) -> Result<(), String> {
-        if let Some((start, ref token, end)) = self.current_token {
-            if token == expected {
-                self.advance();
-                Ok(())
-            } else {
-                Err(format!(
-                    "Expected {:?}, found ({:?}, {:?}, {:?})",
-                    expected, start, token, end
-                ))
-            }
-        } else {
-            Err("Unexpected end of input".into())
+    fn consume(&mut self, expected: &Token) -> Result<(), ParseError> {
+        if self.is_at_end() {
+            return Err(self.error(ParseErrorType::UnexpectedEof));
         }
+        if let Some((_, ref token, _)) = self.current_token
+            && token != expected
+        {
+            return Err(self.error(ParseErrorType::ExpectedToken {
+                expected: expected.clone(),
+                found: token.clone(),
+            }));
+        }
+        self.advance();
+        Ok(())
     }
 
     /// Parses an entire program (list of statements).
-    pub fn parse_program(&mut self) -> Result<Vec<ASTNode>, String> {
+    pub fn parse_program(&mut self) -> Result<Vec<ASTNode>, ParseError> {
         let mut nodes = Vec::new();
 
         let mut count = 20;
-        while let Some((start, ref token, end)) = self.current_token {
+        while !self.is_at_end() {
             #[cfg(test)]
             {
-                println!("{:?}", token);
+                println!("{:?}", self.current_token);
                 count -= 1;
                 if count == 0 {
                     panic!("max loop reached");
                 }
             }
 
-            if token == &Token::NewLine {
+            if let Some((_, Token::NewLine | Token::Whitespace { .. }, _)) = self.current_token {
                 self.advance();
                 continue;
             }
-            if token == &Token::EOF {
-                break;
+            // Only the first token comes straight from the stream, later
+            // comments are skipped by `advance`.
+            if let Some((_, Token::Comment { content }, _)) = &self.current_token {
+                let comment = (content.clone(), true);
+                self.advance();
+                if self.preserve_comments {
+                    self.comments.insert(0, comment);
+                }
+                continue;
             }
-            nodes.push(self.parse_statement()?);
+            nodes.push(self.parse_commented_statement()?);
         }
 
         Ok(nodes)
     }
 
+    /// Parses a single statement with the comments around it, see
+    /// `preserve_comments`.
+    fn parse_commented_statement(&mut self) -> Result<ASTNode, ParseError> {
+        let leading_comments: Vec<_> = self.comments.drain(..).map(|(c, _)| c).collect();
+        let node = self.parse_statement()?;
+        let same_line = self
+            .comments
+            .iter()
+            .take_while(|(_, newline_before)| !newline_before)
+            .count();
+        let trailing_comments: Vec<_> = self.comments.drain(..same_line).map(|(c, _)| c).collect();
+
+        if leading_comments.is_empty() && trailing_comments.is_empty() {
+            return Ok(node);
+        }
+        Ok(ASTNode::Commented {
+            leading_comments,
+            node: Box::new(node),
+            trailing_comments,
+        })
+    }
+
     /// Parses a single statement.
-    fn parse_statement(&mut self) -> Result<ASTNode, String> {
+    fn parse_statement(&mut self) -> Result<ASTNode, ParseError> {
+        if self.is_at_end() {
+            return Err(self.error(ParseErrorType::UnexpectedEof));
+        }
         match self.current_token {
-            Some((_, Token::Fn, _)) => self.parse_function_declaration(),
+            Some((_, Token::Fn | Token::Extern, _)) => {
+                self.parse_function_declaration(vec![], false)
+            }
+            Some((_, Token::At | Token::Pub, _)) => self.parse_item_with_prefix(),
             Some((_, Token::Let, _)) => self.parse_variable_declaration(),
             Some((_, Token::Return, _)) => self.parse_return_statement(),
             Some((_, Token::Struct, _)) => self.parse_struct_declaration(),
-            _ => Err("Unexpected token in statement".into()),
+            Some((_, Token::If, _)) => self.parse_if_statement(),
+            Some((_, Token::While, _)) => self.parse_while_statement(None),
+            Some((_, Token::Label { .. }, _)) => self.parse_labeled_statement(),
+            Some((_, Token::Break, _)) => self.parse_break_statement(),
+            Some((_, Token::Continue, _)) => self.parse_continue_statement(),
+            Some((
+                _,
+                Token::Ident { .. }
+                | Token::LParen
+                | Token::Int { .. }
+                | Token::Float { .. }
+                | Token::Char { .. }
+                | Token::String { .. },
+                _,
+            )) => self.parse_expression_statement(),
+            Some((_, ref tok, _)) => {
+                Err(self.error(ParseErrorType::InvalidStatement { tok: tok.clone() }))
+            }
+            None => Err(self.error(ParseErrorType::UnexpectedEof)),
         }
     }
 
-    /// Parses a function declaration.
-    fn parse_function_declaration(&mut self) -> Result<ASTNode, String> {
+    /// Parses decorators and `pub`, in any order, and the function they
+    /// apply to (e.g., `@memoize pub fn fib(n: int) -> int { ... }`).
+    fn parse_item_with_prefix(&mut self) -> Result<ASTNode, ParseError> {
+        let mut decorators = Vec::new();
+        let mut is_public = false;
+        loop {
+            match self.current_token {
+                Some((_, Token::At, _)) => self.advance(),
+                // A second `pub` is left for the check below to reject.
+                Some((_, Token::Pub, _)) if !is_public => {
+                    is_public = true;
+                    self.advance();
+                    continue;
+                }
+                _ => break,
+            }
+            let Some((_, Token::Ident { ref name }, _)) = self.current_token else {
+                return Err(self.error(ParseErrorType::ExpectedDecoratorName));
+            };
+            let name = name.clone();
+            self.advance();
+            let args = if let Some((_, Token::LParen, _)) = self.current_token {
+                self.parse_arguments()?
+            } else {
+                Vec::new()
+            };
+            decorators.push(Decorator { name, args });
+        }
+
+        match self.current_token {
+            Some((_, Token::Fn | Token::Extern, _)) => {
+                self.parse_function_declaration(decorators, is_public)
+            }
+            _ if is_public => Err(self.error(ParseErrorType::ExpectedFunctionAfterPub)),
+            _ => Err(self.error(ParseErrorType::ExpectedFunctionAfterDecorator)),
+        }
+    }
+
+    /// Parses a function declaration, or an `extern` one ending in `;` where
+    /// the body would be.
+    fn parse_function_declaration(
+        &mut self,
+        decorators: Vec<Decorator>,
+        is_public: bool,
+    ) -> Result<ASTNode, ParseError> {
+        let is_external = matches!(self.current_token, Some((_, Token::Extern, _)));
+        if is_external {
+            self.advance();
+        }
         self.consume(&Token::Fn)?;
         if let Some((_, Token::Ident { ref name }, _)) = self.current_token {
             let function_name = name.clone();
             self.advance();
+            let type_params = self.parse_type_params()?;
 
-            // Parse parameters (e.g., `(a: i32, b: i32)`)
+            // Parse parameters (e.g., `(a: i32, b: i32)` or `(fmt: string, ...)`)
             self.consume(&Token::LParen)?;
-            let params = self.parse_parameters()?;
+            let (params, is_variadic) = self.parse_parameters()?;
             self.consume(&Token::RParen)?;
 
             // Parse return type (`-> type`)
@@ -116,24 +454,67 @@ where
             };
 
             // Parse function body
-            self.consume(&Token::LBrace)?;
-            let body = self.parse_block()?;
-            self.consume(&Token::RBrace)?;
+            let body = if is_external {
+                self.consume(&Token::Semicolon)?;
+                Vec::new()
+            } else {
+                self.consume(&Token::LBrace)?;
+                let body = self.parse_block()?;
+                self.consume(&Token::RBrace)?;
+                body
+            };
 
             Ok(ASTNode::Function {
                 name: function_name,
+                type_params,
                 params,
                 return_type,
+                is_external,
+                is_variadic,
+                is_public,
+                decorators,
                 body,
             })
         } else {
-            Err("Expected function name".into())
+            Err(self.error(ParseErrorType::ExpectedFunctionName))
         }
     }
 
-    /// Parses a list of parameters in a function declaration.
-    fn parse_parameters(&mut self) -> Result<Vec<Parameter>, String> {
+    /// Parses the optional type parameter list after a function or struct
+    /// name (e.g., `<T, U>`).
+    ///
+    /// This is only called right after the name, so `<` is never mistaken for
+    /// a comparison.
+    fn parse_type_params(&mut self) -> Result<Vec<TokenStr>, ParseError> {
+        let mut type_params = Vec::new();
+        if !matches!(self.current_token, Some((_, Token::LArrow, _))) {
+            return Ok(type_params);
+        }
+        self.advance();
+
+        loop {
+            let Some((_, Token::Ident { name }, _)) = &self.current_token else {
+                return Err(self.error(ParseErrorType::ExpectedTypeParameter));
+            };
+            type_params.push(name.clone());
+            self.advance();
+
+            if let Some((_, Token::Comma, _)) = self.current_token {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume(&Token::RArrow)?;
+
+        Ok(type_params)
+    }
+
+    /// Parses a list of parameters in a function declaration, and whether it
+    /// ends in `...`.
+    fn parse_parameters(&mut self) -> Result<(Vec<Parameter>, bool), ParseError> {
         let mut params = Vec::new();
+        let mut is_variadic = false;
 
         while let Some((_, token, _)) = &self.current_token {
             match token {
@@ -142,17 +523,23 @@ where
                     self.advance();
 
                     self.consume(&Token::Colon)?;
-                    if let Some((_, Token::Ident { name: type_name }, _)) = &self.current_token {
-                        params.push(Parameter {
-                            name: param_name,
-                            param_type: Type {
-                                name: type_name.clone(),
-                            },
-                        });
-                        self.advance();
-                    } else {
-                        return Err("Expected a type for parameter".into());
+                    if !matches!(
+                        self.current_token,
+                        Some((
+                            _,
+                            Token::Ident { .. } | Token::Fn | Token::LBracket | Token::Asterisk,
+                            _
+                        ))
+                    ) {
+                        return Err(self.error(ParseErrorType::ExpectedParameterType));
                     }
+                    let Some(param_type) = self.parse_type()? else {
+                        return Err(self.error(ParseErrorType::ExpectedParameterType));
+                    };
+                    params.push(Parameter {
+                        name: param_name,
+                        param_type,
+                    });
 
                     if let Some((_, Token::Comma, _)) = self.current_token {
                         self.advance(); // Consume comma and continue
@@ -161,40 +548,114 @@ where
                     }
                 }
                 Token::RParen => break, // End of parameter list
-                _ => return Err("Unexpected token in parameter list".into()),
+                // `...` can only end the list, the caller expects `)` next.
+                Token::Dot3 => {
+                    self.advance();
+                    is_variadic = true;
+                    break;
+                }
+                _ => {
+                    return Err(self.error(ParseErrorType::InvalidParameter { tok: token.clone() }));
+                }
             }
         }
 
-        Ok(params)
+        Ok((params, is_variadic))
     }
 
-    /// Parses a type annotation (e.g., `i32` or `String`).
-    fn parse_type(&mut self) -> Result<Option<Type>, String> {
-        if let Some((_, Token::Ident { name }, _)) = &self.current_token {
-            let type_name = name.clone();
-            self.advance();
-            Ok(Some(Type { name: type_name }))
-        } else {
-            Err("Expected a type annotation".into())
+    /// Parses a type annotation (e.g., `i32`, `String` or `fn(int) -> bool`).
+    fn parse_type(&mut self) -> Result<Option<Type>, ParseError> {
+        match &self.current_token {
+            Some((_, Token::Ident { name }, _)) => {
+                let type_name = name.clone();
+                self.advance();
+                Ok(Some(Type::Named { name: type_name }))
+            }
+            Some((_, Token::Fn, _)) => {
+                self.advance();
+                self.consume(&Token::LParen)?;
+                let mut params = Vec::new();
+                while !matches!(self.current_token, Some((_, Token::RParen, _))) {
+                    let Some(param) = self.parse_type()? else {
+                        return Err(self.error(ParseErrorType::ExpectedTypeAnnotation));
+                    };
+                    params.push(param);
+                    if let Some((_, Token::Comma, _)) = self.current_token {
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+                self.consume(&Token::RParen)?;
+
+                self.consume(&Token::MinusRArrow)?;
+                let Some(return_type) = self.parse_type()? else {
+                    return Err(self.error(ParseErrorType::ExpectedTypeAnnotation));
+                };
+                Ok(Some(Type::Function {
+                    params,
+                    return_type: Box::new(return_type),
+                }))
+            }
+            Some((_, Token::LBracket, _)) => {
+                self.advance();
+                let Some(element) = self.parse_type()? else {
+                    return Err(self.error(ParseErrorType::ExpectedTypeAnnotation));
+                };
+                self.consume(&Token::Semicolon)?;
+                let size = self.parse_expression()?;
+                self.consume(&Token::RBracket)?;
+                Ok(Some(Type::Array {
+                    element: Box::new(element),
+                    size: Box::new(size),
+                }))
+            }
+            Some((_, Token::Asterisk, _)) => {
+                self.advance();
+                let Some(pointee) = self.parse_type()? else {
+                    return Err(self.error(ParseErrorType::ExpectedTypeAnnotation));
+                };
+                Ok(Some(Type::Pointer {
+                    pointee: Box::new(pointee),
+                }))
+            }
+            _ => Err(self.error(ParseErrorType::ExpectedTypeAnnotation)),
+        }
+    }
+
+    /// Parses a type annotation that makes up all of the input, such as the
+    /// `fn(int) -> bool` of a standalone type in a test or tool.
+    pub fn parse_type_annotation(&mut self) -> Result<Type, ParseError> {
+        let Some(ty) = self.parse_type()? else {
+            return Err(self.error(ParseErrorType::ExpectedTypeAnnotation));
+        };
+        if !self.is_at_end()
+            && let Some((_, token, _)) = &self.current_token
+        {
+            return Err(self.error(ParseErrorType::ExpectedToken {
+                expected: Token::EOF,
+                found: token.clone(),
+            }));
         }
+        Ok(ty)
     }
 
     /// Parses a block of statements enclosed in braces `{ ... }`.
-    fn parse_block(&mut self) -> Result<Vec<ASTNode>, String> {
+    fn parse_block(&mut self) -> Result<Vec<ASTNode>, ParseError> {
         let mut statements = Vec::new();
 
-        while let Some((_, token, _)) = &self.current_token {
-            match token {
-                Token::RBrace => break, // End of block
-                _ => statements.push(self.parse_statement()?),
+        while !self.is_at_end() {
+            if let Some((_, Token::RBrace, _)) = self.current_token {
+                break; // End of block
             }
+            statements.push(self.parse_commented_statement()?);
         }
 
         Ok(statements)
     }
 
     /// Parses a variable declaration (e.g., `let x: i32 = 42;`).
-    fn parse_variable_declaration(&mut self) -> Result<ASTNode, String> {
+    fn parse_variable_declaration(&mut self) -> Result<ASTNode, ParseError> {
         self.consume(&Token::Let)?;
 
         if let Some((_, Token::Ident { name }, _)) = &self.current_token {
@@ -215,99 +676,378 @@ where
                 None
             };
 
-            self.consume(&Token::Semicolon)?;
+            self.consume_statement_end()?;
 
             Ok(ASTNode::Variable {
                 name: variable_name,
+                var_type: variable_type,
                 value: variable_value,
-                // var_type: variable_type,
             })
         } else {
-            Err("Expected variable name".into())
+            Err(self.error(ParseErrorType::ExpectedVariableName))
         }
     }
 
     /// Parses a return statement (e.g., `return 42;`).
-    fn parse_return_statement(&mut self) -> Result<ASTNode, String> {
+    fn parse_return_statement(&mut self) -> Result<ASTNode, ParseError> {
         self.consume(&Token::Return)?;
 
-        let value = if let Some((_, Token::Semicolon, _)) = self.current_token {
+        let value = if let Some((_, Token::Semicolon | Token::RBrace, _)) = self.current_token {
             None // Empty return
         } else {
             Some(Box::new(self.parse_expression()?))
         };
 
-        self.consume(&Token::Semicolon)?;
+        self.consume_statement_end()?;
 
         Ok(ASTNode::Return { value })
     }
 
-    /// Parses a struct declaration.
-    fn parse_struct_declaration(&mut self) -> Result<ASTNode, String> {
-        // TODO: Implement struct declaration parsing
-        Ok(ASTNode::Struct {
-            name: "".into(),
-            fields: vec![],
-        })
-    }
+    /// Parses a struct declaration (e.g., `struct Point { x: i32, y: i32 }`).
+    fn parse_struct_declaration(&mut self) -> Result<ASTNode, ParseError> {
+        self.consume(&Token::Struct)?;
 
-    /// Parses an expression (e.g., literals, variables, binary operations).
-    fn parse_expression(&mut self) -> Result<ASTNode, String> {
-        let mut left = self.parse_primary()?;
+        let Some((_, Token::Ident { name }, _)) = &self.current_token else {
+            return Err(self.error(ParseErrorType::ExpectedStructName));
+        };
+        let struct_name = name.clone();
+        self.advance();
+        let type_params = self.parse_type_params()?;
 
+        self.consume(&Token::LBrace)?;
+        let mut fields = Vec::new();
         while let Some((_, token, _)) = &self.current_token {
             match token {
-                Token::Plus | Token::Minus | Token::Asterisk | Token::Slash => {
-                    let operator = token.clone();
+                Token::RBrace => break,
+                Token::Ident { name } => {
+                    let field_name = name.clone();
                     self.advance();
-                    let right = self.parse_primary()?;
-                    left = ASTNode::BinaryOp {
-                        left: Box::new(left),
-                        operator,
-                        right: Box::new(right),
+
+                    self.consume(&Token::Colon)?;
+                    let Some(field_type) = self.parse_type()? else {
+                        return Err(self.error(ParseErrorType::ExpectedTypeAnnotation));
                     };
+                    fields.push(StructField {
+                        name: field_name,
+                        field_type,
+                    });
+
+                    if let Some((_, Token::Comma, _)) = self.current_token {
+                        self.advance();
+                    } else {
+                        break; // The last field may omit the comma
+                    }
                 }
-                _ => break,
+                _ => return Err(self.error(ParseErrorType::ExpectedFieldName)),
             }
         }
+        self.consume(&Token::RBrace)?;
+
+        Ok(ASTNode::Struct {
+            name: struct_name,
+            type_params,
+            fields,
+        })
+    }
+
+    /// Parses an if statement (e.g., `if a < b { ... } else { ... }`).
+    fn parse_if_statement(&mut self) -> Result<ASTNode, ParseError> {
+        self.consume(&Token::If)?;
+        let condition = self.parse_expression()?;
+
+        self.consume(&Token::LBrace)?;
+        let then_branch = self.parse_block()?;
+        self.consume(&Token::RBrace)?;
+
+        let else_branch = if let Some((_, Token::Else, _)) = self.current_token {
+            self.advance();
+            if let Some((_, Token::If, _)) = self.current_token {
+                // `else if` nests the chained if as the only statement of the else branch
+                Some(vec![self.parse_if_statement()?])
+            } else {
+                self.consume(&Token::LBrace)?;
+                let else_branch = self.parse_block()?;
+                self.consume(&Token::RBrace)?;
+                Some(else_branch)
+            }
+        } else {
+            None
+        };
+
+        Ok(ASTNode::If {
+            condition: Box::new(condition),
+            then_branch,
+            else_branch,
+        })
+    }
+
+    /// Parses a labeled loop (e.g., `'outer: while a < b { ... }`).
+    fn parse_labeled_statement(&mut self) -> Result<ASTNode, ParseError> {
+        let Some((_, Token::Label { name }, _)) = &self.current_token else {
+            unreachable!("parse_labeled_statement called without a label");
+        };
+        let label = name.clone();
+        self.advance();
+        self.consume(&Token::Colon)?;
+
+        match self.current_token {
+            Some((_, Token::While, _)) => self.parse_while_statement(Some(label)),
+            _ => Err(self.error(ParseErrorType::ExpectedLoopAfterLabel)),
+        }
+    }
+
+    /// Parses a while loop (e.g., `while a < b { ... }`).
+    fn parse_while_statement(&mut self, label: Option<TokenStr>) -> Result<ASTNode, ParseError> {
+        self.consume(&Token::While)?;
+        let condition = self.parse_expression()?;
+
+        self.consume(&Token::LBrace)?;
+        let body = self.parse_block()?;
+        self.consume(&Token::RBrace)?;
+
+        Ok(ASTNode::While {
+            label,
+            condition: Box::new(condition),
+            body,
+        })
+    }
+
+    /// Parses a break statement (e.g., `break;` or `break 'outer;`).
+    fn parse_break_statement(&mut self) -> Result<ASTNode, ParseError> {
+        self.consume(&Token::Break)?;
+        let label = self.parse_jump_label();
+        self.consume_statement_end()?;
+
+        Ok(ASTNode::Break { label })
+    }
+
+    /// Parses a continue statement (e.g., `continue;` or `continue 'outer;`).
+    fn parse_continue_statement(&mut self) -> Result<ASTNode, ParseError> {
+        self.consume(&Token::Continue)?;
+        let label = self.parse_jump_label();
+        self.consume_statement_end()?;
+
+        Ok(ASTNode::Continue { label })
+    }
+
+    /// Parses the optional label after `break` or `continue`.
+    fn parse_jump_label(&mut self) -> Option<TokenStr> {
+        let Some((_, Token::Label { name }, _)) = &self.current_token else {
+            return None;
+        };
+        let label = name.clone();
+        self.advance();
+        Some(label)
+    }
+
+    /// Parses an assignment (e.g., `a = b;`) or an expression statement (e.g., `f(a);`).
+    ///
+    /// An expression without `;` at the end of a block is the block's tail.
+    fn parse_expression_statement(&mut self) -> Result<ASTNode, ParseError> {
+        let expr = self.parse_expression()?;
+
+        if let Some((_, Token::Equal, _)) = self.current_token {
+            self.advance();
+            let value = self.parse_expression()?;
+            self.consume_statement_end()?;
+            return Ok(ASTNode::Assignment {
+                target: Box::new(expr),
+                value: Box::new(value),
+            });
+        }
+
+        if self.consume_statement_end()? {
+            Ok(ASTNode::ExpressionStatement(Box::new(expr)))
+        } else {
+            Ok(ASTNode::Tail(Box::new(expr)))
+        }
+    }
+
+    /// Consumes the `;` terminating a statement and returns whether it was there.
+    ///
+    /// The last statement of a block may omit it, the `}` is then left for the block.
+    /// With recovery enabled, a `;` missing before a newline is recorded in
+    /// `errors` and treated as present.
+    fn consume_statement_end(&mut self) -> Result<bool, ParseError> {
+        match self.current_token {
+            Some((_, Token::RBrace, _)) => Ok(false),
+            Some((_, ref token, _))
+                if self.recover && self.newline_before && *token != Token::Semicolon =>
+            {
+                let error = self.error(ParseErrorType::ExpectedToken {
+                    expected: Token::Semicolon,
+                    found: token.clone(),
+                });
+                self.errors.push(error);
+                Ok(true)
+            }
+            _ => {
+                self.consume(&Token::Semicolon)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Parses an expression (e.g., literals, variables, binary operations).
+    fn parse_expression(&mut self) -> Result<ASTNode, ParseError> {
+        self.parse_binary(0)
+    }
+
+    /// Parses a chain of binary operators binding tighter than `min_precedence`,
+    /// all of which are left-associative.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<ASTNode, ParseError> {
+        let mut left = self.parse_postfix()?;
+
+        while let Some((_, token, _)) = &self.current_token {
+            let Some(precedence) = binary_precedence(token) else {
+                break;
+            };
+            if precedence <= min_precedence {
+                break;
+            }
+            let operator = token.clone();
+            self.advance();
+            let right = self.parse_binary(precedence)?;
+            left = ASTNode::BinaryOp {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            };
+        }
 
         Ok(left)
     }
 
+    /// Parses a primary expression followed by any number of `?`.
+    fn parse_postfix(&mut self) -> Result<ASTNode, ParseError> {
+        let mut expr = self.parse_primary()?;
+        while let Some((_, Token::Question, _)) = self.current_token {
+            self.advance();
+            expr = ASTNode::Try {
+                expr: Box::new(expr),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parses the argument list of a call to `name` (e.g., `(a, b)`).
+    fn parse_call(&mut self, name: TokenStr) -> Result<ASTNode, ParseError> {
+        let arguments = self.parse_arguments()?;
+        Ok(ASTNode::FunctionCall { name, arguments })
+    }
+
+    /// Parses a parenthesized, comma-separated list of expressions.
+    fn parse_arguments(&mut self) -> Result<Vec<ASTNode>, ParseError> {
+        self.consume(&Token::LParen)?;
+        let mut arguments = Vec::new();
+        while !matches!(self.current_token, Some((_, Token::RParen, _))) {
+            arguments.push(self.parse_expression()?);
+            if let Some((_, Token::Comma, _)) = self.current_token {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume(&Token::RParen)?;
+        Ok(arguments)
+    }
+
     /// Parses a primary expression (e.g., literals, variables, or grouped expressions).
-    fn parse_primary(&mut self) -> Result<ASTNode, String> {
+    fn parse_primary(&mut self) -> Result<ASTNode, ParseError> {
+        if self.is_at_end() {
+            return Err(self.error(ParseErrorType::UnexpectedEof));
+        }
         if let Some((_, token, _)) = self.current_token.clone() {
             match token {
                 Token::Ident { name } => {
                     self.advance();
+                    if let Some((_, Token::LParen, _)) = self.current_token {
+                        return self.parse_call(name);
+                    }
                     Ok(ASTNode::Variable {
                         name,
+                        var_type: None,
                         value: None, // This will depend on the context of the variable usage
                     })
                 }
-                // Token::Number(value) => {
-                //     self.advance();
-                //     Ok(ASTNode::Literal {
-                //         value: value.to_string(),
-                //     })
-                // }
+                Token::Int { base, value } => {
+                    self.advance();
+                    Ok(ASTNode::Literal(Literal::Int { base, value }))
+                }
+                Token::Float { value, .. } => {
+                    self.advance();
+                    Ok(ASTNode::Literal(Literal::Float { value }))
+                }
+                Token::Char { value } => {
+                    self.advance();
+                    Ok(ASTNode::Literal(Literal::Char { value }))
+                }
+                Token::String { value } => {
+                    self.advance();
+                    Ok(ASTNode::Literal(Literal::String { value }))
+                }
                 Token::LParen => {
                     self.advance();
                     let expr = self.parse_expression()?;
                     self.consume(&Token::RParen)?;
-                    Ok(expr)
+                    Ok(ASTNode::Paren(Box::new(expr)))
+                }
+                // An if-expression, whose value is the tail of the branch taken.
+                Token::If => self.parse_if_statement(),
+                // Logical not, binding tighter than any binary operator.
+                Token::Exclamation => {
+                    self.advance();
+                    let operand = self.parse_postfix()?;
+                    Ok(ASTNode::UnaryOp {
+                        operator: Token::Exclamation,
+                        operand: Box::new(operand),
+                    })
                 }
-                _ => Err(format!("Unexpected token in expression: {:?}", token)),
+                _ => Err(self.error(ParseErrorType::InvalidExpression { tok: token })),
             }
         } else {
-            Err("Unexpected end of input while parsing expression".into())
+            Err(self.error(ParseErrorType::UnexpectedEof))
         }
     }
 }
 
+impl<C> Parser<TokenStream<C>>
+where
+    C: Iterator<Item = (LOC, char)>,
+{
+    /// Create a parser reading tokens straight from `lexer`.
+    ///
+    /// A lexical error ends the token stream, so parsing then fails with
+    /// `UnexpectedEof`; `lex_error` tells the two apart.
+    pub fn from_lexer(lexer: Lexer<C>) -> Self {
+        Self::new(TokenStream::new(lexer))
+    }
+
+    /// The lexical error that cut the token stream short, if any.
+    pub fn lex_error(&self) -> Option<&LexicalError> {
+        self.token_stream.error()
+    }
+}
+
+/// Binding power of a binary operator token; higher binds tighter.
+fn binary_precedence(token: &Token) -> Option<u8> {
+    match token {
+        Token::Or => Some(1),
+        Token::And => Some(2),
+        Token::Equal2
+        | Token::ExclamationEqual
+        | Token::LArrow
+        | Token::RArrow
+        | Token::LArrowEqual
+        | Token::RArrowEqual => Some(3),
+        Token::Plus | Token::Minus => Some(4),
+        Token::Asterisk | Token::Slash | Token::Percent => Some(5),
+        _ => None,
+    }
+}
+
 #[test]
 fn tdd() {
-    use crate::Lexer;
     let source = r#"
     fn sum(arg1: i32, arg2: i32) -> i32 {
         let sum = arg1 + arg2;
@@ -315,47 +1055,43 @@ fn tdd() {
     }
     "#;
     let chars = source.char_indices().map(|(i, c)| (i as u32, c));
-    let mut lexer = Lexer::new(chars);
-
-    // FIXME: iterator
-    let mut tokens = vec![];
-    while let Ok(token) = lexer.next() {
-        if token.1 == Token::EOF {
-            break;
-        }
-        tokens.push(token);
-    }
-
-    println!("Tokens: {:#?}", tokens);
-    let mut parser = Parser::new(tokens.into_iter());
+    let mut parser = Parser::from_lexer(Lexer::new(chars));
     let ast = parser.parse_program().unwrap();
 
     let expected = vec![
         //
         ASTNode::Function {
             name: "sum".into(),
+            type_params: vec![],
             params: vec![
                 Parameter {
                     name: "arg1".into(),
-                    param_type: Type { name: "i32".into() },
+                    param_type: Type::Named { name: "i32".into() },
                 },
                 Parameter {
                     name: "arg2".into(),
-                    param_type: Type { name: "i32".into() },
+                    param_type: Type::Named { name: "i32".into() },
                 },
             ],
-            return_type: Some(Type { name: "i32".into() }),
+            return_type: Some(Type::Named { name: "i32".into() }),
+            is_external: false,
+            is_variadic: false,
+            is_public: false,
+            decorators: vec![],
             body: vec![
                 ASTNode::Variable {
                     name: "sum".into(),
+                    var_type: None,
                     value: Some(Box::new(ASTNode::BinaryOp {
                         left: Box::new(ASTNode::Variable {
                             name: "arg1".into(),
+                            var_type: None,
                             value: None,
                         }),
                         operator: Token::Plus,
                         right: Box::new(ASTNode::Variable {
                             name: "arg2".into(),
+                            var_type: None,
                             value: None,
                         }),
                     })),
@@ -363,6 +1099,7 @@ fn tdd() {
                 ASTNode::Return {
                     value: Some(Box::new(ASTNode::Variable {
                         name: "sum".into(),
+                        var_type: None,
                         value: None,
                     })),
                 },
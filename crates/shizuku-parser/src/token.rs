@@ -1,4 +1,31 @@
+#[cfg(all(feature = "ecow", not(feature = "std-string")))]
 use ecow::EcoString;
+use std::fmt;
+
+/// Text of identifier, literal and comment tokens.
+///
+/// This is an `EcoString` unless the `std-string` feature is enabled, or the
+/// `ecow` one disabled, which switches it to a plain `Box<str>`.
+#[cfg(all(feature = "ecow", not(feature = "std-string")))]
+pub type TokenStr = EcoString;
+#[cfg(any(not(feature = "ecow"), feature = "std-string"))]
+pub type TokenStr = Box<str>;
+
+/// Growable buffer the lexer builds a `TokenStr` in.
+#[cfg(all(feature = "ecow", not(feature = "std-string")))]
+pub(crate) type TokenStrBuf = EcoString;
+#[cfg(any(not(feature = "ecow"), feature = "std-string"))]
+pub(crate) type TokenStrBuf = String;
+
+#[cfg(all(feature = "ecow", not(feature = "std-string")))]
+pub(crate) fn finish_token_str(buf: TokenStrBuf) -> TokenStr {
+    buf
+}
+
+#[cfg(any(not(feature = "ecow"), feature = "std-string"))]
+pub(crate) fn finish_token_str(buf: TokenStrBuf) -> TokenStr {
+    buf.into_boxed_str()
+}
 
 /// Base of numeric literal encoding according to its prefix.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -13,6 +40,38 @@ pub enum Base {
     Hexadecimal = 16,
 }
 
+impl Base {
+    /// The radix digits of this base are read in.
+    pub fn radix(&self) -> u32 {
+        match self {
+            Base::Binary => 2,
+            Base::Octal => 8,
+            Base::Decimal => 10,
+            Base::Hexadecimal => 16,
+        }
+    }
+
+    /// The prefix literals in this base start with, empty for decimal.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Base::Binary => "0b",
+            Base::Octal => "0o",
+            Base::Decimal => "",
+            Base::Hexadecimal => "0x",
+        }
+    }
+
+    /// The rest of `literal` after this base's prefix, in either case, or
+    /// `None` if `literal` does not start with it.
+    pub fn strip_prefix<'a>(&self, literal: &'a str) -> Option<&'a str> {
+        let prefix = self.prefix();
+        let start = literal.get(..prefix.len())?;
+        start
+            .eq_ignore_ascii_case(prefix)
+            .then(|| &literal[prefix.len()..])
+    }
+}
+
 /// Represents the various kinds of tokens that can appear in the source code.
 /// Tokens are the basic building blocks of the language, including literals,
 /// identifiers, operators, delimiters, and keywords.
@@ -21,33 +80,37 @@ pub enum Token {
     // Identifiers and literals
     /// Identifier (e.g., variable names, function names)
     Ident {
-        name: EcoString,
+        name: TokenStr,
     },
     /// Integer literal (e.g., `123`)
     Int {
         base: Base,
-        value: EcoString,
+        value: TokenStr,
     },
     /// Floating-point literal (e.g., `3.14`)
     Float {
         has_exp: bool,
-        value: EcoString,
+        value: TokenStr,
     },
     /// Char literal (e.g., `'h'`)
     Char {
         value: char,
     },
+    /// Loop label (e.g., `'outer`), `name` excludes the quote
+    Label {
+        name: TokenStr,
+    },
     /// String literal (e.g., `"hello"`)
     String {
-        value: EcoString,
+        value: TokenStr,
     },
     /// Single-line comment (e.g., `// comment`)
     Comment {
-        content: EcoString,
+        content: TokenStr,
     },
     /// Documentation comment (e.g., `/// doc comment`)
     CommentDoc {
-        content: EcoString,
+        content: TokenStr,
     },
 
     // Delimiters
@@ -117,6 +180,8 @@ pub enum Token {
     MinusRArrow,
     /// Range operator `..`
     Dot2,
+    /// Ellipsis `...`, marking a variadic parameter list
+    Dot3,
     /// At symbol `@`
     At,
     /// End of file token
@@ -129,6 +194,11 @@ pub enum Token {
     // Control characters
     /// Newline character
     NewLine,
+    /// A run of whitespace other than newlines, only emitted with
+    /// `LexerConfig::emit_trivia`
+    Whitespace {
+        content: TokenStr,
+    },
 
     // Keywords
     // `as` keyword
@@ -171,6 +241,10 @@ pub enum Token {
     Return,
     /// `test` keyword
     Test,
+    /// `while` keyword
+    While,
+    /// `extern` keyword
+    Extern,
 }
 
 const KEYWORDS: &[Token] = &[
@@ -194,7 +268,9 @@ const KEYWORDS: &[Token] = &[
     Token::Await,
     Token::Return,
     Token::Test,
-    // Total: 19
+    Token::While,
+    Token::Extern,
+    // Total: 22
 ];
 
 impl Token {
@@ -224,7 +300,157 @@ impl Token {
             "await" => Some(Token::Await),
             "return" => Some(Token::Return),
             "test" => Some(Token::Test),
+            "while" => Some(Token::While),
+            "extern" => Some(Token::Extern),
             _ => None,
         }
     }
+
+    /// The value of an `Int` token, or `None` for any other token or if the
+    /// value does not fit an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        let Token::Int { base, value } = self else {
+            return None;
+        };
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let digits = base.strip_prefix(digits)?;
+        let magnitude = u64::from_str_radix(&digits.replace('_', ""), base.radix()).ok()?;
+        if negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    /// The value of a `Float` token, or `None` for any other token or if the
+    /// value is too large to be finite.
+    pub fn as_f64(&self) -> Option<f64> {
+        let Token::Float { value, .. } = self else {
+            return None;
+        };
+        value[..]
+            .replace('_', "")
+            .parse()
+            .ok()
+            .filter(|value: &f64| value.is_finite())
+    }
+}
+
+/// Writes the token as it appears in source, so messages can quote it. Tokens
+/// without source text, `EOF` and `NewLine`, are described in words.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Token::Ident { name } => name,
+            Token::Int { value, .. } | Token::Float { value, .. } => value,
+            Token::Char { value } => return write!(f, "'{}'", value),
+            Token::Label { name } => return write!(f, "'{}", name),
+            Token::String { value } => return write!(f, "\"{}\"", value),
+            Token::Comment { content } => return write!(f, "//{}", content),
+            Token::CommentDoc { content } => return write!(f, "///{}", content),
+            Token::Whitespace { content } => content,
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::Semicolon => ";",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Asterisk => "*",
+            Token::Slash => "/",
+            Token::LArrow => "<",
+            Token::RArrow => ">",
+            Token::LArrowEqual => "<=",
+            Token::RArrowEqual => ">=",
+            Token::Percent => "%",
+            Token::Colon => ":",
+            Token::Comma => ",",
+            Token::Hash => "#",
+            Token::Equal => "=",
+            Token::Equal2 => "==",
+            Token::ExclamationEqual => "!=",
+            Token::Pipe => "|",
+            Token::Amper => "&",
+            Token::LArrow2 => "<<",
+            Token::RArrow2 => ">>",
+            Token::PipeRArrow => "|>",
+            Token::Dot => ".",
+            Token::LArrowMinus => "<-",
+            Token::MinusRArrow => "->",
+            Token::Dot2 => "..",
+            Token::Dot3 => "...",
+            Token::At => "@",
+            Token::EOF => "end of file",
+            Token::Question => "?",
+            Token::Exclamation => "!",
+            Token::NewLine => "newline",
+            Token::As => "as",
+            Token::Const => "const",
+            Token::Fn => "fn",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::And => "and",
+            Token::Or => "or",
+            Token::Import => "import",
+            Token::Let => "let",
+            Token::Type => "type",
+            Token::Opaque => "opaque",
+            Token::Pub => "pub",
+            Token::Struct => "struct",
+            Token::Enum => "enum",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Async => "async",
+            Token::Await => "await",
+            Token::Return => "return",
+            Token::Test => "test",
+            Token::While => "while",
+            Token::Extern => "extern",
+        };
+        f.write_str(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The lexer hands out one `Token` per lexeme and the parser buffers them,
+    /// so keep it small. If this fails, box the payload of the variant that
+    /// grew it or, if the growth is deliberate, update the size here.
+    ///
+    /// Both `TokenStr` backends are two words, so the size does not depend on
+    /// the `std-string` feature.
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_token_size() {
+        assert_eq!(std::mem::size_of::<TokenStr>(), 16);
+        assert_eq!(std::mem::size_of::<Token>(), 24);
+    }
+
+    #[test]
+    fn test_token_display() {
+        assert_eq!(Token::RParen.to_string(), ")");
+        assert_eq!(Token::MinusRArrow.to_string(), "->");
+        assert_eq!(Token::Fn.to_string(), "fn");
+        assert_eq!(Token::Ident { name: "x".into() }.to_string(), "x");
+        assert_eq!(Token::String { value: "hi".into() }.to_string(), "\"hi\"");
+        assert_eq!(Token::Label { name: "outer".into() }.to_string(), "'outer");
+        assert_eq!(Token::EOF.to_string(), "end of file");
+    }
+
+    #[test]
+    fn test_base_radix_and_prefix() {
+        assert_eq!(Base::Hexadecimal.radix(), 16);
+        assert_eq!(Base::Hexadecimal.prefix(), "0x");
+        assert_eq!(Base::Binary.radix(), 2);
+        assert_eq!(Base::Binary.prefix(), "0b");
+        assert_eq!(Base::Decimal.radix(), 10);
+        assert_eq!(Base::Decimal.prefix(), "");
+    }
 }
@@ -0,0 +1,99 @@
+use shizuku_parser::LexicalErrorType;
+use shizuku_parser::ParseErrorType;
+use shizuku_parser::Token;
+use std::collections::HashSet;
+
+fn lexical_errors() -> Vec<LexicalErrorType> {
+    vec![
+        LexicalErrorType::UnexpectedStringEnd,
+        LexicalErrorType::UnrecognizedToken { tok: '$' },
+        LexicalErrorType::IllegalLiteral {
+            tok: 'a',
+            partial: "0a".into(),
+        },
+        LexicalErrorType::UnexpectedCharEnd,
+        LexicalErrorType::EmptyCharLiteral,
+        LexicalErrorType::NonAsciiIdentifier { tok: 'é' },
+        LexicalErrorType::IdentifierTooLong { max: 64 },
+        LexicalErrorType::TooManyErrors,
+        LexicalErrorType::InvalidNumberSuffix { suffix_start: 2 },
+        LexicalErrorType::InvalidEscape { ch: 'q' },
+    ]
+}
+
+fn parse_errors() -> Vec<ParseErrorType> {
+    vec![
+        ParseErrorType::ExpectedToken {
+            expected: Token::Semicolon,
+            found: Token::RBrace,
+        },
+        ParseErrorType::UnexpectedEof,
+        ParseErrorType::ExpectedFunctionName,
+        ParseErrorType::ExpectedVariableName,
+        ParseErrorType::ExpectedParameterType,
+        ParseErrorType::ExpectedTypeAnnotation,
+        ParseErrorType::ExpectedStructName,
+        ParseErrorType::ExpectedFieldName,
+        ParseErrorType::ExpectedTypeParameter,
+        ParseErrorType::InvalidParameter { tok: Token::Comma },
+        ParseErrorType::InvalidStatement { tok: Token::Comma },
+        ParseErrorType::InvalidExpression { tok: Token::Comma },
+        ParseErrorType::ExpectedLoopAfterLabel,
+        ParseErrorType::ExpectedDecoratorName,
+        ParseErrorType::ExpectedFunctionAfterDecorator,
+        ParseErrorType::ExpectedFunctionAfterPub,
+    ]
+}
+
+#[test]
+fn test_codes_are_unique() {
+    let codes: Vec<_> = lexical_errors()
+        .iter()
+        .map(LexicalErrorType::code)
+        .chain(parse_errors().iter().map(ParseErrorType::code))
+        .collect();
+    let unique: HashSet<_> = codes.iter().collect();
+
+    assert_eq!(unique.len(), codes.len(), "{codes:?}");
+}
+
+#[test]
+fn test_codes_are_stable() {
+    let lexical: Vec<_> = lexical_errors().iter().map(|e| e.code()).collect();
+    assert_eq!(
+        lexical,
+        [
+            "E0001", "E0002", "E0003", "E0004", "E0005", "E0006", "E0007", "E0008", "E0009",
+            "E0010",
+        ]
+    );
+
+    let parse: Vec<_> = parse_errors().iter().map(|e| e.code()).collect();
+    assert_eq!(
+        parse,
+        [
+            "E1001", "E1002", "E1003", "E1004", "E1005", "E1006", "E1007", "E1008", "E1009",
+            "E1010", "E1011", "E1012", "E1013", "E1014", "E1015", "E1016",
+        ]
+    );
+}
+
+#[test]
+fn test_lexical_error_message() {
+    assert_eq!(
+        LexicalErrorType::UnexpectedStringEnd.to_string(),
+        "unterminated string literal"
+    );
+    assert_eq!(
+        LexicalErrorType::IllegalLiteral {
+            tok: 'a',
+            partial: "0a".into(),
+        }
+        .to_string(),
+        "invalid character 'a' in numeric literal '0a'"
+    );
+    assert_eq!(
+        LexicalErrorType::InvalidEscape { ch: 'q' }.to_string(),
+        "unknown escape sequence '\\q'"
+    );
+}
@@ -0,0 +1,64 @@
+use shizuku_parser::DiagnosticStyle;
+use shizuku_parser::Lexer;
+use shizuku_parser::LineIndex;
+use shizuku_parser::Parser;
+use shizuku_parser::SrcSpan;
+use shizuku_parser::render_diagnostic;
+
+const SOURCE: &str = "fn main() {\n    let a = ;\n}";
+
+fn render(color: bool) -> String {
+    render_diagnostic(
+        SOURCE,
+        SrcSpan { start: 24, end: 25 },
+        "expected expression",
+        DiagnosticStyle { color },
+    )
+}
+
+#[test]
+fn test_render_plain() {
+    let expected = "\
+error: expected expression
+  |
+2 |     let a = ;
+  |             ^";
+
+    assert_eq!(render(false), expected);
+    assert!(!render(false).contains('\x1b'));
+}
+
+#[test]
+fn test_render_color() {
+    let rendered = render(true);
+
+    assert!(rendered.contains("\x1b[1merror: expected expression\x1b[0m"));
+    assert!(rendered.contains("\x1b[34m2 |\x1b[0m"));
+    assert!(rendered.contains("\x1b[31m^\x1b[0m"));
+}
+
+#[test]
+fn test_render_span_width() {
+    let rendered = render_diagnostic(
+        "let value = 1;",
+        SrcSpan { start: 4, end: 9 },
+        "unused variable",
+        DiagnosticStyle::default(),
+    );
+
+    assert!(rendered.ends_with("1 | let value = 1;\n  |     ^^^^^"));
+}
+
+#[test]
+fn test_parse_error_message() {
+    let source = "fn main() {\n    print(1;\n}";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let err = Parser::from_lexer(Lexer::new(chars))
+        .parse_program()
+        .unwrap_err();
+
+    assert_eq!(
+        err.message(&LineIndex::new(source)),
+        "expected ')', found ';' at 2:12"
+    );
+}
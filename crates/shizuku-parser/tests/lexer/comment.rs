@@ -15,7 +15,7 @@ fn test_comment() {
         let start = token.0 as usize;
         let end = token.2 as usize;
         assert_eq!(&source[start..end], match &expected_token.1 {
-            Token::Comment { content } => content.as_str(),
+            Token::Comment { content } => &content[..],
             _ => panic!("Expected a Comment token"),
         });
     }
@@ -67,7 +67,7 @@ fn test_comment_doc() {
         let start = token.0 as usize;
         let end = token.2 as usize;
         assert_eq!(&source[start..end], match &expected_token.1 {
-            Token::CommentDoc { content } => content.as_str(),
+            Token::CommentDoc { content } => &content[..],
             _ => panic!("Expected a DocComment token"),
         });
     }
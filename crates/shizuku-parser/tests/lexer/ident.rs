@@ -0,0 +1,114 @@
+use shizuku_parser::Lexer;
+use shizuku_parser::LexerConfig;
+use shizuku_parser::LexicalError;
+use shizuku_parser::LexicalErrorType;
+use shizuku_parser::SrcSpan;
+use shizuku_parser::Token;
+use shizuku_parser::TokenStr;
+
+#[test]
+fn test_unicode_ident_allowed() {
+    let source = "café = 1";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    assert_eq!(
+        lexer.next(),
+        Ok((
+            0,
+            Token::Ident {
+                name: "café".into()
+            },
+            5
+        ))
+    );
+}
+
+#[test]
+fn test_unicode_ident_disallowed() {
+    let source = "café";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let config = LexerConfig {
+        allow_unicode_idents: false,
+        ..Default::default()
+    };
+    let mut lexer = Lexer::with_config(chars, config);
+
+    assert_eq!(
+        lexer.next(),
+        Err(LexicalError {
+            error: LexicalErrorType::NonAsciiIdentifier { tok: 'é' },
+            location: SrcSpan { start: 3, end: 3 },
+        })
+    );
+}
+
+#[test]
+fn test_unicode_ident_start_disallowed() {
+    let source = "émile";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let config = LexerConfig {
+        allow_unicode_idents: false,
+        ..Default::default()
+    };
+    let mut lexer = Lexer::with_config(chars, config);
+
+    assert_eq!(
+        lexer.next().unwrap_err().error,
+        LexicalErrorType::NonAsciiIdentifier { tok: 'é' }
+    );
+}
+
+#[test]
+fn test_ident_token_str() {
+    let source = "snake_case";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let Ok((_, Token::Ident { name }, _)) = lexer.next() else {
+        panic!("Expected an Ident token");
+    };
+    assert_eq!(&name[..], "snake_case");
+    assert_eq!(name, TokenStr::from("snake_case"));
+}
+
+#[test]
+fn test_ident_at_max_len() {
+    let source = "abcde";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let config = LexerConfig {
+        max_ident_len: Some(5),
+        ..Default::default()
+    };
+    let mut lexer = Lexer::with_config(chars, config);
+
+    assert_eq!(
+        lexer.next(),
+        Ok((
+            0,
+            Token::Ident {
+                name: "abcde".into()
+            },
+            5
+        ))
+    );
+}
+
+#[test]
+fn test_ident_too_long() {
+    let source = "abcdef = 1";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let config = LexerConfig {
+        max_ident_len: Some(5),
+        ..Default::default()
+    };
+    let mut lexer = Lexer::with_config(chars, config);
+
+    assert_eq!(
+        lexer.next(),
+        Err(LexicalError {
+            error: LexicalErrorType::IdentifierTooLong { max: 5 },
+            location: SrcSpan { start: 0, end: 6 },
+        })
+    );
+}
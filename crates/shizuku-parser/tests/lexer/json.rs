@@ -0,0 +1,28 @@
+use shizuku_parser::tokenize_to_json;
+
+#[test]
+fn test_tokenize_to_json() {
+    let json = tokenize_to_json("fn x");
+
+    assert_eq!(
+        json,
+        concat!(
+            r#"[{"end":2,"kind":"Fn","start":0,"text":"fn"},"#,
+            r#"{"end":4,"kind":"Ident","start":3,"text":"x"},"#,
+            r#"{"end":4,"kind":"EOF","start":4,"text":""}]"#,
+        )
+    );
+}
+
+#[test]
+fn test_tokenize_to_json_error() {
+    let json = tokenize_to_json("x $");
+
+    assert_eq!(
+        json,
+        concat!(
+            r#"[{"end":1,"kind":"Ident","start":0,"text":"x"},"#,
+            r#"{"end":2,"error":"unrecognized character '$'","start":2}]"#,
+        )
+    );
+}
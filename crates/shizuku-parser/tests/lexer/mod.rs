@@ -1,4 +1,12 @@
 mod comment;
 mod function;
+mod ident;
+#[cfg(feature = "serde")]
+mod json;
 mod number;
+mod peekable;
+mod recovery;
+mod relex;
+mod shebang;
 mod struct_define;
+mod trivia;
@@ -299,28 +299,34 @@ generate_valid_number_tests! {
 // invalid integer and float
 generate_invalid_number_tests! {
     test_number_1_: "1_" => (
-        LexicalError { error: IllegalLiteral {  tok: '_' }, location: SrcSpan { start: 0, end: 2 } }
+        LexicalError { error: IllegalLiteral { tok: '_', partial: "1_".into() }, location: SrcSpan { start: 0, end: 2 } }
     ),
     test_number_0e: "0e" => (
-        LexicalError { error: IllegalLiteral {  tok: 'e' }, location: SrcSpan { start: 0, end: 2 } }
+        LexicalError { error: IllegalLiteral { tok: 'e', partial: "0e".into() }, location: SrcSpan { start: 0, end: 2 } }
     ),
     test_number_07: "07" => (
-        LexicalError { error: IllegalLiteral {  tok: '7' }, location: SrcSpan { start: 0, end: 2 } }
+        LexicalError { error: IllegalLiteral { tok: '7', partial: "07".into() }, location: SrcSpan { start: 0, end: 2 } }
     ),
     test_number_001: "001" => (
-        LexicalError { error: IllegalLiteral {  tok: '1' }, location: SrcSpan { start: 0, end: 3 } }
+        LexicalError { error: IllegalLiteral { tok: '1', partial: "001".into() }, location: SrcSpan { start: 0, end: 3 } }
     ),
     test_number_0e_3: "0e_3" => (
-        LexicalError { error: IllegalLiteral {  tok: '_' }, location: SrcSpan { start: 0, end: 3 } }
+        LexicalError { error: IllegalLiteral { tok: '_', partial: "0e_".into() }, location: SrcSpan { start: 0, end: 3 } }
     ),
     test_number_0_3: "0_3" => (
-        LexicalError { error: IllegalLiteral {  tok: '_' }, location: SrcSpan { start: 0, end: 2 } }
+        LexicalError { error: IllegalLiteral { tok: '_', partial: "0_".into() }, location: SrcSpan { start: 0, end: 2 } }
     ),
     test_number_1__3: "1__3" => (
-        LexicalError { error: IllegalLiteral {  tok: '_' }, location: SrcSpan { start: 0, end: 3 } }
+        LexicalError { error: IllegalLiteral { tok: '_', partial: "1__".into() }, location: SrcSpan { start: 0, end: 3 } }
     ),
     test_number_0_x3: "0_x3" => (
-        LexicalError { error: IllegalLiteral {  tok: '_' }, location: SrcSpan { start: 0, end: 2 } }
+        LexicalError { error: IllegalLiteral { tok: '_', partial: "0_".into() }, location: SrcSpan { start: 0, end: 2 } }
+    ),
+    test_number_10abc: "10abc" => (
+        LexicalError { error: InvalidNumberSuffix { suffix_start: 2 }, location: SrcSpan { start: 0, end: 5 } }
+    ),
+    test_number_0x1g: "0x1g" => (
+        LexicalError { error: InvalidNumberSuffix { suffix_start: 3 }, location: SrcSpan { start: 0, end: 4 } }
     ),
 }
 
@@ -635,3 +641,67 @@ fn test_float_chunk() {
         assert_eq!(token, (start, expected_token, end));
     }
 }
+
+#[test]
+fn test_int_value() {
+    let int = |base, value: &str| Token::Int {
+        base,
+        value: value.into(),
+    };
+
+    assert_eq!(int(Base::Hexadecimal, "0xFF").as_i64(), Some(255));
+    assert_eq!(int(Base::Hexadecimal, "0XFF").as_i64(), Some(255));
+    assert_eq!(int(Base::Hexadecimal, "F").as_i64(), None);
+    assert_eq!(int(Base::Binary, "0b1010").as_i64(), Some(10));
+    assert_eq!(int(Base::Octal, "0o17").as_i64(), Some(15));
+    assert_eq!(int(Base::Decimal, "1_000").as_i64(), Some(1000));
+    assert_eq!(int(Base::Decimal, "-42").as_i64(), Some(-42));
+    assert_eq!(
+        int(Base::Decimal, "-9223372036854775808").as_i64(),
+        Some(i64::MIN)
+    );
+    assert_eq!(int(Base::Decimal, "9223372036854775808").as_i64(), None);
+    assert_eq!(int(Base::Decimal, "1").as_f64(), None);
+}
+
+#[test]
+fn test_float_value() {
+    let float = |value: &str| Token::Float {
+        has_exp: value.contains(['e', 'E']),
+        value: value.into(),
+    };
+
+    assert_eq!(float("2.5").as_f64(), Some(2.5));
+    assert_eq!(float(".5").as_f64(), Some(0.5));
+    assert_eq!(float("10.").as_f64(), Some(10.0));
+    assert_eq!(float("-2.9e-3").as_f64(), Some(-2.9e-3));
+    assert_eq!(float("1_000.000_1").as_f64(), Some(1000.0001));
+    assert_eq!(float("1e1000").as_f64(), None);
+    assert_eq!(float("1.0").as_i64(), None);
+    assert_eq!(Token::Plus.as_i64(), None);
+}
+
+#[test]
+fn test_number_followed_by_operator() {
+    let source = "10+x";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let expected_tokens = vec![
+        (
+            0,
+            Token::Int {
+                base: Base::Decimal,
+                value: "10".into(),
+            },
+            2,
+        ),
+        (2, Token::Plus, 3),
+        (3, Token::Ident { name: "x".into() }, 4),
+    ];
+
+    for (start, expected_token, end) in expected_tokens {
+        let token = lexer.next().unwrap();
+        assert_eq!(token, (start, expected_token, end));
+    }
+}
@@ -0,0 +1,76 @@
+use shizuku_parser::Lexer;
+use shizuku_parser::LexicalErrorType;
+use shizuku_parser::PeekableLexer;
+use shizuku_parser::Token;
+
+fn lexer(source: &str) -> PeekableLexer<impl Iterator<Item = (u32, char)> + '_> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    PeekableLexer::new(Lexer::new(chars))
+}
+
+#[test]
+fn test_peek_does_not_consume() {
+    let mut lexer = lexer("a b");
+
+    assert_eq!(
+        lexer.peek(),
+        Some(&Ok((0, Token::Ident { name: "a".into() }, 1)))
+    );
+    assert_eq!(
+        lexer.peek(),
+        Some(&Ok((0, Token::Ident { name: "a".into() }, 1)))
+    );
+    assert_eq!(
+        lexer.next_token(),
+        Some(Ok((0, Token::Ident { name: "a".into() }, 1)))
+    );
+    assert_eq!(
+        lexer.next_token(),
+        Some(Ok((2, Token::Ident { name: "b".into() }, 3)))
+    );
+    assert_eq!(lexer.peek(), Some(&Ok((3, Token::EOF, 3))));
+    assert_eq!(lexer.next_token(), Some(Ok((3, Token::EOF, 3))));
+    assert_eq!(lexer.peek(), None);
+    assert_eq!(lexer.next_token(), None);
+}
+
+#[test]
+fn test_peek_error() {
+    let mut lexer = lexer("a $");
+
+    assert!(matches!(
+        lexer.next_token(),
+        Some(Ok((_, Token::Ident { .. }, _)))
+    ));
+    let Some(Err(peeked)) = lexer.peek().cloned() else {
+        panic!("expected an error");
+    };
+    assert_eq!(
+        peeked.error,
+        LexicalErrorType::UnrecognizedToken { tok: '$' }
+    );
+    assert_eq!(lexer.next_token(), Some(Err(peeked)));
+    assert_eq!(lexer.next_token(), None);
+}
+
+#[test]
+fn test_lexer_peek_token() {
+    let chars = "a b".char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let a = Ok((0, Token::Ident { name: "a".into() }, 1));
+    assert_eq!(lexer.peek_token(), a);
+    assert_eq!(lexer.peek_token(), a);
+    assert_eq!(lexer.next(), a);
+    assert_eq!(lexer.next(), Ok((2, Token::Ident { name: "b".into() }, 3)));
+}
+
+#[test]
+fn test_lexer_peek_token_error() {
+    let chars = "\"open".char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let error = lexer.peek_token().unwrap_err();
+    assert_eq!(error.error, LexicalErrorType::UnexpectedStringEnd);
+    assert_eq!(lexer.next(), Err(error));
+}
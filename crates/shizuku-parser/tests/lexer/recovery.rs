@@ -0,0 +1,106 @@
+use shizuku_parser::LexerConfig;
+use shizuku_parser::LexicalError;
+use shizuku_parser::LexicalErrorType;
+use shizuku_parser::SrcSpan;
+use shizuku_parser::Token;
+use shizuku_parser::tokenize_all;
+
+#[test]
+fn test_tokenize_all_skips_errors() {
+    let (tokens, errors) = tokenize_all("a $ b $", LexerConfig::default());
+
+    let names: Vec<_> = tokens.iter().map(|(_, tok, _)| tok.clone()).collect();
+    assert_eq!(names, vec![
+        Token::Ident { name: "a".into() },
+        Token::Ident { name: "b".into() },
+        Token::EOF,
+    ]);
+    assert_eq!(errors, vec![
+        LexicalError {
+            error: LexicalErrorType::UnrecognizedToken { tok: '$' },
+            location: SrcSpan { start: 2, end: 2 },
+        },
+        LexicalError {
+            error: LexicalErrorType::UnrecognizedToken { tok: '$' },
+            location: SrcSpan { start: 6, end: 6 },
+        },
+    ]);
+}
+
+#[test]
+fn test_tokenize_all_caps_errors() {
+    let source = "$".repeat(500);
+    let (tokens, errors) = tokenize_all(&source, LexerConfig::default());
+
+    assert!(tokens.is_empty());
+    assert_eq!(errors.len(), 101);
+    assert!(errors[..100]
+        .iter()
+        .all(|e| e.error == LexicalErrorType::UnrecognizedToken { tok: '$' }));
+    assert_eq!(errors[100], LexicalError {
+        error: LexicalErrorType::TooManyErrors,
+        location: SrcSpan { start: 100, end: 100 },
+    });
+}
+
+#[test]
+fn test_tokenize_all_max_errors_is_configurable() {
+    let config = LexerConfig {
+        max_errors: 2,
+        ..Default::default()
+    };
+    let (_, errors) = tokenize_all("$$$", config);
+
+    let kinds: Vec<_> = errors.iter().map(|e| e.error.clone()).collect();
+    assert_eq!(kinds, vec![
+        LexicalErrorType::UnrecognizedToken { tok: '$' },
+        LexicalErrorType::UnrecognizedToken { tok: '$' },
+        LexicalErrorType::TooManyErrors,
+    ]);
+}
+
+#[test]
+fn test_tokenize_all_resyncs_after_bad_number() {
+    let (tokens, errors) = tokenize_all("1__3 foo", LexerConfig::default());
+
+    assert_eq!(tokens, vec![
+        (5, Token::Ident { name: "foo".into() }, 8),
+        (8, Token::EOF, 8),
+    ]);
+    assert_eq!(errors, vec![LexicalError {
+        error: LexicalErrorType::IllegalLiteral {
+            tok: '_',
+            partial: "1__".into(),
+        },
+        location: SrcSpan { start: 0, end: 3 },
+    }]);
+}
+
+#[test]
+fn test_tokenize_all_resyncs_after_bad_digit() {
+    let (tokens, errors) = tokenize_all("x = 07ab + y;", LexerConfig::default());
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(tokens[..3], [
+        (0, Token::Ident { name: "x".into() }, 1),
+        (2, Token::Equal, 3),
+        (9, Token::Plus, 10),
+    ]);
+}
+
+#[test]
+fn test_tokenize_all_resyncs_after_bad_escape() {
+    let (tokens, errors) = tokenize_all(r#"s = "a\qb"; t"#, LexerConfig::default());
+
+    assert_eq!(errors, vec![LexicalError {
+        error: LexicalErrorType::InvalidEscape { ch: 'q' },
+        location: SrcSpan { start: 6, end: 8 },
+    }]);
+    assert_eq!(tokens, vec![
+        (0, Token::Ident { name: "s".into() }, 1),
+        (2, Token::Equal, 3),
+        (10, Token::Semicolon, 11),
+        (12, Token::Ident { name: "t".into() }, 13),
+        (13, Token::EOF, 13),
+    ]);
+}
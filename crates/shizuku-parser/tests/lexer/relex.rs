@@ -0,0 +1,66 @@
+use shizuku_parser::Lexer;
+use shizuku_parser::Token;
+use shizuku_parser::lexer::Spanned;
+use shizuku_parser::relex_from;
+
+fn lex(source: &str) -> Vec<Spanned> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.next().expect("lex error");
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            return tokens;
+        }
+    }
+}
+
+#[test]
+fn test_relex_append() {
+    let before = "fn sum(a: i32, b: i32) -> i32 {\n";
+    let after = "fn sum(a: i32, b: i32) -> i32 {\n    return a + b;\n}\n";
+
+    let tokens = relex_from(after, before.len() as u32, &lex(before));
+    assert_eq!(tokens, lex(after));
+}
+
+#[test]
+fn test_relex_reuses_prefix() {
+    let before = "let a = b;";
+    let after = "let a = b; let c = d;";
+
+    // Tokens before the edit are taken from `prior` as they are, so a
+    // tampered prefix shows up unchanged in the result.
+    let mut prior = lex(before);
+    prior[1].1 = Token::Ident { name: "z".into() };
+
+    let tokens = relex_from(after, before.len() as u32, &prior);
+    assert_eq!(tokens[1].1, Token::Ident { name: "z".into() });
+    assert_eq!(tokens[..4], prior[..4]);
+    assert_eq!(tokens[4..], lex(after)[4..]);
+}
+
+#[test]
+fn test_relex_extends_token_at_edit() {
+    let tokens = relex_from("let abc", 6, &lex("let ab"));
+    assert_eq!(
+        tokens,
+        vec![
+            (0, Token::Let, 3),
+            (4, Token::Ident { name: "abc".into() }, 7),
+            (7, Token::EOF, 7),
+        ]
+    );
+}
+
+#[test]
+fn test_relex_inside_token() {
+    let before = "return total;";
+    let after = "return subtotal;";
+
+    let tokens = relex_from(after, 7, &lex(before));
+    assert_eq!(tokens, lex(after));
+}
@@ -0,0 +1,37 @@
+use shizuku_parser::Lexer;
+use shizuku_parser::Token;
+use shizuku_parser::lexer::Spanned;
+
+fn lex(source: &str) -> Vec<Spanned> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.next().expect("lex error");
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            return tokens;
+        }
+    }
+}
+
+#[test]
+fn test_shebang_is_skipped() {
+    let tokens = lex("#!/usr/bin/env shizuku\nx");
+
+    assert_eq!(tokens, vec![
+        (22, Token::NewLine, 23),
+        (23, Token::Ident { name: "x".into() }, 24),
+        (24, Token::EOF, 24),
+    ]);
+}
+
+#[test]
+fn test_hash_outside_shebang() {
+    let tokens = lex("x #!");
+
+    assert_eq!(tokens[1], (2, Token::Hash, 3));
+    assert_eq!(tokens[2], (3, Token::Exclamation, 4));
+}
@@ -0,0 +1,64 @@
+use shizuku_parser::Lexer;
+use shizuku_parser::LexerConfig;
+use shizuku_parser::Parser;
+use shizuku_parser::Token;
+use shizuku_parser::lexer::Spanned;
+
+fn lex(source: &str, emit_trivia: bool) -> Vec<Spanned> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let config = LexerConfig {
+        emit_trivia,
+        ..Default::default()
+    };
+    let mut lexer = Lexer::with_config(chars, config);
+
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.next().expect("lex error");
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            return tokens;
+        }
+    }
+}
+
+#[test]
+fn test_emit_whitespace() {
+    assert_eq!(lex("a  b", true), vec![
+        (0, Token::Ident { name: "a".into() }, 1),
+        (1, Token::Whitespace { content: "  ".into() }, 3),
+        (3, Token::Ident { name: "b".into() }, 4),
+        (4, Token::EOF, 4),
+    ]);
+}
+
+#[test]
+fn test_whitespace_stops_at_newline() {
+    assert_eq!(lex(" \t\n a", true), vec![
+        (0, Token::Whitespace { content: " \t".into() }, 2),
+        (2, Token::NewLine, 3),
+        (3, Token::Whitespace { content: " ".into() }, 4),
+        (4, Token::Ident { name: "a".into() }, 5),
+        (5, Token::EOF, 5),
+    ]);
+}
+
+#[test]
+fn test_whitespace_suppressed_by_default() {
+    assert_eq!(lex("a  b", false), vec![
+        (0, Token::Ident { name: "a".into() }, 1),
+        (3, Token::Ident { name: "b".into() }, 4),
+        (4, Token::EOF, 4),
+    ]);
+}
+
+#[test]
+fn test_parser_skips_whitespace() {
+    let source = "  fn f() {\n    let a = 1;\n}";
+    let with_trivia = Parser::new(lex(source, true).into_iter()).parse_program();
+    let without = Parser::new(lex(source, false).into_iter()).parse_program();
+
+    assert_eq!(with_trivia, without);
+    assert!(with_trivia.is_ok());
+}
@@ -1,2 +1,7 @@
+#[macro_use]
+mod support;
+
+mod codes;
+mod diagnostic;
 mod lexer;
 mod parser;
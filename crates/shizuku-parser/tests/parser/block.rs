@@ -0,0 +1,104 @@
+use super::parse_source;
+use shizuku_parser::ASTNode;
+use shizuku_parser::Lexer;
+use shizuku_parser::ParseErrorType;
+use shizuku_parser::Parser;
+use shizuku_parser::Token;
+use shizuku_parser::ast::Literal;
+
+fn body(source: &str) -> Vec<ASTNode> {
+    match parse_source(source).pop() {
+        Some(ASTNode::Function { body, .. }) => body,
+        node => panic!("expected a function, found {:?}", node),
+    }
+}
+
+fn let_a_1() -> ASTNode {
+    ASTNode::Variable {
+        name: "a".into(),
+        var_type: None,
+        value: Some(Box::new(ASTNode::Literal(Literal::Int {
+            base: shizuku_parser::NumberBase::Decimal,
+            value: "1".into(),
+        }))),
+    }
+}
+
+#[test]
+fn test_tail_expression() {
+    assert_eq!(body("fn f() { let a = 1; a }"), vec![
+        let_a_1(),
+        ASTNode::Tail(Box::new(ASTNode::Variable {
+            name: "a".into(),
+            var_type: None,
+            value: None,
+        })),
+    ]);
+}
+
+#[test]
+fn test_if_expression() {
+    let int = |value: &str| {
+        Box::new(ASTNode::Literal(Literal::Int {
+            base: shizuku_parser::NumberBase::Decimal,
+            value: value.into(),
+        }))
+    };
+    let body = body("fn f() { let x = if c { 1 } else { 2 }; }");
+
+    assert_eq!(body, vec![ASTNode::Variable {
+        name: "x".into(),
+        var_type: None,
+        value: Some(Box::new(ASTNode::If {
+            condition: Box::new(ASTNode::Variable {
+                name: "c".into(),
+                var_type: None,
+                value: None,
+            }),
+            then_branch: vec![ASTNode::Tail(int("1"))],
+            else_branch: Some(vec![ASTNode::Tail(int("2"))]),
+        })),
+    }]);
+    assert_eq!(body[0].to_string(), "let x = if c { 1 } else { 2 };");
+}
+
+#[test]
+fn test_parentheses_are_kept() {
+    assert_eq!(body("fn f() { (a); }"), vec![ASTNode::ExpressionStatement(
+        Box::new(ASTNode::Paren(Box::new(ASTNode::Variable {
+            name: "a".into(),
+            var_type: None,
+            value: None,
+        })))
+    )]);
+    assert_eq!(
+        parse_source("fn f() { ((a + b)) * c; }")[0].to_string(),
+        "fn f() {\n    (((a + b)) * c);\n}"
+    );
+}
+
+#[test]
+fn test_last_statement_without_semicolon() {
+    assert_eq!(body("fn f() { let a = 1 }"), vec![let_a_1()]);
+}
+
+#[test]
+fn test_mid_block_statement_requires_semicolon() {
+    let source = "fn f() { let a = 1 a }";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+    let mut tokens = vec![];
+    while let Ok(token) = lexer.next() {
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+
+    let err = Parser::new(tokens.into_iter()).parse_program().unwrap_err();
+    assert_eq!(err.error, ParseErrorType::ExpectedToken {
+        expected: Token::Semicolon,
+        found: Token::Ident { name: "a".into() },
+    });
+}
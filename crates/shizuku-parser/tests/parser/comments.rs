@@ -0,0 +1,73 @@
+use shizuku_parser::ASTNode;
+use shizuku_parser::Lexer;
+use shizuku_parser::Parser;
+use shizuku_parser::TokenStr;
+
+fn parse(source: &str, preserve: bool) -> Vec<ASTNode> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars)).preserve_comments(preserve);
+    parser.parse_program().unwrap()
+}
+
+fn comments(comments: &[&str]) -> Vec<TokenStr> {
+    comments.iter().map(|&comment| comment.into()).collect()
+}
+
+#[test]
+fn test_leading_comment() {
+    let source = "// Does nothing.\nfn f() {}";
+    let ast = parse(source, true);
+
+    let [ASTNode::Commented {
+        leading_comments,
+        node,
+        trailing_comments,
+    }] = &ast[..]
+    else {
+        panic!("expected a commented item, got {ast:?}");
+    };
+    assert_eq!(leading_comments, &comments(&[" Does nothing."]));
+    assert!(matches!(**node, ASTNode::Function { .. }));
+    assert!(trailing_comments.is_empty());
+    assert_eq!(ast[0].to_string(), "// Does nothing.\nfn f() {\n}");
+}
+
+#[test]
+fn test_statement_comments() {
+    let source = "fn f() {\n    let a; // first\n    // second\n    // third\n    let b;\n}";
+    let ast = parse(source, true);
+
+    let [ASTNode::Function { body, .. }] = &ast[..] else {
+        panic!("expected a single function, got {ast:?}");
+    };
+    let [
+        ASTNode::Commented {
+            leading_comments: a_leading,
+            trailing_comments: a_trailing,
+            ..
+        },
+        ASTNode::Commented {
+            leading_comments: b_leading,
+            trailing_comments: b_trailing,
+            ..
+        },
+    ] = &body[..]
+    else {
+        panic!("expected two commented statements, got {body:?}");
+    };
+    assert!(a_leading.is_empty());
+    assert_eq!(a_trailing, &comments(&[" first"]));
+    assert_eq!(b_leading, &comments(&[" second", " third"]));
+    assert!(b_trailing.is_empty());
+    assert_eq!(ast[0].to_string(), source);
+}
+
+#[test]
+fn test_comments_are_skipped_by_default() {
+    let ast = parse("// Does nothing.\nfn f() {\n    let a; // first\n}", false);
+
+    let [ASTNode::Function { body, .. }] = &ast[..] else {
+        panic!("expected a single function, got {ast:?}");
+    };
+    assert!(matches!(&body[..], [ASTNode::Variable { .. }]));
+}
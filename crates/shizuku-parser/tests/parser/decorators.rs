@@ -0,0 +1,106 @@
+use super::parse_source;
+use shizuku_parser::ASTNode;
+use shizuku_parser::Lexer;
+use shizuku_parser::ParseErrorType;
+use shizuku_parser::Parser;
+use shizuku_parser::Token;
+use shizuku_parser::ast::Decorator;
+use shizuku_parser::ast::Literal;
+use shizuku_parser::NumberBase;
+
+#[test]
+fn test_decorated_function() {
+    let ast = parse_source("@memoize fn fib(n: int) -> int { n }");
+
+    let [ASTNode::Function { name, decorators, .. }] = ast.as_slice() else {
+        panic!("expected a function, found {:?}", ast);
+    };
+    assert_eq!(&name[..], "fib");
+    assert_eq!(decorators, &[Decorator {
+        name: "memoize".into(),
+        args: vec![],
+    }]);
+    assert_eq!(
+        ast[0].to_string(),
+        "@memoize\nfn fib(n: int) -> int {\n    n\n}"
+    );
+}
+
+#[test]
+fn test_decorator_arguments() {
+    let ast = parse_source("@cache(64) @inline extern fn f();");
+
+    let [ASTNode::Function { decorators, .. }] = ast.as_slice() else {
+        panic!("expected a function, found {:?}", ast);
+    };
+    assert_eq!(decorators, &[
+        Decorator {
+            name: "cache".into(),
+            args: vec![ASTNode::Literal(Literal::Int {
+                base: NumberBase::Decimal,
+                value: "64".into(),
+            })],
+        },
+        Decorator {
+            name: "inline".into(),
+            args: vec![],
+        },
+    ]);
+    assert_eq!(ast[0].to_string(), "@cache(64)\n@inline\nextern fn f();");
+}
+
+#[test]
+fn test_decorator_without_function() {
+    let source = "@memoize let a = 1;";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+    let mut tokens = vec![];
+    while let Ok(token) = lexer.next() {
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+
+    let err = Parser::new(tokens.into_iter()).parse_program().unwrap_err();
+    assert_eq!(err.error, ParseErrorType::ExpectedFunctionAfterDecorator);
+}
+
+#[test]
+fn test_pub_before_decorator() {
+    let ast = parse_source("pub @inline fn f() {}");
+
+    let [ASTNode::Function { is_public, decorators, .. }] = ast.as_slice() else {
+        panic!("expected a function, found {:?}", ast);
+    };
+    assert!(is_public);
+    assert_eq!(decorators, &[Decorator {
+        name: "inline".into(),
+        args: vec![],
+    }]);
+    assert_eq!(ast[0].to_string(), "@inline\npub fn f() {\n}");
+}
+
+#[test]
+fn test_decorator_before_pub() {
+    let ast = parse_source("@memoize pub fn t() {}");
+
+    let [ASTNode::Function { is_public, decorators, .. }] = ast.as_slice() else {
+        panic!("expected a function, found {:?}", ast);
+    };
+    assert!(is_public);
+    assert_eq!(decorators, &[Decorator {
+        name: "memoize".into(),
+        args: vec![],
+    }]);
+}
+
+#[test]
+fn test_pub_without_function() {
+    let tokens = tokens![Pub, Return, Semicolon, EOF];
+
+    let err = Parser::new(tokens.into_iter()).parse_program().unwrap_err();
+    assert_eq!(err.error, ParseErrorType::ExpectedFunctionAfterPub);
+    assert_eq!(err.location.start, 4);
+}
@@ -0,0 +1,46 @@
+use super::*;
+use shizuku_parser::lexer::Spanned;
+
+fn lex(source: &str) -> Vec<Spanned> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.next().expect("lex error");
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            return tokens;
+        }
+    }
+}
+
+#[test]
+fn test_empty_source() {
+    assert_eq!(lex(""), vec![(0, Token::EOF, 0)]);
+    assert_eq!(parse_source(""), vec![]);
+}
+
+#[test]
+fn test_only_eof_token() {
+    let mut parser = Parser::new(vec![(0, Token::EOF, 0)].into_iter());
+
+    assert_eq!(parser.parse_program(), Ok(vec![]));
+}
+
+#[test]
+fn test_only_newlines() {
+    assert_eq!(lex("\n\n"), vec![
+        (0, Token::NewLine, 1),
+        (1, Token::NewLine, 2),
+        (2, Token::EOF, 2),
+    ]);
+    assert_eq!(parse_source("\n\n"), vec![]);
+}
+
+#[test]
+fn test_only_comment() {
+    assert_eq!(lex("// just a comment").last(), Some(&(17, Token::EOF, 17)));
+    assert_eq!(parse_source("// just a comment"), vec![]);
+}
@@ -0,0 +1,46 @@
+use super::parse_source;
+use shizuku_parser::ASTNode;
+use shizuku_parser::Lexer;
+use shizuku_parser::ParseErrorType;
+use shizuku_parser::Parser;
+use shizuku_parser::Token;
+use shizuku_parser::TokenStr;
+
+#[test]
+fn test_generic_function() {
+    let ast = parse_source("fn id<T>(x: T) -> T { x }");
+
+    let [ASTNode::Function { type_params, .. }] = ast.as_slice() else {
+        panic!("expected a function, found {:?}", ast);
+    };
+    assert_eq!(type_params, &[TokenStr::from("T")]);
+    assert_eq!(ast[0].to_string(), "fn id<T>(x: T) -> T {\n    x\n}");
+}
+
+#[test]
+fn test_generic_struct() {
+    let ast = parse_source("struct Pair<A, B> {}");
+
+    let [ASTNode::Struct { type_params, .. }] = ast.as_slice() else {
+        panic!("expected a struct, found {:?}", ast);
+    };
+    assert_eq!(type_params, &[TokenStr::from("A"), TokenStr::from("B")]);
+}
+
+#[test]
+fn test_missing_type_parameter() {
+    let source = "struct Pair<> {}";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+    let mut tokens = vec![];
+    while let Ok(token) = lexer.next() {
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+
+    let err = Parser::new(tokens.into_iter()).parse_program().unwrap_err();
+    assert_eq!(err.error, ParseErrorType::ExpectedTypeParameter);
+}
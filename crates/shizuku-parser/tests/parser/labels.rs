@@ -0,0 +1,61 @@
+use super::parse_source;
+use shizuku_parser::ASTNode;
+use shizuku_parser::Lexer;
+use shizuku_parser::ParseErrorType;
+use shizuku_parser::Parser;
+use shizuku_parser::Token;
+use shizuku_parser::TokenStr;
+
+fn var(name: &str) -> Box<ASTNode> {
+    Box::new(ASTNode::Variable {
+        name: name.into(),
+        var_type: None,
+        value: None,
+    })
+}
+
+#[test]
+fn test_labeled_nested_loop() {
+    let source = "fn f() { 'outer: while a { while b { break 'outer; } continue; } }";
+    let ast = parse_source(source);
+
+    let [ASTNode::Function { body, .. }] = ast.as_slice() else {
+        panic!("expected a function, found {:?}", ast);
+    };
+    assert_eq!(body, &[ASTNode::While {
+        label: Some(TokenStr::from("outer")),
+        condition: var("a"),
+        body: vec![
+            ASTNode::While {
+                label: None,
+                condition: var("b"),
+                body: vec![ASTNode::Break {
+                    label: Some(TokenStr::from("outer")),
+                }],
+            },
+            ASTNode::Continue { label: None },
+        ],
+    }]);
+    assert_eq!(
+        ast[0].to_string(),
+        "fn f() {\n    'outer: while a {\n        while b {\n            break 'outer;\n        }\n        continue;\n    }\n}"
+    );
+}
+
+#[test]
+fn test_label_without_loop() {
+    let source = "fn f() { 'outer: a; }";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+    let mut tokens = vec![];
+    while let Ok(token) = lexer.next() {
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+
+    let err = Parser::new(tokens.into_iter()).parse_program().unwrap_err();
+    assert_eq!(err.error, ParseErrorType::ExpectedLoopAfterLabel);
+}
@@ -0,0 +1,77 @@
+use shizuku_parser::ASTNode;
+use shizuku_parser::NumberBase;
+use shizuku_parser::Parser;
+use shizuku_parser::Token;
+use shizuku_parser::ast::*;
+
+fn tokens() -> Vec<(u32, Token, u32)> {
+    // let a = 1;
+    // b;
+    vec![
+        (0, Token::Let, 3),
+        (4, Token::Ident { name: "a".into() }, 5),
+        (6, Token::Equal, 7),
+        (
+            8,
+            Token::Int {
+                base: NumberBase::Decimal,
+                value: "1".into(),
+            },
+            9,
+        ),
+        (9, Token::Semicolon, 10),
+        (10, Token::NewLine, 11),
+        (11, Token::Ident { name: "b".into() }, 12),
+        (12, Token::Semicolon, 13),
+        (13, Token::EOF, 13),
+    ]
+}
+
+#[test]
+fn test_peek_nth() {
+    let mut parser = Parser::new(tokens().into_iter());
+
+    assert_eq!(parser.peek_nth(0), Some(&(0, Token::Let, 3)));
+    assert_eq!(parser.peek_nth(2), Some(&(6, Token::Equal, 7)));
+    assert_eq!(
+        parser.peek_nth(1),
+        Some(&(4, Token::Ident { name: "a".into() }, 5))
+    );
+}
+
+#[test]
+fn test_peek_nth_skips_newlines() {
+    let mut parser = Parser::new(tokens().into_iter());
+
+    assert_eq!(
+        parser.peek_nth(5),
+        Some(&(11, Token::Ident { name: "b".into() }, 12))
+    );
+    assert_eq!(parser.peek_nth(7), Some(&(13, Token::EOF, 13)));
+    assert_eq!(parser.peek_nth(8), None);
+}
+
+#[test]
+fn test_peek_nth_does_not_consume() {
+    let mut parser = Parser::new(tokens().into_iter());
+    parser.peek_nth(6);
+
+    assert_eq!(
+        parser.parse_program(),
+        Ok(vec![
+            ASTNode::Variable {
+                name: "a".into(),
+                var_type: None,
+                value: Some(Box::new(ASTNode::Literal(Literal::Int {
+                    base: NumberBase::Decimal,
+                    value: "1".into(),
+                }))),
+            },
+            ASTNode::ExpressionStatement(Box::new(ASTNode::Variable {
+                name: "b".into(),
+                var_type: None,
+                value: None,
+            })),
+        ])
+    );
+}
@@ -1,33 +1,50 @@
+mod block;
+mod comments;
+mod decorators;
+mod empty;
+mod generics;
+mod labels;
+mod lookahead;
+mod recovery;
+mod snapshot;
+mod types;
+
 use shizuku_parser::ASTNode;
+use shizuku_parser::Lexer;
+use shizuku_parser::LexicalErrorType;
+use shizuku_parser::ParseError;
+use shizuku_parser::ParseErrorType;
 use shizuku_parser::Parser;
+use shizuku_parser::SrcSpan;
 use shizuku_parser::Token;
 use shizuku_parser::ast::*;
 
+/// Lexes and parses `source`, panicking on any error.
+fn parse_source(source: &str) -> Vec<ASTNode> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.next().expect("lex error");
+        let eof = token.1 == Token::EOF;
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+
+    let mut parser = Parser::new(tokens.into_iter());
+    parser.parse_program().expect("parse error")
+}
+
 #[test]
 fn test_parse_function_declaration() {
     // fn add (a: i32, b: i32) -> i32 { return a + b; }
-    let source_tokens = vec![
-        (0, Token::Fn, 2),                             // fn
-        (3, Token::Ident { name: "add".into() }, 6),   // add
-        (6, Token::LParen, 7),                         // (
-        (7, Token::Ident { name: "a".into() }, 8),     // a
-        (8, Token::Colon, 9),                          // :
-        (10, Token::Ident { name: "i32".into() }, 13), // i32
-        (13, Token::Comma, 14),                        // ,
-        (15, Token::Ident { name: "b".into() }, 16),   // b
-        (16, Token::Colon, 17),                        // :
-        (18, Token::Ident { name: "i32".into() }, 21), // i32
-        (21, Token::RParen, 22),                       // )
-        (23, Token::MinusRArrow, 25),                  // ->
-        (26, Token::Ident { name: "i32".into() }, 29), // i32
-        (30, Token::LBrace, 31),                       // {
-        (32, Token::Return, 38),                       // return
-        (39, Token::Ident { name: "a".into() }, 40),   // a
-        (41, Token::Plus, 42),                         // +
-        (43, Token::Ident { name: "b".into() }, 44),   // b
-        (44, Token::Semicolon, 45),                    // ;
-        (46, Token::RBrace, 47),                       // }
-        (48, Token::EOF, 48),                          // EOF
+    let source_tokens = tokens![
+        Fn, Ident("add"), LParen, Ident("a"), Colon, Ident("i32"), Comma, Ident("b"), Colon,
+        Ident("i32"), RParen, MinusRArrow, Ident("i32"), LBrace, Return, Ident("a"), Plus,
+        Ident("b"), Semicolon, RBrace, EOF,
     ];
 
     let mut parser = Parser::new(source_tokens.into_iter());
@@ -35,29 +52,321 @@ fn test_parse_function_declaration() {
 
     assert_eq!(ast, vec![ASTNode::Function {
         name: "add".into(),
+        type_params: vec![],
         params: vec![
             Parameter {
                 name: "a".into(),
-                param_type: Type { name: "i32".into() },
+                param_type: Type::Named { name: "i32".into() },
             },
             Parameter {
                 name: "b".into(),
-                param_type: Type { name: "i32".into() },
+                param_type: Type::Named { name: "i32".into() },
             },
         ],
-        return_type: Some(Type { name: "i32".into() }),
+        return_type: Some(Type::Named { name: "i32".into() }),
+        is_external: false,
+        is_variadic: false,
+        is_public: false,
+        decorators: vec![],
         body: vec![ASTNode::Return {
             value: Some(Box::new(ASTNode::BinaryOp {
                 left: Box::new(ASTNode::Variable {
                     name: "a".into(),
+                    var_type: None,
                     value: None
                 }),
                 operator: Token::Plus,
                 right: Box::new(ASTNode::Variable {
                     name: "b".into(),
+                    var_type: None,
                     value: None
                 })
             })),
         }],
     }]);
 }
+
+#[test]
+fn test_parse_extern_function() {
+    let ast = parse_source("extern fn printf(fmt: string) -> i32;");
+
+    assert_eq!(ast, vec![ASTNode::Function {
+        name: "printf".into(),
+        type_params: vec![],
+        params: vec![Parameter {
+            name: "fmt".into(),
+            param_type: Type::Named {
+                name: "string".into()
+            },
+        }],
+        return_type: Some(Type::Named { name: "i32".into() }),
+        is_external: true,
+        is_variadic: false,
+        is_public: false,
+        decorators: vec![],
+        body: vec![],
+    }]);
+    assert_eq!(ast[0].to_string(), "extern fn printf(fmt: string) -> i32;");
+}
+
+#[test]
+fn test_parse_variadic_function() {
+    let ast = parse_source("extern fn printf(fmt: string, ...) -> i32;");
+
+    assert_eq!(ast, vec![ASTNode::Function {
+        name: "printf".into(),
+        type_params: vec![],
+        params: vec![Parameter {
+            name: "fmt".into(),
+            param_type: Type::Named {
+                name: "string".into()
+            },
+        }],
+        return_type: Some(Type::Named { name: "i32".into() }),
+        is_external: true,
+        is_variadic: true,
+        is_public: false,
+        decorators: vec![],
+        body: vec![],
+    }]);
+    assert_eq!(ast[0].to_string(), "extern fn printf(fmt: string, ...) -> i32;");
+}
+
+#[test]
+fn test_parse_error_location() {
+    // fn add (
+    let source_tokens = vec![
+        (0, Token::Fn, 2),                           // fn
+        (3, Token::Ident { name: "add".into() }, 6), // add
+        (7, Token::LBrace, 8),                       // {
+    ];
+
+    let mut parser = Parser::new(source_tokens.into_iter());
+    let err = parser.parse_program().unwrap_err();
+
+    assert_eq!(err, ParseError {
+        error: ParseErrorType::ExpectedToken {
+            expected: Token::LParen,
+            found: Token::LBrace,
+        },
+        location: SrcSpan { start: 7, end: 8 },
+    });
+}
+
+#[test]
+fn test_parse_error_unexpected_eof() {
+    // fn add
+    let source_tokens = vec![
+        (0, Token::Fn, 2),                           // fn
+        (3, Token::Ident { name: "add".into() }, 6), // add
+    ];
+
+    let mut parser = Parser::new(source_tokens.into_iter());
+    let err = parser.parse_program().unwrap_err();
+
+    assert_eq!(err.error, ParseErrorType::UnexpectedEof);
+    assert_eq!(err.location, SrcSpan { start: 6, end: 6 });
+}
+
+#[test]
+fn test_parser_from_lexer() {
+    let source = "let x = a;";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars));
+
+    assert_eq!(parser.parse_program(), Ok(vec![ASTNode::Variable {
+        name: "x".into(),
+        var_type: None,
+        value: Some(Box::new(ASTNode::Variable {
+            name: "a".into(),
+            var_type: None,
+            value: None,
+        })),
+    }]));
+    assert_eq!(parser.lex_error(), None);
+}
+
+#[test]
+fn test_parser_from_lexer_lex_error() {
+    let source = "let x = $;";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars));
+
+    let err = parser.parse_program().unwrap_err();
+    assert_eq!(err.error, ParseErrorType::UnexpectedEof);
+    assert_eq!(
+        parser.lex_error().map(|e| &e.error),
+        Some(&LexicalErrorType::UnrecognizedToken { tok: '$' })
+    );
+}
+
+#[test]
+fn test_parse_without_eof_token() {
+    // fn f() { g(); }
+    let source_tokens = vec![
+        (0, Token::Fn, 2),                          // fn
+        (3, Token::Ident { name: "f".into() }, 4),  // f
+        (4, Token::LParen, 5),                      // (
+        (5, Token::RParen, 6),                      // )
+        (7, Token::LBrace, 8),                      // {
+        (9, Token::Ident { name: "g".into() }, 10), // g
+        (10, Token::LParen, 11),                    // (
+        (11, Token::RParen, 12),                    // )
+        (12, Token::Semicolon, 13),                 // ;
+        (14, Token::RBrace, 15),                    // }
+    ];
+
+    let mut parser = Parser::new(source_tokens.into_iter());
+    let ast = parser.parse_program().expect("Failed to parse program");
+
+    assert_eq!(ast, vec![ASTNode::Function {
+        name: "f".into(),
+        type_params: vec![],
+        params: vec![],
+        return_type: None,
+        is_external: false,
+        is_variadic: false,
+        is_public: false,
+        decorators: vec![],
+        body: vec![ASTNode::ExpressionStatement(Box::new(
+            ASTNode::FunctionCall {
+                name: "g".into(),
+                arguments: vec![],
+            }
+        ))],
+    }]);
+}
+
+#[test]
+fn test_parse_error_eof_inside_block() {
+    // fn f() { g();
+    let source_tokens = vec![
+        (0, Token::Fn, 2),                          // fn
+        (3, Token::Ident { name: "f".into() }, 4),  // f
+        (4, Token::LParen, 5),                      // (
+        (5, Token::RParen, 6),                      // )
+        (7, Token::LBrace, 8),                      // {
+        (9, Token::Ident { name: "g".into() }, 10), // g
+        (10, Token::LParen, 11),                    // (
+        (11, Token::RParen, 12),                    // )
+        (12, Token::Semicolon, 13),                 // ;
+        (13, Token::EOF, 13),                       // EOF
+    ];
+
+    let mut parser = Parser::new(source_tokens.into_iter());
+    let err = parser.parse_program().unwrap_err();
+
+    assert_eq!(err.error, ParseErrorType::UnexpectedEof);
+    assert_eq!(err.location, SrcSpan { start: 13, end: 13 });
+}
+
+#[test]
+fn test_parse_modulo_precedence() {
+    // let x = a + b % c;
+    let source_tokens = vec![
+        (0, Token::Let, 3),                          // let
+        (4, Token::Ident { name: "x".into() }, 5),   // x
+        (6, Token::Equal, 7),                        // =
+        (8, Token::Ident { name: "a".into() }, 9),   // a
+        (10, Token::Plus, 11),                       // +
+        (12, Token::Ident { name: "b".into() }, 13), // b
+        (14, Token::Percent, 15),                    // %
+        (16, Token::Ident { name: "c".into() }, 17), // c
+        (17, Token::Semicolon, 18),                  // ;
+        (18, Token::EOF, 18),                        // EOF
+    ];
+
+    let mut parser = Parser::new(source_tokens.into_iter());
+    let ast = parser.parse_program().expect("Failed to parse program");
+
+    let var = |name: &str| {
+        Box::new(ASTNode::Variable {
+            name: name.into(),
+            var_type: None,
+            value: None,
+        })
+    };
+    assert_eq!(ast, vec![ASTNode::Variable {
+        name: "x".into(),
+        var_type: None,
+        value: Some(Box::new(ASTNode::BinaryOp {
+            left: var("a"),
+            operator: Token::Plus,
+            right: Box::new(ASTNode::BinaryOp {
+                left: var("b"),
+                operator: Token::Percent,
+                right: var("c"),
+            }),
+        })),
+    }]);
+}
+
+#[test]
+fn test_parse_logical_not() {
+    let var = |name: &str| {
+        Box::new(ASTNode::Variable {
+            name: name.into(),
+            var_type: None,
+            value: None,
+        })
+    };
+    let not = |operand| {
+        Some(Box::new(ASTNode::UnaryOp {
+            operator: Token::Exclamation,
+            operand,
+        }))
+    };
+    let a_eq_b = Box::new(ASTNode::BinaryOp {
+        left: var("a"),
+        operator: Token::Equal2,
+        right: var("b"),
+    });
+
+    assert_eq!(parse_source("let x = !done;"), vec![ASTNode::Variable {
+        name: "x".into(),
+        var_type: None,
+        value: not(var("done")),
+    }]);
+    assert_eq!(parse_source("let x = !(a == b);"), vec![ASTNode::Variable {
+        name: "x".into(),
+        var_type: None,
+        value: not(Box::new(ASTNode::Paren(a_eq_b))),
+    }]);
+    // `!` binds tighter than `==`.
+    assert_eq!(parse_source("let x = !a == b;"), vec![ASTNode::Variable {
+        name: "x".into(),
+        var_type: None,
+        value: Some(Box::new(ASTNode::BinaryOp {
+            left: not(var("a")).unwrap(),
+            operator: Token::Equal2,
+            right: var("b"),
+        })),
+    }]);
+}
+
+#[test]
+fn test_parse_try() {
+    let call = |name: &str| ASTNode::FunctionCall {
+        name: name.into(),
+        arguments: vec![],
+    };
+
+    assert_eq!(parse_source("foo()?;"), vec![ASTNode::ExpressionStatement(
+        Box::new(ASTNode::Try {
+            expr: Box::new(call("foo")),
+        })
+    )]);
+    // Postfix `?` binds tighter than prefix `!`, and may repeat.
+    assert_eq!(parse_source("let x = !foo()??;"), vec![ASTNode::Variable {
+        name: "x".into(),
+        var_type: None,
+        value: Some(Box::new(ASTNode::UnaryOp {
+            operator: Token::Exclamation,
+            operand: Box::new(ASTNode::Try {
+                expr: Box::new(ASTNode::Try {
+                    expr: Box::new(call("foo")),
+                }),
+            }),
+        })),
+    }]);
+}
@@ -0,0 +1,73 @@
+use shizuku_parser::ASTNode;
+use shizuku_parser::Lexer;
+use shizuku_parser::NumberBase;
+use shizuku_parser::ParseError;
+use shizuku_parser::ParseErrorType;
+use shizuku_parser::Parser;
+use shizuku_parser::SrcSpan;
+use shizuku_parser::Token;
+use shizuku_parser::ast::*;
+
+fn variable(name: &str) -> ASTNode {
+    ASTNode::Variable {
+        name: name.into(),
+        var_type: None,
+        value: None,
+    }
+}
+
+#[test]
+fn test_recover_missing_semicolon_before_newline() {
+    let source = "fn f() {\n    let x = 1\n    return x;\n}";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars)).with_recovery();
+    let ast = parser.parse_program().unwrap();
+
+    let [ASTNode::Function { body, .. }] = &ast[..] else {
+        panic!("expected a single function, got {ast:?}");
+    };
+    assert_eq!(body, &vec![
+        ASTNode::Variable {
+            name: "x".into(),
+            var_type: None,
+            value: Some(Box::new(ASTNode::Literal(Literal::Int {
+                base: NumberBase::Decimal,
+                value: "1".into(),
+            }))),
+        },
+        ASTNode::Return {
+            value: Some(Box::new(variable("x"))),
+        },
+    ]);
+    assert_eq!(parser.errors(), &[ParseError {
+        error: ParseErrorType::ExpectedToken {
+            expected: Token::Semicolon,
+            found: Token::Return,
+        },
+        location: SrcSpan { start: 27, end: 33 },
+    }]);
+}
+
+#[test]
+fn test_missing_semicolon_is_fatal_without_recovery() {
+    let source = "let x = 1\nreturn x;";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars));
+
+    let err = parser.parse_program().unwrap_err();
+    assert_eq!(err.error, ParseErrorType::ExpectedToken {
+        expected: Token::Semicolon,
+        found: Token::Return,
+    });
+    assert!(parser.errors().is_empty());
+}
+
+#[test]
+fn test_missing_semicolon_on_same_line_is_fatal() {
+    let source = "let x = 1 return x;";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars)).with_recovery();
+
+    assert!(parser.parse_program().is_err());
+    assert!(parser.errors().is_empty());
+}
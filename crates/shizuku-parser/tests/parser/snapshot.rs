@@ -0,0 +1,145 @@
+//! Golden tests comparing the printed AST of small programs against
+//! expected source.
+
+use super::parse_source as parse;
+
+/// Line by line diff, marking expected lines with `-` and actual ones with `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => out += &format!("  {}\n", e),
+            (e, a) => {
+                if let Some(e) = e {
+                    out += &format!("- {}\n", e);
+                }
+                if let Some(a) = a {
+                    out += &format!("+ {}\n", a);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Parses `source` and compares its printed AST with `expected`, ignoring
+/// leading and trailing blank lines.
+#[track_caller]
+fn assert_snapshot(source: &str, expected: &str) {
+    let actual = parse(source)
+        .iter()
+        .map(|node| node.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let expected = expected.trim_matches('\n');
+
+    if actual != expected {
+        panic!(
+            "snapshot mismatch (-expected +actual):\n{}",
+            diff(expected, &actual)
+        );
+    }
+}
+
+#[test]
+fn test_snapshot_function() {
+    assert_snapshot(
+        r#"
+fn sum(a: i32, b: i32) -> i32 {
+    let total: i32 = a + b * a;
+    return total;
+}
+"#,
+        r#"
+fn sum(a: i32, b: i32) -> i32 {
+    let total: i32 = (a + (b * a));
+    return total;
+}
+"#,
+    );
+}
+
+#[test]
+fn test_snapshot_if_else() {
+    assert_snapshot(
+        r#"
+fn max(a: i32, b: i32) -> i32 {
+    if a < b {
+        return b;
+    } else if a == b {
+        return a;
+    } else {
+        log(a, b);
+    }
+    return a;
+}
+"#,
+        r#"
+fn max(a: i32, b: i32) -> i32 {
+    if (a < b) {
+        return b;
+    } else {
+        if (a == b) {
+            return a;
+        } else {
+            log(a, b);
+        }
+    }
+    return a;
+}
+"#,
+    );
+}
+
+#[test]
+fn test_snapshot_while() {
+    assert_snapshot(
+        r#"
+fn count(n: i32, step: i32) {
+    let i = n;
+    while i > n - step and i != step {
+        i = i - step;
+    }
+}
+"#,
+        r#"
+fn count(n: i32, step: i32) {
+    let i = n;
+    while ((i > (n - step)) and (i != step)) {
+        i = (i - step);
+    }
+}
+"#,
+    );
+}
+
+#[test]
+fn test_snapshot_struct() {
+    assert_snapshot(
+        r#"
+struct Point {
+    x: i32,
+    y: i32
+}
+"#,
+        r#"
+struct Point {
+    x: i32,
+    y: i32,
+}
+"#,
+    );
+}
+
+#[test]
+fn test_string_escapes_round_trip() {
+    let source = r#"fn f() { s = "a\"b\n\t\r\\\0"; }"#;
+    let ast = parse(source);
+    let printed = ast[0].to_string();
+
+    assert!(printed.contains(r#""a\"b\n\t\r\\\0""#), "{printed}");
+    assert_eq!(parse(&printed), ast);
+}
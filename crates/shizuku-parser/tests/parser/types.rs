@@ -0,0 +1,70 @@
+use super::parse_source;
+use shizuku_parser::ASTNode;
+use shizuku_parser::ast::Type;
+
+/// Parses `ty` as the annotation of a parameter and returns it.
+fn param_type(ty: &str) -> Type {
+    let mut ast = parse_source(&format!("fn f(x: {ty}) {{}}"));
+    match ast.pop() {
+        Some(ASTNode::Function { mut params, .. }) => params.remove(0).param_type,
+        node => panic!("expected a function, found {:?}", node),
+    }
+}
+
+fn named(name: &str) -> Type {
+    Type::Named { name: name.into() }
+}
+
+#[test]
+fn test_function_type_without_params() {
+    assert_eq!(param_type("fn() -> void"), Type::Function {
+        params: vec![],
+        return_type: Box::new(named("void")),
+    });
+}
+
+#[test]
+fn test_function_type() {
+    assert_eq!(param_type("fn(int) -> bool"), Type::Function {
+        params: vec![named("int")],
+        return_type: Box::new(named("bool")),
+    });
+}
+
+#[test]
+fn test_nested_function_type() {
+    let ty = param_type("fn(fn(int) -> int) -> int");
+
+    assert_eq!(ty, Type::Function {
+        params: vec![Type::Function {
+            params: vec![named("int")],
+            return_type: Box::new(named("int")),
+        }],
+        return_type: Box::new(named("int")),
+    });
+    assert_eq!(ty.to_string(), "fn(fn(int) -> int) -> int");
+}
+
+#[test]
+fn test_function_type_in_let() {
+    let ast = parse_source("fn f() { let g: fn(int, int) -> int = add; }");
+
+    let [ASTNode::Function { body, .. }] = ast.as_slice() else {
+        panic!("expected a function, found {:?}", ast);
+    };
+    let [ASTNode::Variable { var_type, .. }] = body.as_slice() else {
+        panic!("expected a let, found {:?}", body);
+    };
+    assert_eq!(var_type.as_ref().unwrap().to_string(), "fn(int, int) -> int");
+}
+
+#[test]
+fn test_array_and_pointer_types() {
+    assert_eq!(param_type("*[i32; 4]").to_string(), "*[i32; 4]");
+    assert_eq!(param_type("[*Point; 0x10]").to_string(), "[*Point; 0x10]");
+    assert_eq!(param_type("**int"), Type::Pointer {
+        pointee: Box::new(Type::Pointer {
+            pointee: Box::new(named("int")),
+        }),
+    });
+}
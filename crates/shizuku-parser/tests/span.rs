@@ -0,0 +1,37 @@
+use shizuku_common::span::SrcSpan;
+use shizuku_parser::Lexer;
+use shizuku_parser::LexerConfig;
+use shizuku_parser::LexicalErrorType;
+use shizuku_parser::Token;
+use shizuku_parser::lexer::assert_spans_monotonic;
+use shizuku_parser::tokenize_all;
+
+#[test]
+fn test_lexer_span_is_common_span() {
+    let source = "a $";
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+    lexer.next().unwrap();
+
+    let err = lexer.next().unwrap_err();
+    assert_eq!(err.error, LexicalErrorType::UnrecognizedToken { tok: '$' });
+    let span: SrcSpan = err.location;
+    assert_eq!(span, SrcSpan { start: 2, end: 2 });
+}
+
+#[test]
+fn test_lexer_spans_are_monotonic() {
+    let source = "fn main() -> i32 {\n    let s = \"hi\";\n    return 0x1f + 2.5;\n}\n";
+    let (tokens, errors) = tokenize_all(source, LexerConfig::default());
+    assert!(errors.is_empty());
+    assert_spans_monotonic(&tokens);
+}
+
+#[test]
+#[should_panic(expected = "overlaps")]
+fn test_overlapping_spans() {
+    assert_spans_monotonic(&[
+        (0, Token::Ident { name: "ab".into() }, 2),
+        (1, Token::Ident { name: "b".into() }, 2),
+    ]);
+}
@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests.
+
+use shizuku_parser::Token;
+
+/// Builds a `Vec<(u32, Token, u32)>` from token kinds, as the lexer would
+/// produce for the tokens separated by single spaces.
+///
+/// Unit tokens are written by variant name and identifiers as `Ident("add")`.
+macro_rules! tokens {
+    ($($kind:ident $(($arg:expr))?),* $(,)?) => {
+        $crate::support::spaced(vec![$(token!($kind $(($arg))?)),*])
+    };
+}
+
+macro_rules! token {
+    (Ident($name:expr)) => {
+        shizuku_parser::Token::Ident { name: $name.into() }
+    };
+    ($kind:ident) => {
+        shizuku_parser::Token::$kind
+    };
+}
+
+/// Assigns each token a span as long as its source text, one column after
+/// the previous token. `EOF` and `NewLine` have no text and get empty spans.
+pub fn spaced(tokens: Vec<Token>) -> Vec<(u32, Token, u32)> {
+    let mut start = 0;
+    tokens
+        .into_iter()
+        .map(|token| {
+            let len = match token {
+                Token::EOF | Token::NewLine => 0,
+                _ => token.to_string().len() as u32,
+            };
+            let spanned = (start, token, start + len);
+            start += len + 1;
+            spanned
+        })
+        .collect()
+}
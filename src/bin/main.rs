@@ -5,6 +5,8 @@ use llvm_sys::prelude::*;
 use llvm_sys::target::*;
 use llvm_sys::target_machine::LLVMCodeGenFileType::*;
 use llvm_sys::target_machine::*;
+use shizuku::CompileOptions;
+use shizuku::compile_str;
 use std::ffi::CString;
 use std::fmt::Display;
 use std::ptr;
@@ -176,7 +178,41 @@ fn link_object_to_executable(object_filename: &str, output_filename: &str) {
     }
 }
 
+// ******************************* Textual IR *******************************
+
+// Print the IR of the file at `path` in the textual form `parse_ir` reads,
+// returning the exit code.
+fn emit_ir(path: &str) -> i32 {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return 1;
+        }
+    };
+    match compile_str(&source, &CompileOptions::default()) {
+        Ok((program, warnings)) => {
+            for warning in warnings {
+                eprintln!("{}: warning: {}", path, warning);
+            }
+            print!("{}", program);
+            0
+        }
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            1
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [flag, path] = &args[..]
+        && flag == "--emit=ir"
+    {
+        std::process::exit(emit_ir(path));
+    }
+
     println!("LLVM version: {}", LLVMVersion::get_llvm_version());
 
     unsafe {
@@ -0,0 +1,85 @@
+use crate::CompileError;
+use crate::lower_program;
+use shizuku_ir::Program;
+use shizuku_ir::Warning;
+use shizuku_ir::check_program;
+use shizuku_parser::Lexer;
+use shizuku_parser::Parser;
+
+/// Options for `compile_str`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// Fail with `CompileError::Warning` on the first warning instead of
+    /// returning the warnings with the program.
+    pub warnings_as_errors: bool,
+}
+
+/// Lexes, parses, lowers and type checks `source`, returning the checked
+/// program and the warnings found along the way.
+pub fn compile_str(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<(Program, Vec<Warning>), CompileError> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars));
+    let ast = parser
+        .parse_program()
+        .map_err(|err| CompileError::from_parse(err, parser.lex_error()))?;
+
+    let mut program = lower_program(&ast)?;
+    let warnings = check_program(&mut program)?;
+    if options.warnings_as_errors {
+        if let Some(warning) = warnings.into_iter().next() {
+            return Err(CompileError::Warning(warning));
+        }
+        return Ok((program, vec![]));
+    }
+    Ok((program, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shizuku_ir::Symbol;
+
+    const SHADOWING: &str = "fn f() { let x = 1; let x = 2; }";
+
+    fn shadowing() -> Warning {
+        Warning::Shadowing {
+            function: Symbol("f".to_string()),
+            name: Symbol("x".to_string()),
+            previous_index: 0,
+            new_index: 1,
+        }
+    }
+
+    #[test]
+    fn test_compile_str_returns_warnings() {
+        let (program, warnings) = compile_str(SHADOWING, &CompileOptions::default()).unwrap();
+
+        assert_eq!(program.functions.len(), 1);
+        assert_eq!(warnings, vec![shadowing()]);
+    }
+
+    #[test]
+    fn test_warnings_as_errors() {
+        let options = CompileOptions {
+            warnings_as_errors: true,
+        };
+        assert_eq!(
+            compile_str(SHADOWING, &options),
+            Err(CompileError::Warning(shadowing()))
+        );
+
+        let (_, warnings) = compile_str("fn f() { let x = 1; }", &options).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_compile_str_lex_error() {
+        assert!(matches!(
+            compile_str("fn f() { $ }", &CompileOptions::default()),
+            Err(CompileError::Lex(_))
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::LowerError;
+use shizuku_codegen::CodegenError;
+use shizuku_ir::TypeError;
+use shizuku_ir::Warning;
+use shizuku_parser::LexicalError;
+use shizuku_parser::ParseError;
+use std::fmt;
+
+/// An error raised by any phase of the compile pipeline.
+///
+/// Each variant keeps the original error of its phase, so callers can still
+/// inspect the location and kind after propagating it with `?`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CompileError {
+    Lex(LexicalError),
+    Parse(ParseError),
+    Lower(LowerError),
+    Type(TypeError),
+    Codegen(CodegenError),
+    /// A warning, under `CompileOptions::warnings_as_errors`.
+    Warning(Warning),
+}
+
+impl CompileError {
+    /// The error for a failed parse. A lexical error shows up as an early
+    /// end of input, so `lex_error`, the one that cut the tokens short, is
+    /// reported instead when there is one.
+    pub(crate) fn from_parse(err: ParseError, lex_error: Option<&LexicalError>) -> Self {
+        match lex_error {
+            Some(lex_err) => CompileError::Lex(lex_err.clone()),
+            None => CompileError::Parse(err),
+        }
+    }
+}
+
+impl From<LexicalError> for CompileError {
+    fn from(err: LexicalError) -> Self {
+        CompileError::Lex(err)
+    }
+}
+
+impl From<ParseError> for CompileError {
+    fn from(err: ParseError) -> Self {
+        CompileError::Parse(err)
+    }
+}
+
+impl From<LowerError> for CompileError {
+    fn from(err: LowerError) -> Self {
+        CompileError::Lower(err)
+    }
+}
+
+impl From<TypeError> for CompileError {
+    fn from(err: TypeError) -> Self {
+        CompileError::Type(err)
+    }
+}
+
+impl From<CodegenError> for CompileError {
+    fn from(err: CodegenError) -> Self {
+        CompileError::Codegen(err)
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Lex(err) => write!(f, "lex: {}", err),
+            CompileError::Parse(err) => write!(f, "parse: {}", err),
+            CompileError::Lower(err) => write!(f, "lower: {}", err),
+            CompileError::Type(err) => write!(f, "type: {}", err),
+            CompileError::Codegen(err) => write!(f, "codegen: {}", err),
+            CompileError::Warning(warning) => write!(f, "warning: {}", warning),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompileError::Lex(_) | CompileError::Warning(_) => None,
+            CompileError::Parse(err) => Some(err),
+            CompileError::Lower(err) => Some(err),
+            CompileError::Type(err) => Some(err),
+            CompileError::Codegen(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shizuku_ir::Type;
+    use shizuku_parser::LexicalErrorType;
+    use shizuku_parser::ParseErrorType;
+    use shizuku_parser::SrcSpan;
+    use shizuku_parser::Token;
+
+    #[test]
+    fn test_from_lexical_error() {
+        let lex_err = LexicalError {
+            error: LexicalErrorType::UnexpectedStringEnd,
+            location: SrcSpan { start: 0, end: 6 },
+        };
+        let err = CompileError::from(lex_err.clone());
+
+        assert_eq!(err, CompileError::Lex(lex_err));
+        assert_eq!(err.to_string(), "lex: unterminated string literal at 0..6");
+    }
+
+    #[test]
+    fn test_from_parse_error() {
+        let parse_err = ParseError {
+            error: ParseErrorType::ExpectedToken {
+                expected: Token::RParen,
+                found: Token::LBrace,
+            },
+            location: SrcSpan { start: 7, end: 8 },
+        };
+        let err = CompileError::from(parse_err.clone());
+
+        assert_eq!(err, CompileError::Parse(parse_err));
+        assert_eq!(err.to_string(), "parse: expected ')', found '{' at 7..8");
+    }
+
+    #[test]
+    fn test_from_parse_prefers_lex_error() {
+        let lex_err = LexicalError {
+            error: LexicalErrorType::UnexpectedStringEnd,
+            location: SrcSpan { start: 4, end: 6 },
+        };
+        let parse_err = ParseError {
+            error: ParseErrorType::UnexpectedEof,
+            location: SrcSpan { start: 4, end: 4 },
+        };
+
+        assert_eq!(
+            CompileError::from_parse(parse_err.clone(), Some(&lex_err)),
+            CompileError::Lex(lex_err)
+        );
+        assert_eq!(
+            CompileError::from_parse(parse_err.clone(), None),
+            CompileError::Parse(parse_err)
+        );
+    }
+
+    #[test]
+    fn test_from_lower_error() {
+        let lower_err = LowerError::UnknownType("u128".to_string());
+        let err = CompileError::from(lower_err.clone());
+
+        assert_eq!(err, CompileError::Lower(lower_err));
+        assert_eq!(err.to_string(), "lower: unknown type `u128`");
+    }
+
+    #[test]
+    fn test_from_type_error() {
+        let type_err = TypeError::Mismatch {
+            expected: Type::Int,
+            found: Type::Bool,
+        };
+        let err = CompileError::from(type_err.clone());
+
+        assert_eq!(err, CompileError::Type(type_err.clone()));
+        assert_eq!(err.to_string(), format!("type: {}", type_err));
+    }
+
+    #[test]
+    fn test_from_codegen_error() {
+        let codegen_err = CodegenError::JumpOutsideLoop;
+        let err = CompileError::from(codegen_err.clone());
+
+        assert_eq!(err, CompileError::Codegen(codegen_err.clone()));
+        assert_eq!(err.to_string(), format!("codegen: {}", codegen_err));
+    }
+
+    #[test]
+    fn test_question_mark_conversion() {
+        fn lex() -> Result<(), CompileError> {
+            Err(LexicalError {
+                error: LexicalErrorType::EmptyCharLiteral,
+                location: SrcSpan { start: 0, end: 2 },
+            })?
+        }
+
+        assert!(matches!(lex(), Err(CompileError::Lex(_))));
+    }
+}
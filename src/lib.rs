@@ -0,0 +1,13 @@
+mod compile;
+mod error;
+mod lower;
+mod parse;
+
+pub use compile::CompileOptions;
+pub use compile::compile_str;
+pub use error::CompileError;
+pub use lower::LowerConfig;
+pub use lower::LowerError;
+pub use lower::lower_program;
+pub use lower::lower_program_with_config;
+pub use parse::parse_type_str;
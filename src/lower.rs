@@ -0,0 +1,960 @@
+//! Lowering of the parser's AST into the IR.
+//!
+//! Lowering is purely syntactic: names are not resolved and types are not
+//! checked here, that is left to `shizuku_ir::check_program`.
+
+use shizuku_ir::BinOp;
+use shizuku_ir::Constant;
+use shizuku_ir::Expr;
+use shizuku_ir::Function;
+use shizuku_ir::InlineHint;
+use shizuku_ir::Intrinsic;
+use shizuku_ir::Mutability;
+use shizuku_ir::Program;
+use shizuku_ir::Stmt;
+use shizuku_ir::Symbol;
+use shizuku_ir::TempAllocator;
+use shizuku_ir::Type;
+use shizuku_ir::UnOp;
+use shizuku_parser::ASTNode;
+use shizuku_parser::Token;
+use shizuku_parser::ast;
+use shizuku_parser::ast::Literal;
+use std::cell::RefCell;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LowerError {
+    /// A type annotation that names no known type.
+    UnknownType(String),
+    /// A binary operator token with no IR counterpart.
+    InvalidOperator(Token),
+    /// A numeric literal that does not fit its type.
+    InvalidLiteral(String),
+    /// A global whose initializer is not a constant.
+    NonConstantGlobalInitializer(String),
+    /// A construct the IR cannot represent yet.
+    Unsupported(&'static str),
+    /// A `LowerConfig::default_int` other than an integer type.
+    InvalidDefaultInt(Type),
+}
+
+impl fmt::Display for LowerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LowerError::UnknownType(name) => write!(f, "unknown type `{}`", name),
+            LowerError::InvalidOperator(tok) => write!(f, "invalid binary operator {:?}", tok),
+            LowerError::InvalidLiteral(value) => write!(f, "invalid literal `{}`", value),
+            LowerError::NonConstantGlobalInitializer(name) => {
+                write!(f, "initializer of global `{}` is not a constant", name)
+            }
+            LowerError::Unsupported(what) => write!(f, "{} is not supported yet", what),
+            LowerError::InvalidDefaultInt(ty) => {
+                write!(f, "default integer type `{}` is not an integer type", ty)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LowerError {}
+
+/// Options for `lower_program_with_config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LowerConfig {
+    /// The type of an integer literal whose context gives it none, one of
+    /// the integer types. It is recorded as `Program::default_int` for the
+    /// type checker to apply.
+    pub default_int: Type,
+}
+
+impl Default for LowerConfig {
+    fn default() -> Self {
+        Self {
+            default_int: Type::Int,
+        }
+    }
+}
+
+/// Lowers a parsed program into the IR.
+///
+/// A `let` without a type annotation is lowered with `Type::Infer`.
+pub fn lower_program(nodes: &[ASTNode]) -> Result<Program, LowerError> {
+    lower_program_with_config(nodes, &LowerConfig::default())
+}
+
+/// Lowers `nodes` like `lower_program`, under non-default options.
+pub fn lower_program_with_config(
+    nodes: &[ASTNode],
+    config: &LowerConfig,
+) -> Result<Program, LowerError> {
+    if !config.default_int.is_integer() {
+        return Err(LowerError::InvalidDefaultInt(config.default_int.clone()));
+    }
+    let mut program = Program::new();
+    program.default_int = config.default_int.clone();
+
+    let cx = &Context {
+        functions: nodes
+            .iter()
+            .filter_map(|node| match node.without_comments() {
+                ASTNode::Function { name, .. } => Some(&name[..]),
+                _ => None,
+            })
+            .collect(),
+        temps: RefCell::default(),
+    };
+
+    for node in nodes {
+        match node.without_comments() {
+            ASTNode::Function {
+                name,
+                type_params,
+                params,
+                return_type,
+                is_external,
+                is_variadic,
+                // Every function is visible outside its module already.
+                is_public: _,
+                decorators,
+                body,
+            } => {
+                if !type_params.is_empty() {
+                    return Err(LowerError::Unsupported("generic function"));
+                }
+                let inline_hint = lower_inline_hint(decorators)?;
+                // There is no `va_arg` to read the extra arguments with.
+                if *is_variadic && !*is_external {
+                    return Err(LowerError::Unsupported("variadic function definition"));
+                }
+                let params = params
+                    .iter()
+                    .map(|param| Ok((symbol(&param.name), lower_type(&param.param_type)?)))
+                    .collect::<Result<_, LowerError>>()?;
+                let return_type = match return_type {
+                    Some(ty) => lower_type(ty)?,
+                    None => Type::Void,
+                };
+                cx.temps.replace(TempAllocator::new());
+                let mut stmts = body
+                    .iter()
+                    .map(|node| lower_stmt(node, cx))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // The tail expression of a function body is its return value.
+                if let Some(ASTNode::Tail(_)) = body.last().map(ASTNode::without_comments)
+                    && let Some(Stmt::Expr(value)) = stmts.pop()
+                {
+                    stmts.push(Stmt::Return(Some(value)));
+                }
+                program.functions.push(Function {
+                    name: symbol(name),
+                    params,
+                    return_type,
+                    is_external: *is_external,
+                    is_variadic: *is_variadic,
+                    inline_hint,
+                    body: Stmt::Block(stmts),
+                });
+            }
+            ASTNode::GlobalVariable {
+                name,
+                var_type,
+                value,
+            } => {
+                let value = match value {
+                    Some(value) => Some(eval_const(&lower_expr(value, cx)?).ok_or_else(|| {
+                        LowerError::NonConstantGlobalInitializer(name.to_string())
+                    })?),
+                    None => None,
+                };
+                program.globals.push((
+                    symbol(name),
+                    lower_type(var_type)?,
+                    value,
+                    Mutability::Mutable,
+                ));
+            }
+            // Struct types are structural in the IR, a declaration emits nothing.
+            ASTNode::Struct { .. } => {}
+            _ => return Err(LowerError::Unsupported("top-level statement")),
+        }
+    }
+
+    Ok(program)
+}
+
+/// What lowering a function body needs to know about the rest of the program.
+struct Context<'a> {
+    /// Names of every function the program defines.
+    functions: Vec<&'a str>,
+    /// Temporaries of the function being lowered.
+    temps: RefCell<TempAllocator>,
+}
+
+impl Context<'_> {
+    /// The symbol a call to `name` refers to: a built-in like `assert`
+    /// unless the program defines its own function of that name.
+    fn callee(&self, name: &str) -> Symbol {
+        match Intrinsic::from_name(name) {
+            Some(intrinsic) if !self.functions.contains(&name) => intrinsic.symbol(),
+            _ => symbol(name),
+        }
+    }
+}
+
+fn symbol(name: &str) -> Symbol {
+    Symbol(name.to_string())
+}
+
+/// Maps `@inline` and `@inline(never)` to the function's inline hint.
+fn lower_inline_hint(decorators: &[ast::Decorator]) -> Result<InlineHint, LowerError> {
+    let mut hint = InlineHint::Default;
+    for decorator in decorators {
+        hint = match (&decorator.name[..], decorator.args.as_slice()) {
+            ("inline", []) => InlineHint::Always,
+            ("inline", [ASTNode::Variable { name, .. }]) if name == "never" => InlineHint::Never,
+            _ => return Err(LowerError::Unsupported("function decorator")),
+        };
+    }
+    Ok(hint)
+}
+
+fn lower_type(ty: &ast::Type) -> Result<Type, LowerError> {
+    let name = match ty {
+        ast::Type::Named { name } => name,
+        ast::Type::Function {
+            params,
+            return_type,
+        } => {
+            let params = params.iter().map(lower_type).collect::<Result<_, _>>()?;
+            return Ok(Type::Function(
+                params,
+                Box::new(lower_type(return_type)?),
+                false,
+            ));
+        }
+        ast::Type::Array { element, size } => {
+            let ASTNode::Literal(literal @ Literal::Int { .. }) = &**size else {
+                return Err(LowerError::Unsupported("non-literal array size"));
+            };
+            let Constant::Int(size) = lower_literal(literal)? else {
+                unreachable!("an integer literal lowers to `Constant::Int`")
+            };
+            let size =
+                usize::try_from(size).map_err(|_| LowerError::InvalidLiteral(size.to_string()))?;
+            return Ok(Type::Array(Box::new(lower_type(element)?), size));
+        }
+        ast::Type::Pointer { .. } => return Err(LowerError::Unsupported("pointer type")),
+    };
+    match &name[..] {
+        "int" | "i64" => Ok(Type::Int),
+        "i32" => Ok(Type::Int32),
+        "i16" => Ok(Type::Int16),
+        "i8" => Ok(Type::Int8),
+        "float" | "f64" => Ok(Type::Float),
+        "bool" => Ok(Type::Bool),
+        "string" => Ok(Type::String),
+        "void" => Ok(Type::Void),
+        name => Err(LowerError::UnknownType(name.to_string())),
+    }
+}
+
+fn lower_block(nodes: &[ASTNode], cx: &Context) -> Result<Stmt, LowerError> {
+    let stmts = nodes
+        .iter()
+        .map(|node| lower_stmt(node, cx))
+        .collect::<Result<_, _>>()?;
+    Ok(Stmt::Block(stmts))
+}
+
+fn lower_stmt(node: &ASTNode, cx: &Context) -> Result<Stmt, LowerError> {
+    match node {
+        ASTNode::Variable {
+            name,
+            var_type,
+            value,
+        } => {
+            let ty = match var_type {
+                Some(ty) => lower_type(ty)?,
+                None => Type::Infer,
+            };
+            let init = value
+                .as_deref()
+                .map(|node| lower_expr(node, cx))
+                .transpose()?;
+            Ok(Stmt::Declare(symbol(name), ty, init))
+        }
+        ASTNode::Return { value } => Ok(Stmt::Return(
+            value
+                .as_deref()
+                .map(|node| lower_expr(node, cx))
+                .transpose()?,
+        )),
+        ASTNode::Assignment { target, value } => Ok(Stmt::Assign(
+            lower_expr(target, cx)?,
+            lower_expr(value, cx)?,
+        )),
+        ASTNode::ExpressionStatement(expr) | ASTNode::Tail(expr) => {
+            Ok(Stmt::Expr(lower_expr(expr, cx)?))
+        }
+        ASTNode::Commented { node, .. } => lower_stmt(node, cx),
+        ASTNode::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => Ok(Stmt::If(
+            lower_expr(condition, cx)?,
+            Box::new(lower_block(then_branch, cx)?),
+            else_branch
+                .as_deref()
+                .map(|body| lower_block(body, cx))
+                .transpose()?
+                .map(Box::new),
+        )),
+        ASTNode::While {
+            label,
+            condition,
+            body,
+        } => Ok(Stmt::While(
+            lower_expr(condition, cx)?,
+            Box::new(lower_block(body, cx)?),
+            label.as_deref().map(symbol),
+        )),
+        ASTNode::For { .. } => Err(LowerError::Unsupported("for loop")),
+        // `do body while cond;` runs as `let first = true; while first or cond
+        // { first = false; body }`, so a `continue` still checks `cond`.
+        ASTNode::DoWhile { body, condition } => {
+            let first = cx.temps.borrow_mut().fresh();
+            let flag = |value| Expr::Const(Constant::Bool(value));
+            Ok(Stmt::Block(vec![
+                Stmt::Declare(first.clone(), Type::Bool, Some(flag(true))),
+                Stmt::While(
+                    Expr::BinOp(
+                        BinOp::Or,
+                        Box::new(Expr::Var(first.clone())),
+                        Box::new(lower_expr(condition, cx)?),
+                    ),
+                    Box::new(Stmt::Block(vec![
+                        Stmt::Assign(Expr::Var(first), flag(false)),
+                        lower_block(body, cx)?,
+                    ])),
+                    None,
+                ),
+            ]))
+        }
+        ASTNode::Break { label } => Ok(Stmt::Break(label.as_deref().map(symbol))),
+        ASTNode::Continue { label } => Ok(Stmt::Continue(label.as_deref().map(symbol))),
+        ASTNode::Function { .. } => Err(LowerError::Unsupported("nested function")),
+        ASTNode::Struct { .. } | ASTNode::GlobalVariable { .. } => {
+            Err(LowerError::Unsupported("local item declaration"))
+        }
+        expr => Ok(Stmt::Expr(lower_expr(expr, cx)?)),
+    }
+}
+
+/// Evaluates an initializer built from literals and operators, or returns
+/// `None` if it reads a variable, calls a function or an operation fails.
+fn eval_const(expr: &Expr) -> Option<Constant> {
+    match expr {
+        Expr::Const(value) => Some(value.clone()),
+        Expr::BinOp(op, lhs, rhs) => eval_const(lhs)?.binop(*op, &eval_const(rhs)?).ok(),
+        Expr::UnOp(op, operand) => eval_const(operand)?.unop(*op).ok(),
+        // Constants carry no width, only an integer literal is cast.
+        Expr::Cast(value, _) => eval_const(value),
+        Expr::If(cond, then_value, else_value) => match eval_const(cond)? {
+            Constant::Bool(true) => eval_const(then_value),
+            Constant::Bool(false) => eval_const(else_value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn lower_expr(node: &ASTNode, cx: &Context) -> Result<Expr, LowerError> {
+    match node {
+        ASTNode::Variable { name, .. } => Ok(Expr::Var(symbol(name))),
+        ASTNode::Literal(literal) => Ok(Expr::Const(lower_literal(literal)?)),
+        ASTNode::Paren(inner) => lower_expr(inner, cx),
+        ASTNode::BinaryOp {
+            left,
+            operator,
+            right,
+        } => Ok(Expr::BinOp(
+            lower_binop(operator)?,
+            Box::new(lower_expr(left, cx)?),
+            Box::new(lower_expr(right, cx)?),
+        )),
+        ASTNode::FunctionCall { name, arguments } => Ok(Expr::Call(
+            cx.callee(name),
+            arguments
+                .iter()
+                .map(|node| lower_expr(node, cx))
+                .collect::<Result<_, _>>()?,
+        )),
+        ASTNode::FieldAccess { object, field } => Ok(Expr::FieldAccess(
+            Box::new(lower_expr(object, cx)?),
+            symbol(field),
+        )),
+        ASTNode::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => Ok(Expr::If(
+            Box::new(lower_expr(condition, cx)?),
+            Box::new(lower_expr(then_branch, cx)?),
+            Box::new(lower_expr(else_branch, cx)?),
+        )),
+        ASTNode::If {
+            condition,
+            then_branch,
+            else_branch: Some(else_branch),
+        } => Ok(Expr::If(
+            Box::new(lower_expr(condition, cx)?),
+            Box::new(lower_branch(then_branch, cx)?),
+            Box::new(lower_branch(else_branch, cx)?),
+        )),
+        ASTNode::If { .. } => Err(LowerError::Unsupported("if expression without else")),
+        ASTNode::UnaryOp {
+            operator: Token::Exclamation,
+            operand,
+        } => Ok(Expr::UnOp(UnOp::Not, Box::new(lower_expr(operand, cx)?))),
+        ASTNode::UnaryOp { .. } => Err(LowerError::Unsupported("unary operator")),
+        ASTNode::PointerDereference { .. } => Err(LowerError::Unsupported("pointer dereference")),
+        // Nothing the IR has yet can hold the missing value `?` propagates.
+        ASTNode::Try { .. } => Err(LowerError::Unsupported("`?` operator")),
+        _ => Err(LowerError::Unsupported("statement in expression position")),
+    }
+}
+
+/// Lowers a branch of an if-expression, which the IR only has for a single
+/// tail expression or a chained `else if`.
+fn lower_branch(body: &[ASTNode], cx: &Context) -> Result<Expr, LowerError> {
+    match body {
+        [ASTNode::Tail(expr)] => lower_expr(expr, cx),
+        [chained @ ASTNode::If { .. }] => lower_expr(chained, cx),
+        _ => Err(LowerError::Unsupported("statement in if expression")),
+    }
+}
+
+fn lower_literal(literal: &Literal) -> Result<Constant, LowerError> {
+    match literal {
+        Literal::Int { base, value } => Token::Int {
+            base: *base,
+            value: value.clone(),
+        }
+        .as_i64()
+        .map(Constant::Int)
+        .ok_or_else(|| LowerError::InvalidLiteral(value.to_string())),
+        Literal::Float { value } => value[..]
+            .replace('_', "")
+            .parse()
+            .map(Constant::Float)
+            .map_err(|_| LowerError::InvalidLiteral(value.to_string())),
+        Literal::String { value } => Ok(Constant::String(value.to_string())),
+        Literal::Char { .. } => Err(LowerError::Unsupported("char literal")),
+    }
+}
+
+/// The operator a binary operator token stands for.
+///
+/// This is a function rather than `impl TryFrom<&Token> for BinOp`: both
+/// types are foreign to this crate, so the orphan rule forbids that impl.
+fn lower_binop(tok: &Token) -> Result<BinOp, LowerError> {
+    match tok {
+        Token::Plus => Ok(BinOp::Add),
+        Token::Minus => Ok(BinOp::Sub),
+        Token::Asterisk => Ok(BinOp::Mul),
+        Token::Slash => Ok(BinOp::Div),
+        Token::Percent => Ok(BinOp::Mod),
+        Token::Equal2 => Ok(BinOp::Eq),
+        Token::ExclamationEqual => Ok(BinOp::Neq),
+        Token::LArrow => Ok(BinOp::Lt),
+        Token::RArrow => Ok(BinOp::Gt),
+        Token::LArrowEqual => Ok(BinOp::Leq),
+        Token::RArrowEqual => Ok(BinOp::Geq),
+        Token::And => Ok(BinOp::And),
+        Token::Or => Ok(BinOp::Or),
+        tok => Err(LowerError::InvalidOperator(tok.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shizuku_parser::Lexer;
+    use shizuku_parser::NumberBase;
+    use shizuku_parser::Parser;
+
+    fn parse(source: &str) -> Vec<ASTNode> {
+        let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+        let mut lexer = Lexer::new(chars);
+        let mut tokens = vec![];
+        loop {
+            let token = lexer.next().expect("lex error");
+            if token.1 == Token::EOF {
+                break;
+            }
+            tokens.push(token);
+        }
+        Parser::new(tokens.into_iter()).parse_program().unwrap()
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(symbol(name))
+    }
+
+    #[test]
+    fn test_lower_let_without_annotation() {
+        let program = lower_program(&parse("fn f(a: int) { let b: int = a; let c = b; }")).unwrap();
+
+        assert_eq!(
+            program.functions[0].body,
+            Stmt::Block(vec![
+                Stmt::Declare(symbol("b"), Type::Int, Some(var("a"))),
+                Stmt::Declare(symbol("c"), Type::Infer, Some(var("b"))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_lower_drops_parentheses() {
+        let program = lower_program(&parse("fn f(a: int) { let b: int = (a); }")).unwrap();
+
+        assert_eq!(
+            program.functions[0].body,
+            Stmt::Block(vec![Stmt::Declare(symbol("b"), Type::Int, Some(var("a")))])
+        );
+    }
+
+    #[test]
+    fn test_lowered_let_is_inferred() {
+        let source = "fn f(a: i32) -> i32 { let b = a + a; return b; }";
+        let mut program = lower_program(&parse(source)).unwrap();
+        shizuku_ir::check_program(&mut program).unwrap();
+
+        let Stmt::Block(body) = &program.functions[0].body else {
+            unreachable!()
+        };
+        assert!(matches!(&body[0], Stmt::Declare(_, Type::Int32, _)));
+    }
+
+    #[test]
+    fn test_lower_extern_function() {
+        let source = "extern fn puts(s: string) -> i32; fn f() { puts(\"hi\"); }";
+        let program = lower_program(&parse(source)).unwrap();
+
+        assert_eq!(
+            program.functions[0],
+            Function {
+                name: symbol("puts"),
+                params: vec![(symbol("s"), Type::String)],
+                return_type: Type::Int32,
+                is_external: true,
+                is_variadic: false,
+                inline_hint: InlineHint::Default,
+                body: Stmt::Block(vec![]),
+            }
+        );
+        assert!(!program.functions[1].is_external);
+    }
+
+    #[test]
+    fn test_lower_variadic_function() {
+        let program = lower_program(&parse("extern fn printf(fmt: string, ...) -> i32;")).unwrap();
+        assert!(program.functions[0].is_variadic);
+
+        assert_eq!(
+            lower_program(&parse("fn f(a: int, ...) {}")),
+            Err(LowerError::Unsupported("variadic function definition"))
+        );
+    }
+
+    #[test]
+    fn test_lower_ignores_comments() {
+        let source = "// Doubles.\nfn f(a: int) -> int {\n    // Tail.\n    a + a\n}";
+        let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+        let nodes = Parser::from_lexer(Lexer::new(chars))
+            .preserve_comments(true)
+            .parse_program()
+            .unwrap();
+        assert!(matches!(nodes[0], ASTNode::Commented { .. }));
+
+        assert_eq!(lower_program(&nodes), lower_program(&parse(source)));
+    }
+
+    #[test]
+    fn test_lower_if_expression() {
+        let int = |value| Box::new(Expr::Const(Constant::Int(value)));
+        let source = "fn f(c: bool, d: bool) { let x = if c { 1 } else if d { 2 } else { 3 }; }";
+        let program = lower_program(&parse(source)).unwrap();
+        let Stmt::Block(body) = &program.functions[0].body else {
+            unreachable!()
+        };
+        assert_eq!(
+            body[0],
+            Stmt::Declare(
+                symbol("x"),
+                Type::Infer,
+                Some(Expr::If(
+                    Box::new(Expr::Var(symbol("c"))),
+                    int(1),
+                    Box::new(Expr::If(Box::new(Expr::Var(symbol("d"))), int(2), int(3))),
+                )),
+            )
+        );
+
+        assert_eq!(
+            lower_program(&parse("fn f(c: bool) { let x = if c { 1 }; }")),
+            Err(LowerError::Unsupported("if expression without else"))
+        );
+    }
+
+    #[test]
+    fn test_lower_logical_not() {
+        let source = "fn f(done: bool, a: int, b: int) { let x = !done; let y = !(a == b); }";
+        let program = lower_program(&parse(source)).unwrap();
+        let Stmt::Block(body) = &program.functions[0].body else {
+            unreachable!()
+        };
+        let not = |operand| Some(Expr::UnOp(UnOp::Not, Box::new(operand)));
+        assert_eq!(
+            body[..],
+            [
+                Stmt::Declare(symbol("x"), Type::Infer, not(var("done"))),
+                Stmt::Declare(
+                    symbol("y"),
+                    Type::Infer,
+                    not(Expr::BinOp(
+                        BinOp::Eq,
+                        Box::new(var("a")),
+                        Box::new(var("b"))
+                    )),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lower_int_literal() {
+        let int = |base, value: &str| Literal::Int {
+            base,
+            value: value.into(),
+        };
+
+        assert_eq!(
+            lower_literal(&int(NumberBase::Hexadecimal, "0x1F")),
+            Ok(Constant::Int(31))
+        );
+        // Built by hand without the prefix the base calls for.
+        assert_eq!(
+            lower_literal(&int(NumberBase::Hexadecimal, "1")),
+            Err(LowerError::InvalidLiteral("1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_lower_binop() {
+        let operators = [
+            (Token::Plus, BinOp::Add),
+            (Token::Minus, BinOp::Sub),
+            (Token::Asterisk, BinOp::Mul),
+            (Token::Slash, BinOp::Div),
+            (Token::Percent, BinOp::Mod),
+            (Token::Equal2, BinOp::Eq),
+            (Token::ExclamationEqual, BinOp::Neq),
+            (Token::LArrow, BinOp::Lt),
+            (Token::RArrow, BinOp::Gt),
+            (Token::LArrowEqual, BinOp::Leq),
+            (Token::RArrowEqual, BinOp::Geq),
+            (Token::And, BinOp::And),
+            (Token::Or, BinOp::Or),
+        ];
+        for (tok, op) in operators {
+            assert_eq!(lower_binop(&tok), Ok(op));
+        }
+
+        assert_eq!(
+            lower_binop(&Token::LParen),
+            Err(LowerError::InvalidOperator(Token::LParen))
+        );
+    }
+
+    #[test]
+    fn test_lower_try() {
+        assert_eq!(
+            lower_program(&parse("fn f() { let x = g()?; }")),
+            Err(LowerError::Unsupported("`?` operator"))
+        );
+    }
+
+    #[test]
+    fn test_lower_decorated_function() {
+        assert_eq!(
+            lower_program(&parse("@memoize fn f() {}")),
+            Err(LowerError::Unsupported("function decorator"))
+        );
+    }
+
+    #[test]
+    fn test_lower_inline_decorator() {
+        let program = lower_program(&parse(
+            "@inline fn f() {} @inline(never) fn g() {} fn h() {}",
+        ))
+        .unwrap();
+
+        let hints: Vec<_> = program.functions.iter().map(|f| f.inline_hint).collect();
+        assert_eq!(
+            hints,
+            [InlineHint::Always, InlineHint::Never, InlineHint::Default]
+        );
+        assert_eq!(
+            lower_program(&parse("@inline(always) fn f() {}")),
+            Err(LowerError::Unsupported("function decorator"))
+        );
+    }
+
+    #[test]
+    fn test_lower_unknown_type() {
+        assert_eq!(
+            lower_program(&parse("fn f(a: foo) {}")),
+            Err(LowerError::UnknownType("foo".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_lower_function_type() {
+        let program = lower_program(&parse("fn f(g: fn(int) -> bool) {}")).unwrap();
+
+        assert_eq!(
+            program.functions[0].params,
+            vec![(
+                symbol("g"),
+                Type::Function(vec![Type::Int], Box::new(Type::Bool), false)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_lower_array_type() {
+        let program = lower_program(&parse("fn f(a: [int; 4], p: *int) {}"));
+        assert_eq!(program, Err(LowerError::Unsupported("pointer type")));
+
+        let program = lower_program(&parse("fn f(a: [[bool; 2]; 0x10]) {}")).unwrap();
+        assert_eq!(
+            program.functions[0].params,
+            vec![(
+                symbol("a"),
+                Type::Array(Box::new(Type::Array(Box::new(Type::Bool), 2)), 16)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_lower_assert_intrinsic() {
+        let program = lower_program(&parse("fn f(x: int) { assert(x < 10); }")).unwrap();
+
+        let Stmt::Block(body) = &program.functions[0].body else {
+            unreachable!()
+        };
+        assert!(matches!(
+            &body[0],
+            Stmt::Expr(Expr::Call(callee, _)) if *callee == Intrinsic::Assert.symbol()
+        ));
+    }
+
+    #[test]
+    fn test_user_function_overrides_intrinsic() {
+        let source = "fn assert(x: int) {} fn f() { assert(1); }";
+        let program = lower_program(&parse(source)).unwrap();
+
+        let Stmt::Block(body) = &program.functions[1].body else {
+            unreachable!()
+        };
+        assert!(matches!(
+            &body[0],
+            Stmt::Expr(Expr::Call(callee, _)) if *callee == symbol("assert")
+        ));
+    }
+
+    /// `name: int = init` at the top level, which the parser does not read.
+    fn global(name: &str, init: &str) -> ASTNode {
+        let ASTNode::Variable {
+            var_type: Some(var_type),
+            value,
+            ..
+        } = parse(&format!("let {name}: int = {init};")).remove(0)
+        else {
+            unreachable!()
+        };
+        ASTNode::GlobalVariable {
+            name: name.into(),
+            var_type,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_lower_global_initializer() {
+        let program = lower_program(&[global("X", "2 + 3")]).unwrap();
+        assert_eq!(
+            program.globals,
+            vec![(
+                symbol("X"),
+                Type::Int,
+                Some(Constant::Int(5)),
+                Mutability::Mutable
+            )]
+        );
+
+        assert_eq!(
+            lower_program(&[global("Y", "foo()")]),
+            Err(LowerError::NonConstantGlobalInitializer("Y".to_string()))
+        );
+        assert_eq!(
+            lower_program(&[global("Z", "X + 1")]),
+            Err(LowerError::NonConstantGlobalInitializer("Z".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_lower_default_int() {
+        let ast = parse("fn f() { let x = 42; }");
+        let declared_type = |config: &LowerConfig| {
+            let mut program = lower_program_with_config(&ast, config).unwrap();
+            shizuku_ir::check_program(&mut program).unwrap();
+            let Stmt::Block(body) = &program.functions[0].body else {
+                unreachable!()
+            };
+            let Stmt::Declare(_, ty, _) = &body[0] else {
+                unreachable!()
+            };
+            ty.clone()
+        };
+
+        assert_eq!(declared_type(&LowerConfig::default()), Type::Int);
+        let config = LowerConfig {
+            default_int: Type::Int32,
+        };
+        assert_eq!(declared_type(&config), Type::Int32);
+
+        let program = lower_program_with_config(&ast, &config).unwrap();
+        let Stmt::Block(body) = &program.functions[0].body else {
+            unreachable!()
+        };
+        assert_eq!(program.default_int, Type::Int32);
+        assert_eq!(
+            body[0],
+            Stmt::Declare(
+                symbol("x"),
+                Type::Infer,
+                Some(Expr::Const(Constant::Int(42)))
+            )
+        );
+    }
+
+    #[test]
+    fn test_default_int_takes_expected_type() {
+        let ast = parse("fn f() -> int { let x: int = 1; return x + 2; }");
+        let config = LowerConfig {
+            default_int: Type::Int32,
+        };
+
+        let mut program = lower_program_with_config(&ast, &config).unwrap();
+        assert_eq!(shizuku_ir::check_program(&mut program), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_non_integer_default_int() {
+        let config = LowerConfig {
+            default_int: Type::Float,
+        };
+
+        assert_eq!(
+            lower_program_with_config(&[], &config),
+            Err(LowerError::InvalidDefaultInt(Type::Float))
+        );
+    }
+
+    /// `fn name(c: bool) { do { step(); } while c; ... }` with `loops`
+    /// loops, built by hand since the parser has no `do` loops.
+    fn do_while_function(name: &str, loops: usize) -> ASTNode {
+        let ASTNode::Function {
+            params,
+            return_type,
+            ..
+        } = parse(&format!("fn {name}(c: bool) {{}}")).remove(0)
+        else {
+            unreachable!()
+        };
+        ASTNode::Function {
+            name: name.into(),
+            type_params: vec![],
+            params,
+            return_type,
+            is_external: false,
+            is_variadic: false,
+            is_public: false,
+            decorators: vec![],
+            body: (0..loops)
+                .map(|_| ASTNode::DoWhile {
+                    body: vec![ASTNode::ExpressionStatement(Box::new(
+                        ASTNode::FunctionCall {
+                            name: "step".into(),
+                            arguments: vec![],
+                        },
+                    ))],
+                    condition: Box::new(ASTNode::Variable {
+                        name: "c".into(),
+                        var_type: None,
+                        value: None,
+                    }),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_lower_do_while() {
+        let program = lower_program(&[do_while_function("f", 1)]).unwrap();
+
+        let flag = |value| Expr::Const(Constant::Bool(value));
+        assert_eq!(
+            program.functions[0].body,
+            Stmt::Block(vec![Stmt::Block(vec![
+                Stmt::Declare(symbol("__tmp0"), Type::Bool, Some(flag(true))),
+                Stmt::While(
+                    Expr::BinOp(BinOp::Or, Box::new(var("__tmp0")), Box::new(var("c"))),
+                    Box::new(Stmt::Block(vec![
+                        Stmt::Assign(var("__tmp0"), flag(false)),
+                        Stmt::Block(vec![Stmt::Expr(Expr::Call(symbol("step"), vec![]))]),
+                    ])),
+                    None,
+                ),
+            ])])
+        );
+    }
+
+    #[test]
+    fn test_lower_temp_names_are_deterministic() {
+        let nodes = [do_while_function("f", 2), do_while_function("g", 1)];
+        let program = lower_program(&nodes).unwrap();
+        assert_eq!(lower_program(&nodes).unwrap(), program);
+
+        // Every function numbers its temporaries from zero.
+        let declared = |function: &Function| {
+            let Stmt::Block(body) = &function.body else {
+                unreachable!()
+            };
+            body.iter()
+                .map(|stmt| match stmt {
+                    Stmt::Block(desugared) => match &desugared[0] {
+                        Stmt::Declare(temp, _, _) => temp.0.clone(),
+                        _ => unreachable!(),
+                    },
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(declared(&program.functions[0]), ["__tmp0", "__tmp1"]);
+        assert_eq!(declared(&program.functions[1]), ["__tmp0"]);
+    }
+}
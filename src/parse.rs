@@ -0,0 +1,84 @@
+use crate::CompileError;
+use shizuku_parser::Lexer;
+use shizuku_parser::Parser;
+use shizuku_parser::ast::Type;
+
+/// Lexes and parses `source` as a single type annotation, such as
+/// `fn(int) -> bool`. Tokens left after the type are an error.
+pub fn parse_type_str(source: &str) -> Result<Type, CompileError> {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut parser = Parser::from_lexer(Lexer::new(chars));
+    parser
+        .parse_type_annotation()
+        .map_err(|err| CompileError::from_parse(err, parser.lex_error()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shizuku_parser::ASTNode;
+    use shizuku_parser::NumberBase;
+    use shizuku_parser::ParseErrorType;
+    use shizuku_parser::Token;
+    use shizuku_parser::ast::Literal;
+
+    fn named(name: &str) -> Type {
+        Type::Named { name: name.into() }
+    }
+
+    #[test]
+    fn test_parse_array_type() {
+        assert_eq!(
+            parse_type_str("[i32; 4]"),
+            Ok(Type::Array {
+                element: Box::new(named("i32")),
+                size: Box::new(ASTNode::Literal(Literal::Int {
+                    base: NumberBase::Decimal,
+                    value: "4".into(),
+                })),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_pointer_type() {
+        assert_eq!(
+            parse_type_str("*MyStruct"),
+            Ok(Type::Pointer {
+                pointee: Box::new(named("MyStruct")),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_function_type() {
+        assert_eq!(
+            parse_type_str("fn(int) -> bool"),
+            Ok(Type::Function {
+                params: vec![named("int")],
+                return_type: Box::new(named("bool")),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_type_with_leftover_tokens() {
+        let Err(CompileError::Parse(err)) = parse_type_str("int bool") else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            err.error,
+            ParseErrorType::ExpectedToken {
+                expected: Token::EOF,
+                found: Token::Ident {
+                    name: "bool".into()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_type_lex_error() {
+        assert!(matches!(parse_type_str("$"), Err(CompileError::Lex(_))));
+    }
+}
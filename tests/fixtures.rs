@@ -0,0 +1,54 @@
+//! Golden tests: each `tests/fixtures/*.shz` file is compiled with
+//! `compile_str` and its IR compared with the `.ir` file next to it.
+//!
+//! Run with `SHIZUKU_BLESS=1` to write the current output to the `.ir`
+//! files instead, then review the diff.
+
+use shizuku::CompileOptions;
+use shizuku::compile_str;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let bless = std::env::var_os("SHIZUKU_BLESS").is_some();
+
+    let mut sources: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "shz"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty(), "no fixtures in {}", dir.display());
+
+    let mut failures = vec![];
+    for source_path in &sources {
+        let source = fs::read_to_string(source_path).unwrap();
+        let actual = match compile_str(&source, &CompileOptions::default()) {
+            Ok((program, _)) => program.to_string(),
+            Err(err) => panic!("{}: {}", source_path.display(), err),
+        };
+
+        let golden_path = source_path.with_extension("ir");
+        if bless {
+            fs::write(&golden_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden_path).unwrap_or_default();
+        if actual != expected {
+            failures.push(format!(
+                "{}:\n--- expected\n{}--- actual\n{}",
+                golden_path.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "IR differs from the golden files, rerun with SHIZUKU_BLESS=1 to update them\n\n{}",
+        failures.join("\n")
+    );
+}
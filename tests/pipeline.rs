@@ -0,0 +1,94 @@
+use shizuku::lower_program;
+use shizuku_codegen::codegen_program;
+use shizuku_ir::Program;
+use shizuku_ir::opt::cse;
+use shizuku_parser::Lexer;
+use shizuku_parser::Parser;
+use shizuku_parser::Token;
+
+/// Lexes, parses and lowers `source`.
+fn lower(source: &str) -> Program {
+    let chars = source.char_indices().map(|(i, c)| (i as u32, c));
+    let mut lexer = Lexer::new(chars);
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.next().expect("lex error");
+        if token.1 == Token::EOF {
+            break;
+        }
+        tokens.push(token);
+    }
+
+    let ast = Parser::new(tokens.into_iter()).parse_program().unwrap();
+    lower_program(&ast).unwrap()
+}
+
+/// Type checks `program` and returns its textual LLVM IR.
+fn emit(mut program: Program) -> String {
+    shizuku_ir::check_program(&mut program).unwrap();
+    codegen_program(&program).unwrap().to_llvm_ir_string()
+}
+
+/// Runs `source` through every phase and returns the textual LLVM IR.
+fn compile(source: &str) -> String {
+    emit(lower(source))
+}
+
+#[test]
+fn test_int_modulo() {
+    let ir = compile("fn rem(a: int, b: int) -> int { return a % b; }");
+    assert!(ir.contains("srem i64"), "{ir}");
+}
+
+#[test]
+fn test_float_modulo() {
+    let ir = compile("fn rem(a: float, b: float) -> float { return a % b; }");
+    assert!(ir.contains("frem double"), "{ir}");
+}
+
+#[test]
+fn test_modulo_binds_like_multiplication() {
+    let ir = compile("fn f(a: int, b: int, c: int) -> int { return a + b % c; }");
+    let rem = ir.find("srem").expect("missing srem");
+    let add = ir.find("add i64").expect("missing add");
+    assert!(rem < add, "{ir}");
+}
+
+#[test]
+fn test_inline_decorator() {
+    let ir = compile("@inline fn f() {} @inline(never) fn g() {}");
+    assert!(ir.contains("alwaysinline"), "{ir}");
+    assert!(ir.contains("noinline"), "{ir}");
+}
+
+#[test]
+fn test_cse_shares_repeated_product() {
+    let mut program = lower("fn f(a: int, b: int) -> int { return a * b + a * b; }");
+    cse(&mut program.functions[0]);
+
+    let ir = emit(program);
+    assert_eq!(ir.matches("mul i64").count(), 1, "{ir}");
+}
+
+#[test]
+fn test_tail_expression_is_returned() {
+    let ir = compile("fn f() -> int { let a = 41; a + 1 }");
+    assert!(ir.contains("ret i64 %add"), "{ir}");
+}
+
+#[test]
+fn test_assert_traps() {
+    let ir = compile("fn f(x: int) { assert(x < 10); }");
+    assert!(
+        ir.contains("br i1 %cmp, label %assert.ok, label %assert.fail"),
+        "{ir}"
+    );
+    assert!(ir.contains("call void @llvm.trap()"), "{ir}");
+}
+
+#[test]
+fn test_panic_aborts() {
+    let ir = compile("fn f() { panic(\"unreachable\"); }");
+    assert!(ir.contains("@puts("), "{ir}");
+    assert!(ir.contains("call void @abort()"), "{ir}");
+}